@@ -0,0 +1,33 @@
+// The boundary conditions and obstacle kinds shared by every shader resolving cells beyond the
+// edges of the grid, mirroring `Boundary` and `Wall` on the CPU.
+
+const BOUNDARY_FIXED: u32 = 0u;
+const BOUNDARY_FREE: u32 = 1u;
+const BOUNDARY_PERIODIC: u32 = 2u;
+const BOUNDARY_ABSORBING: u32 = 3u;
+const BOUNDARY_PML: u32 = 4u;
+
+// clamped obstacles need no special handling beyond holding their own cells at zero, while
+// reflecting obstacles additionally mirror the cells bordering them
+const OBSTACLE_OPEN: u32 = 0u;
+const OBSTACLE_CLAMPED: u32 = 1u;
+const OBSTACLE_REFLECTING: u32 = 2u;
+
+/// Maps a single coordinate lying beyond an edge of a `length` long axis back into the grid, or
+/// leaves it out of bounds if the cell is held at zero.
+fn resolve_ghost(x: i32, length: i32, boundary: u32) -> i32 {
+    switch boundary {
+        case BOUNDARY_PERIODIC: {
+            return (x + length) % length;
+        }
+        // absorbing edges only sample ghost cells at corners, where mirroring is a good enough
+        // approximation
+        case BOUNDARY_FREE, BOUNDARY_ABSORBING: {
+            return clamp(x, 0, length - 1);
+        }
+        // the outermost cells of a perfectly matched layer are held fixed
+        case BOUNDARY_FIXED, BOUNDARY_PML, default: {
+            return x;
+        }
+    }
+}
@@ -0,0 +1,50 @@
+/// The parameters of the sampled segment, mirroring `LineCutUniforms` on the CPU.
+struct LineCutParameters {
+    /// The grid coordinates the segment starts at.
+    start: vec2<f32>,
+    /// The grid coordinates the segment ends at.
+    end: vec2<f32>,
+    /// The number of evenly spaced samples taken along the segment.
+    sample_count: u32,
+    _padding: vec3<u32>,
+}
+
+@group(0) @binding(0)
+var<uniform> parameters: LineCutParameters;
+@group(0) @binding(1)
+var state: texture_2d<f32>;
+@group(0) @binding(2)
+var<storage, read_write> samples: array<f32>;
+
+@compute @workgroup_size(64, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= parameters.sample_count {
+        return;
+    }
+
+    let t = f32(id.x) / f32(max(parameters.sample_count - 1u, 1u));
+
+    samples[id.x] = sample_bilinear(mix(parameters.start, parameters.end, t));
+}
+
+/// Interpolates u(t) bilinearly between the four cells surrounding the given grid coordinates,
+/// clamping them into the grid.
+///
+/// The state texture is not filterable, so the interpolation is done by hand.
+fn sample_bilinear(position: vec2<f32>) -> f32 {
+    let size = vec2<f32>(textureDimensions(state));
+    let clamped = clamp(position, vec2<f32>(0.0), size - 1.0);
+
+    let base = vec2<i32>(floor(clamped));
+    let fraction = clamped - floor(clamped);
+    let last = vec2<i32>(size) - 1;
+
+    let next = min(base + 1, last);
+
+    let u00 = textureLoad(state, base, 0).r;
+    let u10 = textureLoad(state, vec2<i32>(next.x, base.y), 0).r;
+    let u01 = textureLoad(state, vec2<i32>(base.x, next.y), 0).r;
+    let u11 = textureLoad(state, next, 0).r;
+
+    return mix(mix(u00, u10, fraction.x), mix(u01, u11, fraction.x), fraction.y);
+}
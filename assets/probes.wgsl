@@ -0,0 +1,28 @@
+/// The parameters of a single recording, mirroring `ProbeUniforms` on the CPU.
+struct ProbeParameters {
+    /// The slot of the `samples` ring buffer the values of the tick are written to.
+    slot: u32,
+    /// The number of probes in the `probes` buffer.
+    probe_count: u32,
+    _padding: vec2<u32>,
+}
+
+// `MAX_PROBES` is defined by the preprocessor, mirroring the constant of the same name on the CPU
+
+@group(0) @binding(0)
+var<uniform> parameters: ProbeParameters;
+@group(0) @binding(1)
+var state: texture_2d<f32>;
+@group(0) @binding(2)
+var<storage, read> probes: array<vec2<u32>, MAX_PROBES>;
+@group(0) @binding(3)
+var<storage, read_write> samples: array<f32>;
+
+@compute @workgroup_size(MAX_PROBES, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= parameters.probe_count {
+        return;
+    }
+
+    samples[parameters.slot * MAX_PROBES + id.x] = textureLoad(state, probes[id.x], 0).r;
+}
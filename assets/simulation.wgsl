@@ -1,26 +1,450 @@
+/// The physical parameters of the simulation, mirroring `SimulationUniforms` on the CPU.
+struct SimulationParameters {
+    /// The duration of a single tick (dt).
+    dt: f32,
+    /// The distance between two adjacent cells of the grid (dx).
+    dx: f32,
+    /// The thickness of the perfectly matched layer (in cells).
+    pml_thickness: f32,
+    /// The damping coefficient at the outermost cell of the perfectly matched layer.
+    pml_max_damping: f32,
+    /// The boundary condition of each edge, in the order x_min, x_max, z_min, z_max.
+    boundaries: vec4<u32>,
+    /// The order of the polynomial damping profile of the perfectly matched layer.
+    pml_order: f32,
+    /// The simulation time at the start of the tick.
+    time: f32,
+    /// The number of sources in the `sources` buffer.
+    source_count: u32,
+    /// The number of `impulses` to apply during the tick.
+    impulse_count: u32,
+    /// The gaussian bumps to add on top of the wave during the tick, each packed as
+    /// (x, z, radius, amplitude).
+    impulses: array<vec4<f32>, MAX_IMPULSES>,
+}
+
+/// An emitter injecting waves as a forcing term, mirroring `GpuSource` on the CPU.
+struct Source {
+    /// The point, line start or ring center of the shape.
+    origin: vec2<f32>,
+    /// The line end of the shape, or the radius of a ring in the first component.
+    extent: vec2<f32>,
+    /// The kind of shape the source has.
+    shape: u32,
+    /// How the strength of the source varies over time.
+    waveform: u32,
+    /// The (central) frequency of the waveform (in Hz).
+    frequency: f32,
+    /// The strength of the source.
+    amplitude: f32,
+    /// The phase offset of the waveform (in radians).
+    phase: f32,
+    /// The simulation time the source turns on at.
+    start_time: f32,
+    /// The simulation time the source turns off at.
+    end_time: f32,
+}
+
+#include "boundaries.wgsl"
+
+const SHAPE_POINT: u32 = 0u;
+const SHAPE_LINE: u32 = 1u;
+const SHAPE_RING: u32 = 2u;
+
+const WAVEFORM_SINUSOID: u32 = 0u;
+const WAVEFORM_GAUSSIAN_PULSE: u32 = 1u;
+const WAVEFORM_RICKER: u32 = 2u;
+const WAVEFORM_EXPRESSION: u32 = 3u;
+
+const PI: f32 = 3.14159265;
+
+// `MAX_IMPULSES` and `WORKGROUP_SIZE` are defined by the preprocessor, mirroring the constants of
+// the same name on the CPU
+
 @group(0) @binding(0)
 var current: texture_2d<f32>;
 @group(0) @binding(1)
 var next: texture_storage_2d<rg32float, write>;
+@group(0) @binding(2)
+var auxiliary: texture_2d<f32>;
+@group(0) @binding(3)
+var next_auxiliary: texture_storage_2d<rg32float, write>;
 
-const PI: f32 = 3.14159;
+@group(1) @binding(0)
+var<uniform> parameters: SimulationParameters;
+@group(1) @binding(1)
+var speed_map: texture_2d<f32>;
+@group(1) @binding(2)
+var obstacles: texture_2d<u32>;
+@group(1) @binding(3)
+var<storage, read> sources: array<Source>;
 
+/// Advances the wave equation by one tick using a second order leapfrog scheme.
+///
+/// The red channel of each texel holds u(x, t) and the green channel holds u(x, t-1), so the
+/// update writes (u(x, t+1), u(x, t)) into the `next` texture.
+///
+/// Inside the perfectly matched layer the equation is augmented with the damping terms of
+/// Grote & Sim, whose auxiliary field psi is advanced into the `next_auxiliary` texture.
+///
+/// Cells covered by an obstacle are held at zero, while every other cell is driven by the sum of
+/// all sources.
 @compute
-@workgroup_size(16, 16, 1)
+@workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, 1)
 fn main(
     @builtin(global_invocation_id) id: vec3<u32>,
 ) {
-    let current_value = textureLoad(current, id.xy, 0);
+    let dims = vec2<i32>(textureDimensions(current));
+    let coord = vec2<i32>(id.xy);
+
+    if any(coord >= dims) {
+        return;
+    }
+
+    let u = textureLoad(current, coord, 0).r;
+
+    var u_next: f32;
+
+    // cells on an absorbing edge are extrapolated from their inner neighbour, every other cell
+    // is advanced by the interior stencil
+    if obstacle_at(coord) != OBSTACLE_OPEN {
+        u_next = 0.0;
+    } else if coord.x == 0 && parameters.boundaries.x == BOUNDARY_ABSORBING {
+        u_next = mur(coord, coord + vec2<i32>(1, 0));
+    } else if coord.x == dims.x - 1 && parameters.boundaries.y == BOUNDARY_ABSORBING {
+        u_next = mur(coord, coord - vec2<i32>(1, 0));
+    } else if coord.y == 0 && parameters.boundaries.z == BOUNDARY_ABSORBING {
+        u_next = mur(coord, coord + vec2<i32>(0, 1));
+    } else if coord.y == dims.y - 1 && parameters.boundaries.w == BOUNDARY_ABSORBING {
+        u_next = mur(coord, coord - vec2<i32>(0, 1));
+    } else {
+        u_next = leapfrog(coord);
+    }
 
-    // textureStore(next, id.xy, vec4<f32>(current_value.x + 0.01, 0.0, 0.0,
+    // impulses are added to both time levels so the bump starts out at rest
+    var bump = 0.0;
 
-    let uv = vec2<f32>(id.xy) / vec2<f32>(textureDimensions(current));
-    let value = sin(8.0 * PI * uv.x) * sin(8.0 * PI * uv.y) * exp(-4.0 * distance(vec2<f32>(0.5), uv));
+    if obstacle_at(coord) == OBSTACLE_OPEN {
+        u_next += parameters.dt * parameters.dt * forcing(coord);
+        bump = impulse_displacement(coord);
+    }
 
     textureStore(
         next,
-        id.xy,
-        vec4<f32>(value, 0.0, 0.0, 0.0)
+        coord,
+        vec4<f32>(u_next + bump, u + bump, 0.0, 0.0)
     );
+
+    textureStore(
+        next_auxiliary,
+        coord,
+        vec4<f32>(auxiliary_next(coord), 0.0, 0.0)
+    );
+}
+
+/// Returns u(x, t+1) at the given cell using the leapfrog discretization of the wave equation.
+fn leapfrog(coord: vec2<i32>) -> f32 {
+    let state = textureLoad(current, coord, 0);
+
+    let u = state.r;
+    let u_previous = state.g;
+
+    let laplacian = (
+        neighbour_at(coord, vec2<i32>(1, 0), u)
+        + neighbour_at(coord, vec2<i32>(-1, 0), u)
+        + neighbour_at(coord, vec2<i32>(0, 1), u)
+        + neighbour_at(coord, vec2<i32>(0, -1), u)
+        - 4.0 * u
+    ) / (parameters.dx * parameters.dx);
+
+    let c = speed_at(coord);
+    let dt = parameters.dt;
+    let sigma = pml_damping(coord);
+
+    if all(sigma == vec2<f32>(0.0)) {
+        return 2.0 * u - u_previous + c * c * dt * dt * laplacian;
+    }
+
+    let divergence = (
+        psi_at(coord + vec2<i32>(1, 0)).x
+        - psi_at(coord - vec2<i32>(1, 0)).x
+        + psi_at(coord + vec2<i32>(0, 1)).y
+        - psi_at(coord - vec2<i32>(0, 1)).y
+    ) / (2.0 * parameters.dx);
+
+    // the first order damping term is discretized with central differences, which makes the
+    // update semi-implicit in u(x, t+1)
+    let a = 0.5 * dt * (sigma.x + sigma.y);
+
+    return (
+        2.0 * u
+        - (1.0 - a) * u_previous
+        - dt * dt * sigma.x * sigma.y * u
+        + dt * dt * (c * c * laplacian + divergence)
+    ) / (1.0 + a);
+}
+
+/// Returns the auxiliary field psi(x, t+1) of the perfectly matched layer at the given cell.
+fn auxiliary_next(coord: vec2<i32>) -> vec2<f32> {
+    let sigma = pml_damping(coord);
+
+    if all(sigma == vec2<f32>(0.0)) {
+        return vec2<f32>(0.0);
+    }
+
+    let psi = psi_at(coord);
+
+    let gradient = vec2<f32>(
+        u_at(coord + vec2<i32>(1, 0)) - u_at(coord - vec2<i32>(1, 0)),
+        u_at(coord + vec2<i32>(0, 1)) - u_at(coord - vec2<i32>(0, 1)),
+    ) / (2.0 * parameters.dx);
+
+    let c = speed_at(coord);
+    let coupling = vec2<f32>(sigma.y - sigma.x, sigma.x - sigma.y);
+
+    return psi + parameters.dt * (-sigma * psi + c * c * coupling * gradient);
+}
+
+/// Returns the sum of the forcing terms of all sources at the given cell.
+fn forcing(coord: vec2<i32>) -> f32 {
+    let position = vec2<f32>(coord) * parameters.dx;
+
+    var total = 0.0;
+
+    for (var i = 0u; i < parameters.source_count; i++) {
+        let source = sources[i];
+        let signal = source_signal(i, source, parameters.time, position);
+
+        if signal != 0.0 {
+            total += signal * source_falloff(source_distance(source, position));
+        }
+    }
+
+    return total;
+}
+
+/// Returns the total height of all impulses applied during the tick at the given cell.
+fn impulse_displacement(coord: vec2<i32>) -> f32 {
+    let position = vec2<f32>(coord) * parameters.dx;
+
+    var total = 0.0;
+
+    for (var i = 0u; i < parameters.impulse_count; i++) {
+        let impulse = parameters.impulses[i];
+        let offset = (position - impulse.xy) / impulse.z;
+
+        total += impulse.w * exp(-0.5 * dot(offset, offset));
+    }
+
+    return total;
+}
+
+/// Returns the strength of the source with the given index at the given time and position,
+/// before any spatial falloff is applied.
+fn source_signal(index: u32, source: Source, time: f32, position: vec2<f32>) -> f32 {
+    if time < source.start_time || time >= source.end_time {
+        return 0.0;
+    }
+
+    let local_time = time - source.start_time;
+    let omega = 2.0 * PI * source.frequency;
+
+    // pulses are delayed by one period so they start out (nearly) at zero
+    let arg = PI * source.frequency * (local_time - 1.0 / source.frequency);
+
+    var signal: f32;
+
+    switch source.waveform {
+        case WAVEFORM_GAUSSIAN_PULSE: {
+            signal = exp(-arg * arg);
+        }
+        case WAVEFORM_RICKER: {
+            signal = (1.0 - 2.0 * arg * arg) * exp(-arg * arg);
+        }
+        case WAVEFORM_EXPRESSION: {
+            signal = expression_signal(index, local_time, position);
+        }
+        case WAVEFORM_SINUSOID, default: {
+            signal = sin(omega * local_time + source.phase);
+        }
+    }
+
+    // the response of the medium to a forcing term falls off with the square of its frequency,
+    // so this keeps the amplitude comparable across frequencies
+    return source.amplitude * omega * omega * signal;
+}
+
+/// Returns the drive expression of the source with the given index, at the time `t` since it
+/// turned on and the given position.
+///
+/// The marked line is replaced with a switch over the sources driven by an expression when the
+/// shader is compiled, see `expression_signal_wgsl` on the CPU.
+fn expression_signal(index: u32, t: f32, position: vec2<f32>) -> f32 {
+    let x = position.x;
+    let z = position.y;
+
+    // EXPRESSION SIGNALS
+
+    return 0.0;
+}
+
+/// Returns the distance from the world space position to the shape of the source.
+fn source_distance(source: Source, position: vec2<f32>) -> f32 {
+    switch source.shape {
+        case SHAPE_LINE: {
+            let direction = source.extent - source.origin;
+            let length_squared = dot(direction, direction);
+
+            var t = 0.0;
+            if length_squared > 0.0 {
+                t = clamp(dot(position - source.origin, direction) / length_squared, 0.0, 1.0);
+            }
+
+            return distance(position, source.origin + t * direction);
+        }
+        case SHAPE_RING: {
+            return abs(distance(position, source.origin) - source.extent.x);
+        }
+        case SHAPE_POINT, default: {
+            return distance(position, source.origin);
+        }
+    }
+}
+
+/// Returns the spatial falloff of a source at `distance` from its shape, spreading it over a
+/// couple of cells to avoid grid artifacts.
+fn source_falloff(distance: f32) -> f32 {
+    let scaled = distance / parameters.dx;
+
+    return exp(-0.5 * scaled * scaled);
+}
+
+/// Returns the wave speed c(x, z) of the given cell.
+fn speed_at(coord: vec2<i32>) -> f32 {
+    return textureLoad(speed_map, coord, 0).r;
+}
+
+/// Returns the damping coefficients (sigma_x, sigma_z) of the perfectly matched layer at the given
+/// cell, which are zero outside of the layer.
+fn pml_damping(coord: vec2<i32>) -> vec2<f32> {
+    let thickness = parameters.pml_thickness;
+
+    if thickness <= 0.0 {
+        return vec2<f32>(0.0);
+    }
+
+    let dims = vec2<f32>(textureDimensions(current));
+    let position = vec2<f32>(coord);
+
+    // how far into the layer the cell lies on each edge, from 0 at its inner edge to 1 at the
+    // outermost cell
+    let depth_min = (thickness - position) / thickness;
+    let depth_max = (position - (dims - 1.0 - thickness)) / thickness;
+
+    var depth = vec2<f32>(0.0);
+
+    if parameters.boundaries.x == BOUNDARY_PML {
+        depth.x = max(depth.x, depth_min.x);
+    }
+    if parameters.boundaries.y == BOUNDARY_PML {
+        depth.x = max(depth.x, depth_max.x);
+    }
+    if parameters.boundaries.z == BOUNDARY_PML {
+        depth.y = max(depth.y, depth_min.y);
+    }
+    if parameters.boundaries.w == BOUNDARY_PML {
+        depth.y = max(depth.y, depth_max.y);
+    }
+
+    // pow(0, n) is undefined in WGSL, so cells outside the layer are masked out explicitly
+    let profile = pow(clamp(depth, vec2<f32>(1e-6), vec2<f32>(1.0)), vec2<f32>(parameters.pml_order));
+
+    return select(vec2<f32>(0.0), parameters.pml_max_damping * profile, depth > vec2<f32>(0.0));
+}
+
+/// Returns the auxiliary field psi(x, t) at the given coordinates, which is zero outside the grid.
+fn psi_at(coord: vec2<i32>) -> vec2<f32> {
+    let dims = vec2<i32>(textureDimensions(auxiliary));
+
+    if any(coord < vec2<i32>(0)) || any(coord >= dims) {
+        return vec2<f32>(0.0);
+    }
+
+    return textureLoad(auxiliary, coord, 0).xy;
 }
 
+/// Returns u(x, t+1) at a cell on an absorbing edge using the first order Mur condition, which
+/// lets waves travelling perpendicular to the edge leave the domain without reflecting.
+fn mur(coord: vec2<i32>, inner: vec2<i32>) -> f32 {
+    let c_dt = speed_at(coord) * parameters.dt;
+    let ratio = (c_dt - parameters.dx) / (c_dt + parameters.dx);
+
+    let u_edge = textureLoad(current, coord, 0).r;
+    let u_inner = textureLoad(current, inner, 0).r;
+
+    return u_inner + ratio * (leapfrog(inner) - u_edge);
+}
+
+/// Returns u(x, t) at the given coordinates, resolving cells outside of the grid according to the
+/// boundary condition of the edge they lie beyond.
+fn u_at(coord: vec2<i32>) -> f32 {
+    let resolved = resolve(coord);
+
+    // fixed edges hold every cell beyond them at zero
+    if !in_grid(resolved) {
+        return 0.0;
+    }
+
+    return textureLoad(current, resolved, 0).r;
+}
+
+/// Returns u(x, t) of the neighbour at `offset` from the cell at `coord`, whose own value is `u`.
+///
+/// Reflecting obstacles mirror the value of the cell they border, giving them zero slope.
+fn neighbour_at(coord: vec2<i32>, offset: vec2<i32>, u: f32) -> f32 {
+    let resolved = resolve(coord + offset);
+
+    if !in_grid(resolved) {
+        return 0.0;
+    }
+
+    if obstacle_at(resolved) == OBSTACLE_REFLECTING {
+        return u;
+    }
+
+    return textureLoad(current, resolved, 0).r;
+}
+
+/// Returns whether the given coordinates lie inside the grid.
+fn in_grid(coord: vec2<i32>) -> bool {
+    let dims = vec2<i32>(textureDimensions(current));
+
+    return all(coord >= vec2<i32>(0)) && all(coord < dims);
+}
+
+/// Returns the obstacle covering the given cell, which must lie inside the grid.
+fn obstacle_at(coord: vec2<i32>) -> u32 {
+    return textureLoad(obstacles, coord, 0).r;
+}
+
+/// Maps coordinates outside of the grid back into it according to the boundary condition of the
+/// edge they lie beyond, leaving them out of bounds if the cell is held at zero.
+fn resolve(coord: vec2<i32>) -> vec2<i32> {
+    let dims = vec2<i32>(textureDimensions(current));
+
+    var resolved = coord;
+
+    if coord.x < 0 {
+        resolved.x = resolve_ghost(coord.x, dims.x, parameters.boundaries.x);
+    } else if coord.x >= dims.x {
+        resolved.x = resolve_ghost(coord.x, dims.x, parameters.boundaries.y);
+    }
+
+    if coord.y < 0 {
+        resolved.y = resolve_ghost(coord.y, dims.y, parameters.boundaries.z);
+    } else if coord.y >= dims.y {
+        resolved.y = resolve_ghost(coord.y, dims.y, parameters.boundaries.w);
+    }
+
+    return resolved;
+}
@@ -0,0 +1,201 @@
+/// The parameters of the reduction, mirroring `StatisticsUniforms` on the CPU.
+struct StatisticsParameters {
+    /// The duration of the tick separating u(t) and u(t - dt).
+    dt: f32,
+    /// The distance between two adjacent cells of the grid (dx).
+    dx: f32,
+    _padding: vec2<f32>,
+    /// The boundary condition of each edge, in the order x_min, x_max, z_min, z_max.
+    boundaries: vec4<u32>,
+}
+
+/// The statistics of the cells covered by a single workgroup, mirroring `StatisticsPartial` on
+/// the CPU.
+struct Partial {
+    /// The kinetic energy of the covered cells.
+    kinetic_energy: f32,
+    /// The potential energy of the covered cells.
+    potential_energy: f32,
+    /// The smallest u(t) of the covered cells.
+    min: f32,
+    /// The largest u(t) of the covered cells.
+    max: f32,
+    /// The sum of u(t) over the covered cells.
+    sum: f32,
+    /// The sum of u(t)² over the covered cells.
+    sum_of_squares: f32,
+}
+
+#include "boundaries.wgsl"
+
+/// The number of invocations of a workgroup, whose side length `WORKGROUP_SIZE` is defined by the
+/// preprocessor.
+const WORKGROUP_INVOCATIONS: u32 = WORKGROUP_SIZE * WORKGROUP_SIZE;
+
+const FLOAT_MAX: f32 = 3.40282347e38;
+
+@group(0) @binding(0)
+var<uniform> parameters: StatisticsParameters;
+@group(0) @binding(1)
+var state: texture_2d<f32>;
+@group(0) @binding(2)
+var speed_map: texture_2d<f32>;
+@group(0) @binding(3)
+var obstacles: texture_2d<u32>;
+@group(0) @binding(4)
+var<storage, read_write> partials: array<Partial>;
+
+var<workgroup> scratch: array<Partial, WORKGROUP_INVOCATIONS>;
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, 1)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup: vec3<u32>,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    let size = textureDimensions(state);
+
+    // invocations past the edge of the grid still take part in the reduction, contributing
+    // nothing to it
+    var partial = empty_partial();
+
+    if all(id.xy < size) {
+        partial = cell_partial(vec2<i32>(id.xy));
+    }
+
+    scratch[local_index] = partial;
+    workgroupBarrier();
+
+    // pairwise tree reduction, halving the number of active invocations each round
+    for (var stride = WORKGROUP_INVOCATIONS / 2u; stride > 0u; stride /= 2u) {
+        if local_index < stride {
+            scratch[local_index] = combine(scratch[local_index], scratch[local_index + stride]);
+        }
+
+        workgroupBarrier();
+    }
+
+    if local_index == 0u {
+        partials[workgroup.y * workgroups.x + workgroup.x] = scratch[0];
+    }
+}
+
+/// Computes the statistics of a single cell.
+///
+/// The energy is the one the leapfrog scheme conserves, pairing the gradients of u(t) and
+/// u(t - dt) across every edge between two cells, including those to the ghost cells beyond the
+/// edges of the grid.
+fn cell_partial(position: vec2<i32>) -> Partial {
+    let values = textureLoad(state, position, 0).xy;
+    let speed = textureLoad(speed_map, position, 0).r;
+
+    let velocity = (values.x - values.y) / parameters.dt;
+
+    // each cell accounts for the edges to its neighbours towards +X and +Z, while the cells
+    // along the min edges also account for the edges to the ghost cells behind them, unless
+    // these wrap around to the opposite side and are already accounted for there
+    var gradient_product = edge_gradient_product(position, vec2<i32>(1, 0), values)
+        + edge_gradient_product(position, vec2<i32>(0, 1), values);
+
+    if position.x == 0 && parameters.boundaries.x != BOUNDARY_PERIODIC {
+        gradient_product += edge_gradient_product(position, vec2<i32>(-1, 0), values);
+    }
+
+    if position.y == 0 && parameters.boundaries.z != BOUNDARY_PERIODIC {
+        gradient_product += edge_gradient_product(position, vec2<i32>(0, -1), values);
+    }
+
+    let area = parameters.dx * parameters.dx;
+
+    var partial: Partial;
+
+    partial.kinetic_energy = 0.5 * velocity * velocity * area;
+    partial.potential_energy = 0.5 * speed * speed * gradient_product * area;
+    partial.min = values.x;
+    partial.max = values.x;
+    partial.sum = values.x;
+    partial.sum_of_squares = values.x * values.x;
+
+    return partial;
+}
+
+/// Returns the product of the gradients of u(t) and u(t - dt) across the edge between the cell at
+/// `position` holding `values` and its neighbour at `offset`.
+///
+/// Reflecting obstacles give the wave zero slope, so edges touching them carry no energy.
+fn edge_gradient_product(position: vec2<i32>, offset: vec2<i32>, values: vec2<f32>) -> f32 {
+    let neighbour = resolve(position + offset);
+
+    if is_reflecting(position) || (in_grid(neighbour) && is_reflecting(neighbour)) {
+        return 0.0;
+    }
+
+    // fixed edges hold every cell beyond them at zero
+    var neighbour_values = vec2<f32>(0.0);
+
+    if in_grid(neighbour) {
+        neighbour_values = textureLoad(state, neighbour, 0).xy;
+    }
+
+    let gradient = (neighbour_values - values) / parameters.dx;
+
+    return gradient.x * gradient.y;
+}
+
+/// Maps coordinates outside of the grid back into it according to the boundary condition of the
+/// edge they lie beyond, leaving them out of bounds if the cell is held at zero.
+fn resolve(position: vec2<i32>) -> vec2<i32> {
+    let size = vec2<i32>(textureDimensions(state));
+    var resolved = position;
+
+    if position.x < 0 {
+        resolved.x = resolve_ghost(position.x, size.x, parameters.boundaries.x);
+    } else if position.x >= size.x {
+        resolved.x = resolve_ghost(position.x, size.x, parameters.boundaries.y);
+    }
+
+    if position.y < 0 {
+        resolved.y = resolve_ghost(position.y, size.y, parameters.boundaries.z);
+    } else if position.y >= size.y {
+        resolved.y = resolve_ghost(position.y, size.y, parameters.boundaries.w);
+    }
+
+    return resolved;
+}
+
+/// Returns whether the given coordinates lie inside the grid.
+fn in_grid(position: vec2<i32>) -> bool {
+    let size = vec2<i32>(textureDimensions(state));
+
+    return all(position >= vec2<i32>(0)) && all(position < size);
+}
+
+/// Returns whether the given cell, which must lie inside the grid, is a reflecting obstacle.
+fn is_reflecting(position: vec2<i32>) -> bool {
+    return textureLoad(obstacles, position, 0).r == OBSTACLE_REFLECTING;
+}
+
+/// Returns the statistics of no cells at all.
+fn empty_partial() -> Partial {
+    var partial: Partial;
+
+    partial.min = FLOAT_MAX;
+    partial.max = -FLOAT_MAX;
+
+    return partial;
+}
+
+/// Merges the statistics of two disjoint sets of cells.
+fn combine(a: Partial, b: Partial) -> Partial {
+    var partial: Partial;
+
+    partial.kinetic_energy = a.kinetic_energy + b.kinetic_energy;
+    partial.potential_energy = a.potential_energy + b.potential_energy;
+    partial.min = min(a.min, b.min);
+    partial.max = max(a.max, b.max);
+    partial.sum = a.sum + b.sum;
+    partial.sum_of_squares = a.sum_of_squares + b.sum_of_squares;
+
+    return partial;
+}
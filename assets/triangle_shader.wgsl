@@ -16,6 +16,12 @@ var<uniform> camera_view_projection: mat4x4<f32>;
 @group(1) @binding(0)
 var displacement_map: texture_2d<f32>;
 
+@group(2) @binding(2)
+var obstacles: texture_2d<u32>;
+
+/// The colour of cells covered by an obstacle.
+const OBSTACLE_COLOR: vec3<f32> = vec3<f32>(0.9, 0.45, 0.1);
+
 @vertex
 fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
@@ -63,7 +69,12 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let diffuse = lambertian_shading(normal, light_dir, light_color);
 
-    return vec4<f32>(vec3<f32>(in.uv, 0.0) * diffuse, 1.0);
+    let cell = vec2<u32>(vec2<f32>(textureDimensions(obstacles)) * in.uv);
+    let is_obstacle = textureLoad(obstacles, cell, 0).r != 0u;
+
+    let albedo = select(vec3<f32>(in.uv, 0.0), OBSTACLE_COLOR, is_obstacle);
+
+    return vec4<f32>(albedo * diffuse, 1.0);
 }
 
 fn lambertian_shading(normal: vec3<f32>, light_dir: vec3<f32>, light_color: vec3<f32>) -> vec3<f32> {
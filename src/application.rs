@@ -1,6 +1,6 @@
-use std::sync::Arc;
+use std::{collections::VecDeque, sync::Arc};
 
-use glam::vec3;
+use glam::{Vec2, vec3};
 
 #[cfg(target_arch = "wasm32")]
 use winit::event_loop::EventLoopProxy;
@@ -8,18 +8,129 @@ use winit::event_loop::EventLoopProxy;
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
-    event::{DeviceEvent, DeviceId, WindowEvent},
+    event::{DeviceEvent, DeviceId, ElementState, MouseButton, WindowEvent},
     event_loop::ActiveEventLoop,
+    keyboard::KeyCode,
     window::{Window, WindowId},
 };
 
 use crate::{
-    input::InputState,
+    input::{InputState, InteractionMode},
+    plot::LinePlot,
     renderer::{Renderer, camera::Camera},
-    simulation::WaveSimulation,
+    simulation::{
+        Impulse, MAX_COURANT_NUMBER, MAX_SUBSTEPS, WaveParameters, WaveSimulation,
+        boundary::{Boundary, Edge},
+        config::SimulationConfig,
+        expression::{Expression, Function, Variable},
+        initial::{InitialCondition, InitialConditionKind},
+        line_cut::{LineCut, LineProfile},
+        obstacles::{ObstacleMask, ObstaclePreset, Wall},
+        probes::{MAX_PROBES, Probe, ProbeHistory},
+        sources::{MAX_SOURCES, Source, SourceShape, Waveform},
+        spectrum,
+        speed_map::{SpeedMap, SpeedMapPreset},
+        statistics::WaveStatistics,
+        timestep::FixedTimestep,
+    },
     timer::FrameTimer,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    renderer::shaders::{ASSETS_DIRECTORY, ShaderWatcher},
+    scenario::{CameraPose, Medium, Obstacles, Scenario, TimeStep},
+    simulation::{
+        PendingSnapshot,
+        export::{ExportFields, ExportFormat, ExportSeries, FieldExport},
+        snapshot::Snapshot,
+    },
+};
+#[cfg(not(target_arch = "wasm32"))]
+use web_time::{Duration, Instant};
+
+/// The most measurements of the [`WaveStatistics`] kept to be plotted.
+const STATISTICS_HISTORY_LENGTH: usize = 600;
+
+/// The most points of a probe's time series drawn in its plot, beyond which the series is thinned
+/// out.
+const MAX_PLOTTED_SAMPLES: usize = 2000;
+
+/// The most of the latest samples of a probe its spectrum is computed from.
+const SPECTRUM_LENGTH: usize = 8192;
+
+/// How often the simulation state is read back to be restored after the GPU device is lost.
+#[cfg(not(target_arch = "wasm32"))]
+const RECOVERY_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Returns the color the probe at the given index is drawn in.
+fn probe_color(index: usize) -> egui::Color32 {
+    // stepping the hue by the golden ratio keeps neighbouring probes far apart
+    let hue = (index as f32 * 0.618_034).fract();
+
+    egui::ecolor::Hsva::new(hue, 0.7, 1.0, 1.0).into()
+}
+
+/// Returns the time series of the given probe, keeping at most [`MAX_PLOTTED_SAMPLES`] evenly
+/// spaced samples.
+fn thinned_series(history: &ProbeHistory, probe: usize) -> impl Iterator<Item = [f32; 2]> {
+    let stride = history.len().div_ceil(MAX_PLOTTED_SAMPLES).max(1);
+
+    history
+        .times()
+        .iter()
+        .zip(history.samples(probe))
+        .step_by(stride)
+        .map(|(time, sample)| [*time, *sample])
+}
+
+/// Renders a text field editing `expression`, which is only replaced once the edited text parses,
+/// and returns whether it was.
+///
+/// The edited text is kept in the memory of the UI, so it survives the frames it doesn't parse in
+/// alongside the error explaining why.
+fn expression_ui(ui: &mut egui::Ui, id_salt: &str, expression: &mut Expression) -> bool {
+    let id = ui.make_persistent_id(id_salt);
+
+    // the text is reset whenever the expression is replaced from elsewhere, e.g. by a scenario
+    let (mut text, mut error) = ui
+        .data_mut(|data| data.get_temp::<(String, Option<String>)>(id))
+        .filter(|(text, error)| error.is_some() || text == expression.text())
+        .unwrap_or_else(|| (expression.text().to_owned(), None));
+
+    let mut changed = false;
+
+    ui.vertical(|ui| {
+        let functions = Function::ALL.map(Function::name).join(", ");
+        let variables = Variable::ALL.map(Variable::name).join(", ");
+
+        let response = ui
+            .add(egui::TextEdit::singleline(&mut text).font(egui::TextStyle::Monospace))
+            .on_hover_text(format!(
+                "Variables: {variables}\nConstants: pi, e\nFunctions: {functions}"
+            ));
+
+        if response.changed() {
+            match Expression::parse(&text) {
+                Ok(parsed) => {
+                    *expression = parsed;
+                    error = None;
+                    changed = true;
+                }
+                Err(parse_error) => error = Some(format!("{parse_error:#}")),
+            }
+        }
+
+        if let Some(error) = &error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    });
+
+    ui.data_mut(|data| data.insert_temp(id, (text, error)));
+
+    changed
+}
+
 /// Manages all subsystems and handles incoming events.
 pub struct App {
     /// The primary window being rendered onto.
@@ -36,6 +147,106 @@ pub struct App {
 
     /// The current GPU state of the simulation.
     simulation: WaveSimulation,
+    /// Converts the duration of each frame into a number of simulation ticks.
+    timestep: FixedTimestep,
+    /// How many ticks a single step advances the simulation by.
+    step_count: u32,
+    /// The simulation domain being edited in the UI, applied once confirmed.
+    pending_config: SimulationConfig,
+    /// The medium selected in the UI, generated once confirmed.
+    speed_preset: SpeedMapPreset,
+    /// The wave speed of the surrounding medium used when generating the `speed_preset`.
+    base_speed: f32,
+    /// The wave speed c(x, z) typed in by the user, generated once confirmed.
+    speed_expression: Expression,
+    /// The obstacles selected in the UI, placed once confirmed.
+    obstacle_preset: ObstaclePreset,
+    /// The kind of wall the `obstacle_preset` is built from.
+    wall: Wall,
+    /// The medium last generated or loaded, as written to scenario files.
+    #[cfg(not(target_arch = "wasm32"))]
+    medium: Medium,
+    /// The obstacles last placed, as written to scenario files.
+    #[cfg(not(target_arch = "wasm32"))]
+    obstacles: Obstacles,
+    /// The number of ticks a headless run of the scenario lasts, if it has a fixed length.
+    #[cfg(not(target_arch = "wasm32"))]
+    run_length: Option<u32>,
+    /// The path of a PGM image to load a speed map from.
+    #[cfg(not(target_arch = "wasm32"))]
+    speed_map_path: String,
+    /// Why the speed map at `speed_map_path` last failed to load, until one loads.
+    #[cfg(not(target_arch = "wasm32"))]
+    speed_map_error: Option<String>,
+    /// The path of the snapshot file the simulation state is saved to and loaded from.
+    #[cfg(not(target_arch = "wasm32"))]
+    snapshot_path: String,
+    /// Why the last save or load of a snapshot failed, until one succeeds.
+    #[cfg(not(target_arch = "wasm32"))]
+    snapshot_error: Option<String>,
+    /// The path of the scenario file the experiment is saved to and loaded from.
+    #[cfg(not(target_arch = "wasm32"))]
+    scenario_path: String,
+    /// Why the last save or load of a scenario failed, until one succeeds.
+    #[cfg(not(target_arch = "wasm32"))]
+    scenario_error: Option<String>,
+
+    /// The path exported file names are derived from.
+    #[cfg(not(target_arch = "wasm32"))]
+    export_stem: String,
+    /// The format the field is exported in.
+    #[cfg(not(target_arch = "wasm32"))]
+    export_format: ExportFormat,
+    /// The fields exported alongside the displacement u.
+    #[cfg(not(target_arch = "wasm32"))]
+    export_fields: ExportFields,
+    /// The number of ticks between two frames of an exported time series.
+    #[cfg(not(target_arch = "wasm32"))]
+    export_interval: u32,
+    /// The time series being exported, if any.
+    #[cfg(not(target_arch = "wasm32"))]
+    export_series: Option<ExportSeries>,
+
+    /// The standard deviation of the bump left by poking the surface (in world units).
+    poke_radius: f32,
+    /// The height of the bump left by poking the surface.
+    poke_amplitude: f32,
+    /// Where the surface was last poked during the current drag, if any.
+    last_poke: Option<Vec2>,
+
+    /// The latest measurements of the [`WaveStatistics`], oldest first.
+    statistics_history: VecDeque<WaveStatistics>,
+
+    /// Where the line cut being drawn starts, while the left mouse button is held.
+    line_cut_anchor: Option<[f32; 2]>,
+    /// The latest displacement sampled along the line cut.
+    line_profile: Option<LineProfile>,
+    /// A profile kept around to compare the latest one against.
+    held_line_profile: Option<LineProfile>,
+
+    /// The highest frequency shown in the probe spectra (in Hz).
+    spectrum_max_frequency: f32,
+    /// The path of the CSV file the probe history is exported to.
+    #[cfg(not(target_arch = "wasm32"))]
+    probe_csv_path: String,
+
+    /// Watches the shaders in `assets/` for changes, if the directory exists.
+    #[cfg(not(target_arch = "wasm32"))]
+    shader_watcher: Option<ShaderWatcher>,
+    /// The name of every reloaded shader or include file that failed to compile or be read,
+    /// alongside its error, while the previous shader keeps running.
+    #[cfg(not(target_arch = "wasm32"))]
+    shader_errors: Vec<(&'static str, String)>,
+
+    /// The latest simulation state read back from the GPU, restored if the device is lost.
+    #[cfg(not(target_arch = "wasm32"))]
+    recovery_snapshot: Option<Snapshot>,
+    /// The next `recovery_snapshot` while it is being read back.
+    #[cfg(not(target_arch = "wasm32"))]
+    pending_recovery_snapshot: Option<PendingSnapshot>,
+    /// When the latest recovery snapshot was started, or None if one is due on the next frame.
+    #[cfg(not(target_arch = "wasm32"))]
+    recovery_snapshot_taken: Option<Instant>,
 
     /// The state of the UI context.
     ui_context: egui::Context,
@@ -46,7 +257,9 @@ pub struct App {
 impl App {
     /// Creates a new [`App`], targetting the given window.
     pub async fn new(window: Arc<Window>) -> Self {
-        let renderer = Renderer::new(Arc::clone(&window)).await.unwrap();
+        let config = SimulationConfig::default();
+
+        let renderer = Renderer::new(Arc::clone(&window), &config).await.unwrap();
 
         let camera = Camera {
             position: vec3(2.5, 3.0, 6.0),
@@ -61,7 +274,12 @@ impl App {
         let input = InputState::new(Arc::clone(&window));
         let timer = FrameTimer::new();
 
-        let simulation = WaveSimulation::new(&renderer.gpu.device, &renderer.pipelines);
+        let simulation = WaveSimulation::new(
+            &renderer.gpu.device,
+            &renderer.gpu.queue,
+            &renderer.pipelines,
+            config,
+        );
 
         let ui_context = egui::Context::default();
         let ui_input = egui_winit::State::new(
@@ -73,21 +291,106 @@ impl App {
             None,
         );
 
-        Self {
+        #[allow(unused_mut)]
+        let mut app = Self {
             window,
             renderer,
             camera,
             input,
             timer,
             simulation,
+            timestep: FixedTimestep::default(),
+            step_count: 1,
+            pending_config: config,
+            speed_preset: SpeedMapPreset::Uniform,
+            base_speed: 1.0,
+            speed_expression: Expression::parse("0.75 + 0.25 * cos(2 * pi * x)")
+                .expect("the default wave speed is a valid expression"),
+            obstacle_preset: ObstaclePreset::None,
+            wall: Wall::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            medium: Medium::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            obstacles: Obstacles::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            run_length: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            speed_map_path: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            speed_map_error: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            snapshot_path: String::from("simulation.wavesnap"),
+            #[cfg(not(target_arch = "wasm32"))]
+            snapshot_error: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            scenario_path: String::from("scenario.toml"),
+            #[cfg(not(target_arch = "wasm32"))]
+            scenario_error: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            export_stem: String::from("wave"),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_format: ExportFormat::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_fields: ExportFields::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_interval: 10,
+            #[cfg(not(target_arch = "wasm32"))]
+            export_series: None,
+            poke_radius: 0.05,
+            poke_amplitude: 0.2,
+            last_poke: None,
+            statistics_history: VecDeque::with_capacity(STATISTICS_HISTORY_LENGTH),
+            line_cut_anchor: None,
+            line_profile: None,
+            held_line_profile: None,
+            spectrum_max_frequency: 10.0,
+            #[cfg(not(target_arch = "wasm32"))]
+            probe_csv_path: String::from("probes.csv"),
+            #[cfg(not(target_arch = "wasm32"))]
+            shader_watcher: std::path::Path::new(ASSETS_DIRECTORY)
+                .is_dir()
+                .then(|| ShaderWatcher::new(ASSETS_DIRECTORY)),
+            #[cfg(not(target_arch = "wasm32"))]
+            shader_errors: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            recovery_snapshot: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            pending_recovery_snapshot: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            recovery_snapshot_taken: None,
             ui_context,
             ui_input,
+        };
+
+        // a scenario file may be passed as the only command line argument
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = std::env::args().nth(1) {
+            app.scenario_path = path;
+
+            match app.load_scenario() {
+                Ok(()) => log::info!("Loaded the scenario {}", app.scenario_path),
+                Err(error) => {
+                    log::error!("Failed to load scenario: {error:#}");
+                    app.scenario_error = Some(format!("Failed to load: {error:#}"));
+                }
+            }
         }
+
+        app
     }
 
     /// Processes an incoming [`WindowEvent`].
     pub fn window_event(&mut self, event_loop: &ActiveEventLoop, event: &WindowEvent) {
         if self.ui_input.on_window_event(&self.window, event).consumed {
+            // releasing a button over the UI must still end a drag started outside of it
+            if let WindowEvent::MouseInput {
+                state: ElementState::Released,
+                ..
+            } = event
+            {
+                self.input.window_event(event);
+            }
+
             return;
         }
 
@@ -115,6 +418,16 @@ impl App {
 
     /// Runs the render and update cycle of the app.
     fn update(&mut self) {
+        // nothing is drawn while minimised, and resizing the window back requests a new frame
+        if self.renderer.gpu.is_minimised() {
+            return;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.renderer.gpu.is_device_lost() {
+            self.recover_from_device_loss();
+        }
+
         self.timer.tick();
 
         let dt = self.timer.dt.as_secs_f32();
@@ -122,6 +435,39 @@ impl App {
         if self.input.focused {
             self.camera
                 .update_position(|k| self.input.keys_held.contains(k), dt);
+        } else {
+            match self.input.interaction {
+                InteractionMode::Camera => {}
+                InteractionMode::Poke => self.poke_surface(),
+                InteractionMode::Probe => self.place_probe(),
+                InteractionMode::LineCut => self.draw_line_cut(),
+            }
+        }
+
+        self.handle_shortcuts();
+        self.input.end_frame();
+
+        #[allow(unused_mut)]
+        let mut substeps = self.timestep.advance(dt, self.simulation.tick_dt());
+
+        // frames of an exported series are taken after exactly every `interval` ticks, at the
+        // cost of running fewer ticks during the frames they are due in
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(series) = &self.export_series {
+            substeps = substeps.min(series.ticks_until_next());
+        }
+
+        if let Some(statistics) = self.simulation.poll_statistics(&self.renderer.gpu.device) {
+            self.record_statistics(statistics);
+        }
+
+        self.simulation.poll_probes(&self.renderer.gpu.device);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.reload_shaders();
+
+        if let Some(profile) = self.simulation.poll_line_cut(&self.renderer.gpu.device) {
+            self.line_profile = Some(profile);
         }
 
         let ui = self
@@ -139,12 +485,261 @@ impl App {
             &self.ui_context,
             ui,
             &mut self.simulation,
+            substeps,
             || self.window.pre_present_notify(),
         );
 
+        #[cfg(not(target_arch = "wasm32"))]
+        self.advance_export_series(substeps);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.refresh_recovery_snapshot();
+
         self.window.request_redraw();
     }
 
+    /// Reads back the simulation state every [`RECOVERY_SNAPSHOT_INTERVAL`], for it to survive
+    /// the loss of the GPU device.
+    ///
+    /// The state is read back across frames rather than blocking, so large grids don't stall.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn refresh_recovery_snapshot(&mut self) {
+        let gpu = &self.renderer.gpu;
+
+        if let Some(pending) = &mut self.pending_recovery_snapshot {
+            match pending.poll(&gpu.device) {
+                None => return,
+                Some(Ok(snapshot)) => self.recovery_snapshot = Some(snapshot),
+                Some(Err(error)) => log::warn!("Failed to take a recovery snapshot: {error:#}"),
+            }
+
+            self.pending_recovery_snapshot = None;
+        }
+
+        if self
+            .recovery_snapshot_taken
+            .is_some_and(|taken| taken.elapsed() < RECOVERY_SNAPSHOT_INTERVAL)
+        {
+            return;
+        }
+
+        self.recovery_snapshot_taken = Some(Instant::now());
+        self.pending_recovery_snapshot =
+            Some(self.simulation.begin_snapshot(&gpu.device, &gpu.queue));
+    }
+
+    /// Recreates the renderer and simulation on a new GPU device, restoring the latest
+    /// `recovery_snapshot`.
+    ///
+    /// If no device can be created yet, the next frame tries again.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn recover_from_device_loss(&mut self) {
+        log::warn!("Recovering from the loss of the GPU device");
+
+        let config = *self.simulation.config();
+
+        if let Err(error) = pollster::block_on(self.renderer.recover(&self.ui_context, &config)) {
+            log::error!("Failed to recreate the GPU device: {error:#}");
+            return;
+        }
+
+        let gpu = &self.renderer.gpu;
+        let mut simulation =
+            WaveSimulation::new(&gpu.device, &gpu.queue, &self.renderer.pipelines, config);
+
+        simulation.set_probes(&gpu.queue, self.simulation.probes().to_vec());
+        simulation.set_line_cut(self.simulation.line_cut());
+
+        self.simulation = simulation;
+
+        // the shaders are rebuilt from the ones embedded in the binary, so edits on disk are
+        // reloaded once they change again
+        self.shader_errors.clear();
+
+        match self.recovery_snapshot.clone() {
+            Some(snapshot) => self.restore_snapshot(snapshot),
+            None => {
+                self.timestep.reset();
+                self.statistics_history.clear();
+            }
+        }
+
+        self.pending_recovery_snapshot = None;
+        self.recovery_snapshot_taken = None;
+    }
+
+    /// Recompiles every shader changed on disk, keeping track of the ones that failed to.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_shaders(&mut self) {
+        let Some(watcher) = &mut self.shader_watcher else {
+            return;
+        };
+
+        for (name, source) in watcher.poll() {
+            self.shader_errors.retain(|(failed, _)| *failed != name);
+
+            let source = match source {
+                Ok(source) => source,
+                Err(error) => {
+                    log::error!("Failed to reload shader: {error:#}");
+                    self.shader_errors.push((name, format!("{error:#}")));
+                    continue;
+                }
+            };
+
+            for (file, result) in self.renderer.reload_shader(name, source) {
+                let name = file.file_name();
+                self.shader_errors.retain(|(failed, _)| *failed != name);
+
+                match result {
+                    Ok(()) => log::info!("Reloaded {name}"),
+                    Err(error) => {
+                        log::error!("Failed to reload shader: {error:#}");
+                        self.shader_errors.push((name, format!("{error:#}")));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Appends a measurement to the `statistics_history`, dropping the oldest ones.
+    fn record_statistics(&mut self, statistics: WaveStatistics) {
+        // a measurement from before the latest one means the simulation has been reset
+        if self
+            .statistics_history
+            .back()
+            .is_some_and(|latest| statistics.time < latest.time)
+        {
+            self.statistics_history.clear();
+        }
+
+        if self.statistics_history.len() == STATISTICS_HISTORY_LENGTH {
+            self.statistics_history.pop_front();
+        }
+
+        self.statistics_history.push_back(statistics);
+    }
+
+    /// Applies the simulation transport shortcuts pressed since the last frame.
+    fn handle_shortcuts(&mut self) {
+        let timestep = &mut self.timestep;
+
+        if self.input.was_pressed(KeyCode::KeyK) {
+            timestep.paused = !timestep.paused;
+        }
+
+        if self.input.was_pressed(KeyCode::Period) {
+            timestep.step(self.step_count);
+        }
+
+        if self.input.was_pressed(KeyCode::KeyJ) {
+            timestep.speed = (timestep.speed / 2.0).max(FixedTimestep::MIN_SPEED);
+        }
+
+        if self.input.was_pressed(KeyCode::KeyL) {
+            timestep.speed = (timestep.speed * 2.0).min(FixedTimestep::MAX_SPEED);
+        }
+
+        if self.input.was_pressed(KeyCode::KeyR) {
+            self.reset_simulation();
+        }
+    }
+
+    /// Pokes the water surface under the cursor while the left mouse button is held.
+    fn poke_surface(&mut self) {
+        if !self.input.buttons_held.contains(&MouseButton::Left) {
+            self.last_poke = None;
+            return;
+        }
+
+        let Some((hit, position)) = self.cursor_cell() else {
+            return;
+        };
+
+        // while dragging, only poke again once the cursor has moved far enough to leave an even
+        // trail rather than piling bumps on top of each other
+        if self
+            .last_poke
+            .is_some_and(|last| last.distance(hit) < self.poke_radius / 2.0)
+        {
+            return;
+        }
+
+        self.simulation.poke(Impulse {
+            position,
+            radius: self.poke_radius,
+            amplitude: self.poke_amplitude,
+        });
+
+        self.last_poke = Some(hit);
+    }
+
+    /// Places a probe on the cell under the cursor when the left mouse button is clicked.
+    fn place_probe(&mut self) {
+        if !self.input.was_clicked(MouseButton::Left) {
+            return;
+        }
+
+        let Some((_, position)) = self.cursor_cell() else {
+            return;
+        };
+
+        let mut probes = self.simulation.probes().to_vec();
+
+        if probes.len() < MAX_PROBES {
+            probes.push(Probe { position });
+            self.simulation.set_probes(&self.renderer.gpu.queue, probes);
+        }
+    }
+
+    /// Draws the line cut from where the left mouse button was pressed to the cell under the
+    /// cursor, for as long as it is held.
+    fn draw_line_cut(&mut self) {
+        if !self.input.buttons_held.contains(&MouseButton::Left) {
+            self.line_cut_anchor = None;
+            return;
+        }
+
+        let Some((_, position)) = self.cursor_cell() else {
+            return;
+        };
+
+        if self.input.was_clicked(MouseButton::Left) {
+            self.line_cut_anchor = Some(position);
+        }
+
+        let Some(start) = self.line_cut_anchor else {
+            return;
+        };
+
+        self.simulation.set_line_cut(Some(LineCut {
+            start,
+            end: position,
+        }));
+    }
+
+    /// Returns where the cursor hits the water surface at rest, alongside the world space
+    /// position of the cell nearest to it, or `None` if the cursor lies outside of the domain.
+    fn cursor_cell(&self) -> Option<(Vec2, [f32; 2])> {
+        let cursor = self.input.last_mouse?;
+        let hit = self
+            .camera
+            .cursor_plane_hit(cursor, self.window.inner_size())?;
+
+        let config = self.simulation.config();
+        let (width, depth) = config.grid_size();
+        let dx = config.grid_spacing();
+
+        let cell_x = (hit.x / dx).round();
+        let cell_z = (hit.y / dx).round();
+
+        if !(0.0..width as f32).contains(&cell_x) || !(0.0..depth as f32).contains(&cell_z) {
+            return None;
+        }
+
+        Some((hit, [cell_x * dx, cell_z * dx]))
+    }
+
     /// Renders all application UI.
     fn ui(&mut self, ui: &egui::Context) {
         use egui::*;
@@ -155,34 +750,1584 @@ impl App {
                 "Frame Time: {:.2}ms",
                 self.timer.dt.as_secs_f32() * 1000.0
             ));
+            ui.label(format!(
+                "Simulation / Wall Time: {:.2}x",
+                self.timestep.time_ratio()
+            ));
+            ui.label(format!("Substeps: {}", self.timestep.last_substeps()));
+
+            ui.separator();
+
+            self.statistics_ui(ui);
+        });
+
+        Window::new("Simulation").show(ui, |ui| {
+            self.transport_ui(ui);
+
+            #[cfg(not(target_arch = "wasm32"))]
+            self.snapshot_ui(ui);
+
+            #[cfg(not(target_arch = "wasm32"))]
+            self.scenario_ui(ui);
+
+            ui.separator();
+
+            let max_cells = self.max_grid_cells();
+            let config = &mut self.pending_config;
+
+            Grid::new("simulation_config").show(ui, |ui| {
+                ui.label("Width");
+                ui.add(
+                    DragValue::new(&mut config.width)
+                        .range(0.1..=50.0)
+                        .speed(0.05),
+                );
+                ui.end_row();
+
+                ui.label("Depth");
+                ui.add(
+                    DragValue::new(&mut config.depth)
+                        .range(0.1..=50.0)
+                        .speed(0.05),
+                );
+                ui.end_row();
+
+                ui.label("Cells per unit");
+                ui.add(
+                    DragValue::new(&mut config.cells_per_unit)
+                        .range(4.0..=1000.0)
+                        .speed(1.0),
+                );
+                ui.end_row();
+            });
+
+            let (width, depth) = config.grid_size();
+            ui.label(format!(
+                "Grid: {width}x{depth} cells (at most {max_cells} per side)"
+            ));
+
+            let fits = config.ensure_fits(max_cells);
+
+            if let Err(error) = &fits {
+                ui.colored_label(Color32::RED, format!("{error}"));
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Max substeps per frame");
+                ui.add(DragValue::new(&mut self.timestep.max_substeps).range(1..=MAX_SUBSTEPS));
+            });
+
+            let config = *config;
+            let changed = config != *self.simulation.config();
+
+            if ui
+                .add_enabled(changed && fits.is_ok(), Button::new("Apply"))
+                .clicked()
+            {
+                self.set_simulation_config(config);
+            }
+
+            ui.separator();
+
+            Grid::new("simulation_boundaries").show(ui, |ui| {
+                let boundaries = &mut self.simulation.boundaries;
+
+                for edge in Edge::ALL {
+                    let mut boundary = boundaries.get(edge);
+
+                    ui.label(format!("{} edge", edge.name()));
+                    ComboBox::from_id_salt(edge)
+                        .selected_text(boundary.name())
+                        .show_ui(ui, |ui| {
+                            for option in Boundary::ALL {
+                                ui.selectable_value(&mut boundary, option, option.name());
+                            }
+                        });
+                    ui.end_row();
+
+                    if boundary != boundaries.get(edge) {
+                        boundaries.set(edge, boundary);
+                    }
+                }
+            });
+
+            if self.simulation.boundaries.contains(Boundary::Pml) {
+                let pml = &mut self.simulation.pml;
+
+                ui.separator();
+
+                Grid::new("simulation_pml").show(ui, |ui| {
+                    ui.label("PML thickness");
+                    ui.add(
+                        DragValue::new(&mut pml.thickness)
+                            .range(1..=200)
+                            .suffix(" cells"),
+                    );
+                    ui.end_row();
+
+                    ui.label("PML profile order");
+                    ui.add(DragValue::new(&mut pml.order).range(1.0..=6.0).speed(0.05));
+                    ui.end_row();
+
+                    ui.label("PML reflection");
+                    ui.add(
+                        Slider::new(&mut pml.reflection, 1e-8..=0.5)
+                            .logarithmic(true)
+                            .custom_formatter(|value, _| format!("{value:.1e}")),
+                    );
+                    ui.end_row();
+                });
+            }
+
+            ui.separator();
+            self.medium_ui(ui);
+        });
+
+        Window::new("Sources").show(ui, |ui| self.sources_ui(ui));
+
+        Window::new("Initial Condition").show(ui, |ui| self.initial_condition_ui(ui));
+
+        Window::new("Interaction").show(ui, |ui| {
+            Grid::new("interaction").show(ui, |ui| {
+                let mut interaction = self.input.interaction;
+
+                ui.label("Mode");
+                ComboBox::from_id_salt("interaction_mode")
+                    .selected_text(interaction.name())
+                    .show_ui(ui, |ui| {
+                        for option in InteractionMode::ALL {
+                            ui.selectable_value(&mut interaction, option, option.name());
+                        }
+                    });
+                ui.end_row();
+
+                if interaction != self.input.interaction {
+                    self.input.set_interaction(interaction);
+                }
+
+                ui.label("Poke radius");
+                ui.add(
+                    DragValue::new(&mut self.poke_radius)
+                        .range(0.005..=1.0)
+                        .speed(0.001),
+                );
+                ui.end_row();
+
+                ui.label("Poke amplitude");
+                ui.add(
+                    DragValue::new(&mut self.poke_amplitude)
+                        .range(-2.0..=2.0)
+                        .speed(0.01),
+                );
+                ui.end_row();
+            });
+
+            ui.label(
+                "Press P to cycle through the modes. While poking, placing probes or drawing the \
+                line cut, right click to look around.",
+            );
         });
+
+        Window::new("Probes").show(ui, |ui| self.probes_ui(ui));
+
+        Window::new("Line Cut").show(ui, |ui| self.line_cut_ui(ui));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        Window::new("Export").show(ui, |ui| self.export_ui(ui));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.shader_errors.is_empty() {
+            Window::new("Shader Errors").show(ui, |ui| self.shader_errors_ui(ui));
+        }
+
+        self.probe_markers_ui(ui);
+        self.line_cut_marker_ui(ui);
     }
 
-    /// Resizes the state of the app to match the new window size.
-    fn resize(&mut self, size: PhysicalSize<u32>) {
-        self.renderer.resize(size);
-        self.camera.resize(size);
+    /// Lists the errors of the shaders that failed to reload, which are dismissed once they
+    /// compile again.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn shader_errors_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        ui.label(
+            "The previous version of these shaders keeps running until they are fixed and saved \
+            again.",
+        );
+
+        ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            for (name, error) in &self.shader_errors {
+                ui.separator();
+                ui.strong(*name);
+                ui.label(RichText::new(error).monospace().color(Color32::LIGHT_RED));
+            }
+        });
+
+        if ui.button("Dismiss").clicked() {
+            self.shader_errors.clear();
+        }
     }
-}
 
-/// Manages the creation and lifecycle of the actual [`App`].
-pub struct AppHandler {
-    /// A proxy to create the app when dealing with async events (only needed on web).
-    #[cfg(target_arch = "wasm32")]
-    proxy: Option<EventLoopProxy<App>>,
+    /// Renders the controls for placing the line cut, and plots the displacement sampled along
+    /// it.
+    fn line_cut_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
 
-    /// The initialized Some(app), or None if the window hasn't been created yet.
-    app: Option<App>,
-}
+        let config = *self.simulation.config();
 
-impl AppHandler {
-    /// Creates a new [`AppHandler`], the main entry point to the app.
-    pub fn new(#[cfg(target_arch = "wasm32")] proxy: EventLoopProxy<App>) -> Self {
-        Self {
-            #[cfg(target_arch = "wasm32")]
-            proxy: Some(proxy),
-            app: None,
+        let Some(mut line) = self.simulation.line_cut() else {
+            ui.label(
+                "Left click and drag across the surface in the \"Draw line cut\" interaction mode \
+                to sample the displacement along a line.",
+            );
+
+            if ui.button("Add line cut").clicked() {
+                self.simulation.set_line_cut(Some(LineCut {
+                    start: [0.1 * config.width, config.depth / 2.0],
+                    end: [0.9 * config.width, config.depth / 2.0],
+                }));
+            }
+
+            return;
+        };
+
+        Grid::new("line_cut").show(ui, |ui| {
+            for (label, position) in [("Start", &mut line.start), ("End", &mut line.end)] {
+                ui.label(label);
+                ui.horizontal(|ui| {
+                    for (axis, extent) in [config.width, config.depth].into_iter().enumerate() {
+                        ui.add(
+                            DragValue::new(&mut position[axis])
+                                .range(0.0..=extent)
+                                .speed(config.grid_spacing())
+                                .prefix(["x: ", "z: "][axis]),
+                        );
+                    }
+                });
+                ui.end_row();
+            }
+        });
+
+        ui.label(format!(
+            "Length: {:.3}, {} samples",
+            line.length(),
+            line.sample_count(&config)
+        ));
+
+        if Some(line) != self.simulation.line_cut() {
+            self.simulation.set_line_cut(Some(line));
         }
+
+        ui.horizontal(|ui| {
+            if ui.button("Remove").clicked() {
+                self.simulation.set_line_cut(None);
+                self.line_profile = None;
+            }
+
+            if ui.button("Hold profile").clicked() {
+                self.held_line_profile = self.line_profile.clone();
+            }
+
+            if ui
+                .add_enabled(
+                    self.held_line_profile.is_some(),
+                    Button::new("Release profile"),
+                )
+                .clicked()
+            {
+                self.held_line_profile = None;
+            }
+        });
+
+        let mut plot = LinePlot::new().height(160.0);
+
+        if let Some(held) = &self.held_line_profile {
+            plot = plot.series(
+                format!("held (t = {:.3}s)", held.time),
+                Color32::GRAY,
+                held.samples.iter().copied(),
+            );
+        }
+
+        if let Some(profile) = &self.line_profile {
+            plot = plot.series(
+                format!("u (t = {:.3}s)", profile.time),
+                Color32::LIGHT_BLUE,
+                profile.samples.iter().copied(),
+            );
+        }
+
+        plot.show(ui);
+    }
+
+    /// Draws the line cut on the water surface following the latest sampled profile, drawn
+    /// behind all windows.
+    fn line_cut_marker_ui(&self, ui: &egui::Context) {
+        use egui::*;
+
+        let Some(line) = self.simulation.line_cut() else {
+            return;
+        };
+
+        let painter = ui.layer_painter(LayerId::background());
+        let size = self.window.inner_size();
+        let pixels_per_point = ui.pixels_per_point();
+        let color = Color32::LIGHT_BLUE;
+
+        let to_screen = |[x, z]: [f32; 2], height: f32| {
+            self.camera
+                .world_to_screen(vec3(x, height, z), size)
+                .map(|screen| pos2(screen.x, screen.y) / pixels_per_point)
+        };
+
+        // the profile only follows the surface while it was sampled along the current line
+        let heights = self
+            .line_profile
+            .as_ref()
+            .filter(|profile| profile.line == line)
+            .map(|profile| profile.samples.as_slice())
+            .unwrap_or(&[[0.0; 2], [1.0, 0.0]]);
+
+        let length = heights
+            .last()
+            .map_or(1.0, |[distance, _]| *distance)
+            .max(1e-6);
+
+        let points = heights
+            .iter()
+            .filter_map(|[distance, height]| to_screen(line.point_at(distance / length), *height))
+            .collect::<Vec<_>>();
+
+        painter.add(Shape::line(points, Stroke::new(2.0, color)));
+
+        for (label, position) in [("A", line.start), ("B", line.end)] {
+            let Some(center) = to_screen(position, 0.0) else {
+                continue;
+            };
+
+            painter.circle_filled(center, 3.0, color);
+            painter.text(
+                center + vec2(5.0, -5.0),
+                Align2::LEFT_BOTTOM,
+                label,
+                FontId::proportional(14.0),
+                color,
+            );
+        }
+    }
+
+    /// Renders the controls for placing probes, and plots the time series and spectrum of the
+    /// displacement recorded at each of them.
+    fn probes_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        let config = *self.simulation.config();
+        let queue = &self.renderer.gpu.queue;
+
+        let mut probes = self.simulation.probes().to_vec();
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(probes.len() < MAX_PROBES, |ui| {
+                if ui.button("Add probe").clicked() {
+                    probes.push(Probe {
+                        position: [config.width / 2.0, config.depth / 2.0],
+                    });
+                    changed = true;
+                }
+            });
+
+            if ui.button("Clear history").clicked() {
+                changed = true;
+            }
+        });
+
+        ui.label("Left click the surface in the \"Place probes\" interaction mode to add probes.");
+
+        let history = self.simulation.probe_history();
+        let spectra = (0..probes.len())
+            .map(|probe| history.spectrum(probe, SPECTRUM_LENGTH))
+            .collect::<Vec<_>>();
+
+        let mut removed = None;
+
+        Grid::new("probes").striped(true).show(ui, |ui| {
+            ui.label("");
+            ui.label("X");
+            ui.label("Z");
+            ui.label("u");
+            ui.label("Peak");
+            ui.end_row();
+
+            for (i, probe) in probes.iter_mut().enumerate() {
+                ui.colored_label(probe_color(i), format!("Probe {}", i + 1));
+
+                for (axis, extent) in [config.width, config.depth].into_iter().enumerate() {
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut probe.position[axis])
+                                .range(0.0..=extent)
+                                .speed(config.grid_spacing()),
+                        )
+                        .changed();
+                }
+
+                let latest = history.latest(i).unwrap_or(0.0);
+                ui.label(format!("{latest:+.4}"));
+
+                match spectrum::peak_frequency(&spectra[i]) {
+                    Some(peak) => ui.label(format!("{peak:.3} Hz")),
+                    None => ui.label("-"),
+                };
+
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(i);
+                }
+
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = removed {
+            probes.remove(i);
+            changed = true;
+        }
+
+        if changed {
+            self.simulation.set_probes(queue, probes);
+            return;
+        }
+
+        if probes.is_empty() {
+            return;
+        }
+
+        CollapsingHeader::new("Time Series")
+            .default_open(true)
+            .show(ui, |ui| {
+                let plot = (0..probes.len()).fold(LinePlot::new().x_unit("s"), |plot, probe| {
+                    plot.series(
+                        format!("Probe {}", probe + 1),
+                        probe_color(probe),
+                        thinned_series(history, probe),
+                    )
+                });
+
+                plot.show(ui);
+            });
+
+        CollapsingHeader::new("Spectrum")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Max frequency");
+                    ui.add(
+                        DragValue::new(&mut self.spectrum_max_frequency)
+                            .range(0.1..=1000.0)
+                            .speed(0.1)
+                            .suffix(" Hz"),
+                    );
+                });
+
+                let max_frequency = self.spectrum_max_frequency;
+
+                let plot = spectra.into_iter().enumerate().fold(
+                    LinePlot::new().x_unit("Hz"),
+                    |plot, (probe, spectrum)| {
+                        plot.series(
+                            format!("Probe {}", probe + 1),
+                            probe_color(probe),
+                            spectrum
+                                .into_iter()
+                                .take_while(|[frequency, _]| *frequency <= max_frequency),
+                        )
+                    },
+                );
+
+                plot.show(ui);
+            });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.probe_csv_path);
+
+            if ui.button("Export CSV").clicked() {
+                match std::fs::write(&self.probe_csv_path, history.to_csv()) {
+                    Ok(()) => log::info!(
+                        "Exported {} ticks of probe samples to {}",
+                        history.len(),
+                        self.probe_csv_path
+                    ),
+                    Err(error) => log::error!("Failed to export probe samples: {error}"),
+                }
+            }
+        });
+    }
+
+    /// Marks every probe on the water surface, drawn behind all windows.
+    fn probe_markers_ui(&self, ui: &egui::Context) {
+        use egui::*;
+
+        let painter = ui.layer_painter(LayerId::background());
+        let size = self.window.inner_size();
+        let pixels_per_point = ui.pixels_per_point();
+
+        let history = self.simulation.probe_history();
+
+        for (i, probe) in self.simulation.probes().iter().enumerate() {
+            let [x, z] = probe.position;
+            let height = history.latest(i).unwrap_or(0.0);
+
+            let Some(screen) = self.camera.world_to_screen(vec3(x, height, z), size) else {
+                continue;
+            };
+
+            let center = pos2(screen.x, screen.y) / pixels_per_point;
+            let color = probe_color(i);
+
+            painter.circle_stroke(center, 5.0, Stroke::new(2.0, color));
+            painter.text(
+                center + vec2(7.0, -7.0),
+                Align2::LEFT_BOTTOM,
+                (i + 1).to_string(),
+                FontId::proportional(14.0),
+                color,
+            );
+        }
+    }
+
+    /// Renders the latest [`WaveStatistics`] and plots their history.
+    fn statistics_ui(&self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        let history = &self.statistics_history;
+
+        let (Some(oldest), Some(latest)) = (history.front(), history.back()) else {
+            ui.label("Waiting for statistics...");
+            return;
+        };
+
+        let change = latest.total_energy() - oldest.total_energy();
+
+        // a wave starting out flat has no energy to compare against, so the absolute change is
+        // shown instead
+        let change = if oldest.total_energy() > f32::EPSILON * latest.total_energy() {
+            format!("{:+.3e}%", change / oldest.total_energy() * 100.0)
+        } else {
+            format!("{change:+.3e}")
+        };
+
+        Grid::new("statistics").show(ui, |ui| {
+            ui.label("Energy");
+            ui.label(format!(
+                "{:.4e} (kinetic {:.3e}, potential {:.3e})",
+                latest.total_energy(),
+                latest.kinetic_energy,
+                latest.potential_energy
+            ));
+            ui.end_row();
+
+            ui.label("Energy Change");
+            ui.label(change).on_hover_text(
+                "Change since the oldest measurement of the plotted window, which moves along with \
+                 it, relative to that measurement unless it had no energy",
+            );
+            ui.end_row();
+
+            ui.label("u min / max");
+            ui.label(format!("{:.4} / {:.4}", latest.min, latest.max));
+            ui.end_row();
+
+            ui.label("u mean / RMS");
+            ui.label(format!("{:.4e} / {:.4e}", latest.mean, latest.rms));
+            ui.end_row();
+        });
+
+        let series = |value: fn(&WaveStatistics) -> f32| {
+            history
+                .iter()
+                .map(move |statistics| [statistics.time, value(statistics)])
+        };
+
+        CollapsingHeader::new("Energy History")
+            .default_open(true)
+            .show(ui, |ui| {
+                LinePlot::new()
+                    .x_unit("s")
+                    .series("total", Color32::WHITE, series(|s| s.total_energy()))
+                    .series("kinetic", Color32::LIGHT_BLUE, series(|s| s.kinetic_energy))
+                    .series("potential", Color32::ORANGE, series(|s| s.potential_energy))
+                    .show(ui);
+            });
+
+        CollapsingHeader::new("Displacement History").show(ui, |ui| {
+            LinePlot::new()
+                .x_unit("s")
+                .series("max", Color32::LIGHT_GREEN, series(|s| s.max))
+                .series("min", Color32::LIGHT_RED, series(|s| s.min))
+                .series("RMS", Color32::LIGHT_BLUE, series(|s| s.rms))
+                .show(ui);
+        });
+    }
+
+    /// Renders the controls for pausing, stepping, speeding up and resetting the simulation.
+    fn transport_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        ui.horizontal(|ui| {
+            let timestep = &mut self.timestep;
+
+            let label = if timestep.paused { "Resume" } else { "Pause" };
+
+            if ui.button(label).clicked() {
+                timestep.paused = !timestep.paused;
+            }
+
+            if ui.button("Step").clicked() {
+                timestep.step(self.step_count);
+            }
+
+            ui.add(
+                DragValue::new(&mut self.step_count)
+                    .range(1..=10_000)
+                    .suffix(" ticks"),
+            );
+
+            if ui.button("Reset").clicked() {
+                self.reset_simulation();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Speed");
+            ui.add(
+                Slider::new(
+                    &mut self.timestep.speed,
+                    FixedTimestep::MIN_SPEED..=FixedTimestep::MAX_SPEED,
+                )
+                .logarithmic(true)
+                .suffix("x"),
+            );
+        });
+
+        ui.label(format!("Simulation time: {:.3}s", self.simulation.time()));
+        ui.label("K: pause, period: step, J / L: slower / faster, R: reset");
+    }
+
+    /// Renders the controls for saving the full simulation state to a snapshot file and restoring
+    /// it later.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn snapshot_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.snapshot_path);
+
+            if ui.button("Save").clicked() {
+                self.snapshot_error = match self.save_snapshot() {
+                    Ok(()) => {
+                        log::info!("Saved a snapshot to {}", self.snapshot_path);
+                        None
+                    }
+                    Err(error) => {
+                        log::error!("Failed to save snapshot: {error:#}");
+                        Some(format!("Failed to save: {error:#}"))
+                    }
+                };
+            }
+
+            if ui.button("Load").clicked() {
+                self.snapshot_error = match self.load_snapshot() {
+                    Ok(()) => {
+                        log::info!("Loaded a snapshot from {}", self.snapshot_path);
+                        None
+                    }
+                    Err(error) => {
+                        log::error!("Failed to load snapshot: {error:#}");
+                        Some(format!("Failed to load: {error:#}"))
+                    }
+                };
+            }
+        });
+
+        if let Some(error) = &self.snapshot_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    /// Writes the full simulation state to the file at `snapshot_path`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_snapshot(&self) -> anyhow::Result<()> {
+        let gpu = &self.renderer.gpu;
+        let snapshot = self.simulation.snapshot(&gpu.device, &gpu.queue)?;
+
+        std::fs::write(&self.snapshot_path, snapshot.to_bytes())?;
+
+        Ok(())
+    }
+
+    /// Restores the full simulation state from the file at `snapshot_path`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_snapshot(&mut self) -> anyhow::Result<()> {
+        let bytes = std::fs::read(&self.snapshot_path)?;
+        let snapshot = crate::simulation::snapshot::Snapshot::from_bytes(&bytes)?;
+
+        snapshot.config.ensure_fits(self.max_grid_cells())?;
+        self.restore_snapshot(snapshot);
+
+        Ok(())
+    }
+
+    /// Renders the controls for saving the current setup to a scenario file and loading it back.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn scenario_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.scenario_path)
+                .on_hover_text("A TOML file describing the whole experiment");
+
+            if ui.button("Save").clicked() {
+                self.scenario_error = match self.save_scenario() {
+                    Ok(()) => {
+                        log::info!("Saved the scenario to {}", self.scenario_path);
+                        None
+                    }
+                    Err(error) => {
+                        log::error!("Failed to save scenario: {error:#}");
+                        Some(format!("Failed to save: {error:#}"))
+                    }
+                };
+            }
+
+            if ui.button("Load").clicked() {
+                self.scenario_error = match self.load_scenario() {
+                    Ok(()) => {
+                        log::info!("Loaded the scenario {}", self.scenario_path);
+                        None
+                    }
+                    Err(error) => {
+                        log::error!("Failed to load scenario: {error:#}");
+                        Some(format!("Failed to load: {error:#}"))
+                    }
+                };
+            }
+        });
+
+        if let Some(error) = &self.scenario_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        ui.horizontal(|ui| {
+            let mut fixed = self.run_length.is_some();
+
+            if ui.checkbox(&mut fixed, "Run length").changed() {
+                self.run_length = fixed.then_some(1000);
+            }
+
+            if let Some(ticks) = &mut self.run_length {
+                ui.add(DragValue::new(ticks).range(1..=u32::MAX).suffix(" ticks"));
+            }
+        })
+        .response
+        .on_hover_text("How long headless runs of the scenario last");
+    }
+
+    /// Writes the current setup of the experiment to the file at `scenario_path`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_scenario(&self) -> anyhow::Result<()> {
+        let simulation = &self.simulation;
+
+        let scenario = Scenario {
+            ticks: self.run_length,
+            domain: *simulation.config(),
+            time: TimeStep {
+                dt: Some(simulation.parameters.dt),
+                auto_subdivide: simulation.parameters.auto_subdivide,
+            },
+            boundaries: simulation.boundaries,
+            pml: simulation.pml,
+            medium: self.medium.clone(),
+            obstacles: self.obstacles,
+            initial: Some(simulation.initial_condition().clone()),
+            sources: simulation.sources.clone(),
+            camera: Some(CameraPose::from_camera(&self.camera)),
+        };
+
+        scenario.save(self.scenario_path.as_ref())
+    }
+
+    /// Sets up the experiment described by the file at `scenario_path`, starting it over.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_scenario(&mut self) -> anyhow::Result<()> {
+        let scenario = Scenario::load(self.scenario_path.as_ref())?;
+
+        scenario.domain.ensure_fits(self.max_grid_cells())?;
+        self.restore_snapshot(scenario.to_snapshot()?);
+
+        if let Some(camera) = &scenario.camera {
+            camera.apply(&mut self.camera);
+        }
+
+        let medium = &scenario.medium;
+        self.speed_preset = medium.preset.unwrap_or(SpeedMapPreset::Uniform);
+        self.base_speed = medium.speed;
+        self.speed_map_path = medium
+            .image
+            .as_ref()
+            .map(|image| image.display().to_string())
+            .unwrap_or_default();
+
+        if let Some(expression) = &medium.expression {
+            self.speed_expression = expression.clone();
+        }
+
+        self.obstacle_preset = scenario.obstacles.preset;
+        self.wall = scenario.obstacles.wall;
+
+        self.medium = scenario.medium;
+        self.obstacles = scenario.obstacles;
+        self.run_length = scenario.ticks;
+
+        Ok(())
+    }
+
+    /// Replaces the full simulation state, rebuilding the surface mesh if the domain changed.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn restore_snapshot(&mut self, snapshot: crate::simulation::snapshot::Snapshot) {
+        let config = snapshot.config;
+
+        self.simulation.restore(
+            &self.renderer.gpu.device,
+            &self.renderer.gpu.queue,
+            &self.renderer.pipelines,
+            snapshot,
+        );
+
+        self.pending_config = config;
+        self.renderer.set_simulation_config(&config);
+        self.timestep.reset();
+        self.statistics_history.clear();
+    }
+
+    /// Renders the controls for exporting the field as a one-off snapshot or as a time series.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        let recording = self.export_series.is_some();
+
+        ui.add_enabled_ui(!recording, |ui| {
+            Grid::new("export").show(ui, |ui| {
+                ui.label("Path");
+                ui.text_edit_singleline(&mut self.export_stem)
+                    .on_hover_text("File names are derived from this path, without an extension");
+                ui.end_row();
+
+                ui.label("Format");
+                ComboBox::from_id_salt("export_format")
+                    .selected_text(self.export_format.name())
+                    .show_ui(ui, |ui| {
+                        for format in ExportFormat::ALL {
+                            ui.selectable_value(&mut self.export_format, format, format.name());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Fields");
+                ui.horizontal(|ui| {
+                    ui.add_enabled(false, Checkbox::new(&mut true, "u"));
+                    ui.checkbox(&mut self.export_fields.velocity, "velocity");
+                    ui.checkbox(&mut self.export_fields.energy_density, "energy density");
+                });
+                ui.end_row();
+
+                ui.label("Series interval");
+                ui.add(
+                    DragValue::new(&mut self.export_interval)
+                        .range(1..=100_000)
+                        .suffix(" ticks"),
+                );
+                ui.end_row();
+            });
+        });
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!recording, Button::new("Export now"))
+                .clicked()
+            {
+                let result = self
+                    .export_field(self.export_fields)
+                    .and_then(|export| export.write(self.export_format, self.export_stem.as_ref()));
+
+                match result {
+                    Ok(paths) => log::info!("Exported the field to {paths:?}"),
+                    Err(error) => log::error!("Failed to export the field: {error:#}"),
+                }
+            }
+
+            if let Some(series) = &self.export_series {
+                if ui.button("Stop series").clicked() {
+                    log::info!(
+                        "Exported {} frames to {}",
+                        series.frame_count(),
+                        series.stem.display()
+                    );
+                    self.export_series = None;
+                }
+            } else if ui.button("Start series").clicked() {
+                self.export_series = Some(ExportSeries::new(
+                    self.export_stem.clone().into(),
+                    self.export_format,
+                    self.export_fields,
+                    self.export_interval,
+                ));
+            }
+        });
+
+        if let Some(series) = &self.export_series {
+            ui.label(format!(
+                "Recording: {} frames written, one every {} ticks",
+                series.frame_count(),
+                series.interval
+            ));
+        }
+    }
+
+    /// Reads the current state back from the GPU and derives the requested fields from it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_field(&self, fields: ExportFields) -> anyhow::Result<FieldExport> {
+        let gpu = &self.renderer.gpu;
+        let state = self.simulation.read_state(&gpu.device, &gpu.queue)?;
+
+        Ok(FieldExport::from_state(
+            self.simulation.config(),
+            &state,
+            self.simulation.speed_map(),
+            self.simulation.tick_dt(),
+            self.simulation.time(),
+            fields,
+        ))
+    }
+
+    /// Accounts for `substeps` ticks having run, writing the next frame of the exported series
+    /// once it is due.
+    ///
+    /// The series is stopped if a frame fails to be written.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn advance_export_series(&mut self, substeps: u32) {
+        let Some(mut series) = self.export_series.take() else {
+            return;
+        };
+
+        // unstable ticks are not run at all
+        if self.simulation.tick_is_stable() {
+            series.advance(substeps);
+        }
+
+        if series.is_due() {
+            let result = self
+                .export_field(series.fields)
+                .and_then(|export| series.write_frame(&export));
+
+            if let Err(error) = result {
+                log::error!("Stopped exporting the series: {error:#}");
+                return;
+            }
+        }
+
+        self.export_series = Some(series);
+    }
+
+    /// Renders the timestep controls alongside the Courant number and any stability warnings.
+    fn stability_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        let max_speed = self.simulation.speed_map().max_speed();
+        let stable_dt = WaveParameters::stable_dt(self.simulation.config(), max_speed);
+
+        let parameters = &mut self.simulation.parameters;
+
+        Grid::new("simulation_stability").show(ui, |ui| {
+            ui.label("dt");
+            ui.horizontal(|ui| {
+                ui.add(
+                    DragValue::new(&mut parameters.dt)
+                        .range(1e-6..=1.0)
+                        .speed(1e-5)
+                        .custom_formatter(|value, _| format!("{value:.2e}")),
+                );
+
+                if ui.button("Use stable dt").clicked() {
+                    parameters.dt = stable_dt;
+                }
+            });
+            ui.end_row();
+
+            ui.label("Auto subdivide");
+            ui.checkbox(&mut parameters.auto_subdivide, "");
+            ui.end_row();
+        });
+
+        let courant = self.simulation.courant_number();
+
+        ui.label(format!(
+            "Max wave speed: {max_speed:.3}, Courant number: {courant:.3} (limit {MAX_COURANT_NUMBER:.3})"
+        ));
+
+        if !self.simulation.tick_is_stable() {
+            ui.colored_label(
+                Color32::RED,
+                "Unstable timestep: the simulation is halted until dt is lowered",
+            );
+        } else if self.simulation.subdivisions() > 1 {
+            ui.colored_label(
+                Color32::YELLOW,
+                format!(
+                    "dt exceeds the stability limit, each step is split into {} ticks",
+                    self.simulation.subdivisions()
+                ),
+            );
+        }
+    }
+
+    /// Renders the controls for adding, editing and removing wave sources.
+    fn sources_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        let config = *self.simulation.config();
+        let center = [config.width / 2.0, config.depth / 2.0];
+        let expression_error = self.simulation.expression_error().map(str::to_owned);
+        let sources = &mut self.simulation.sources;
+
+        ui.add_enabled_ui(sources.len() < MAX_SOURCES, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Add point").clicked() {
+                    sources.push(Source::point(center, 2.0, 0.05));
+                }
+
+                if ui.button("Add line").clicked() {
+                    sources.push(Source {
+                        shape: SourceShape::Line {
+                            start: [0.1 * config.width, 0.25 * config.depth],
+                            end: [0.1 * config.width, 0.75 * config.depth],
+                        },
+                        ..Source::point(center, 2.0, 0.05)
+                    });
+                }
+
+                if ui.button("Add ring").clicked() {
+                    sources.push(Source {
+                        shape: SourceShape::Ring {
+                            center,
+                            radius: 0.1 * config.width.min(config.depth),
+                        },
+                        ..Source::point(center, 2.0, 0.05)
+                    });
+                }
+            });
+
+            if ui.button("Two coherent sources").clicked() {
+                let separation = 0.1 * config.depth;
+
+                sources.push(Source::point(
+                    [center[0], center[1] - separation],
+                    2.0,
+                    0.05,
+                ));
+                sources.push(Source::point(
+                    [center[0], center[1] + separation],
+                    2.0,
+                    0.05,
+                ));
+            }
+        });
+
+        let mut removed = None;
+
+        ScrollArea::vertical().show(ui, |ui| {
+            for (i, source) in sources.iter_mut().enumerate() {
+                CollapsingHeader::new(format!("Source {} ({})", i + 1, source.shape.name()))
+                    .id_salt(i)
+                    .show(ui, |ui| {
+                        Grid::new(("source", i)).show(ui, |ui| {
+                            Self::source_ui(ui, source, expression_error.as_deref());
+                        });
+
+                        if ui.button("Remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+            }
+        });
+
+        if let Some(i) = removed {
+            sources.remove(i);
+        }
+    }
+
+    /// Renders the editable properties of a single [`Source`] as rows of a grid.
+    ///
+    /// The drive expression is shown with the reason it cannot be compiled for the GPU, or else
+    /// with the `expression_error` of the simulation if any.
+    fn source_ui(ui: &mut egui::Ui, source: &mut Source, expression_error: Option<&str>) {
+        use egui::*;
+
+        let position_ui = |ui: &mut Ui, label: &str, position: &mut [f32; 2]| {
+            ui.label(label);
+            ui.horizontal(|ui| {
+                ui.add(DragValue::new(&mut position[0]).speed(0.01).prefix("x: "));
+                ui.add(DragValue::new(&mut position[1]).speed(0.01).prefix("z: "));
+            });
+            ui.end_row();
+        };
+
+        ui.label("Enabled");
+        ui.checkbox(&mut source.enabled, "");
+        ui.end_row();
+
+        match &mut source.shape {
+            SourceShape::Point { position } => position_ui(ui, "Position", position),
+            SourceShape::Line { start, end } => {
+                position_ui(ui, "Start", start);
+                position_ui(ui, "End", end);
+            }
+            SourceShape::Ring { center, radius } => {
+                position_ui(ui, "Center", center);
+
+                ui.label("Radius");
+                ui.add(DragValue::new(radius).range(0.0..=f32::MAX).speed(0.01));
+                ui.end_row();
+            }
+        }
+
+        ui.label("Waveform");
+        ComboBox::from_id_salt(ui.next_auto_id())
+            .selected_text(source.waveform.name())
+            .show_ui(ui, |ui| {
+                for waveform in Waveform::ALL {
+                    let name = waveform.name();
+                    ui.selectable_value(&mut source.waveform, waveform, name);
+                }
+
+                let is_expression = matches!(source.waveform, Waveform::Expression(_));
+
+                if ui.selectable_label(is_expression, "Expression").clicked() && !is_expression {
+                    let drive = Expression::parse(Waveform::DEFAULT_EXPRESSION)
+                        .expect("the default drive is a valid expression");
+
+                    source.waveform = Waveform::Expression(drive);
+                }
+            });
+        ui.end_row();
+
+        if let Waveform::Expression(drive) = &mut source.waveform {
+            ui.label("d(x, z, t)");
+            ui.vertical(|ui| {
+                expression_ui(ui, "drive", drive);
+
+                let error = drive.to_wgsl().err().map(|error| format!("{error:#}"));
+
+                if let Some(error) = error.as_deref().or(expression_error) {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+            });
+            ui.end_row();
+        }
+
+        ui.label("Frequency");
+        ui.add(
+            DragValue::new(&mut source.frequency)
+                .range(0.01..=100.0)
+                .speed(0.01)
+                .suffix(" Hz"),
+        );
+        ui.end_row();
+
+        ui.label("Amplitude");
+        ui.add(DragValue::new(&mut source.amplitude).speed(0.001));
+        ui.end_row();
+
+        ui.label("Phase");
+        ui.drag_angle(&mut source.phase);
+        ui.end_row();
+
+        ui.label("Start time");
+        ui.add(
+            DragValue::new(&mut source.start_time)
+                .range(0.0..=f32::MAX)
+                .speed(0.01),
+        );
+        ui.end_row();
+
+        let mut forever = source.end_time.is_infinite();
+
+        ui.label("End time");
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut forever, "Never").changed() {
+                source.end_time = if forever {
+                    f32::INFINITY
+                } else {
+                    source.start_time + 1.0
+                };
+            }
+
+            if !forever {
+                ui.add(
+                    DragValue::new(&mut source.end_time)
+                        .range(source.start_time..=f32::MAX)
+                        .speed(0.01),
+                );
+            }
+        });
+        ui.end_row();
+    }
+
+    /// Renders the controls for choosing the state the wave starts out from, resetting the
+    /// simulation to it whenever it changes.
+    fn initial_condition_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        let config = *self.simulation.config();
+        let mut condition = self.simulation.initial_condition().clone();
+        let mut kind = condition.kind();
+
+        let position_ui = |ui: &mut Ui, label: &str, position: &mut [f32; 2]| {
+            ui.label(label);
+            ui.horizontal(|ui| {
+                ui.add(DragValue::new(&mut position[0]).speed(0.01).prefix("x: "));
+                ui.add(DragValue::new(&mut position[1]).speed(0.01).prefix("z: "));
+            });
+            ui.end_row();
+        };
+
+        let length_ui = |ui: &mut Ui, label: &str, length: &mut f32| {
+            ui.label(label);
+            ui.add(
+                DragValue::new(length)
+                    .range(config.grid_spacing()..=f32::MAX)
+                    .speed(0.005),
+            );
+            ui.end_row();
+        };
+
+        Grid::new("initial_condition").show(ui, |ui| {
+            ui.label("Kind");
+            ComboBox::from_id_salt("initial_condition_kind")
+                .selected_text(kind.name())
+                .show_ui(ui, |ui| {
+                    for option in InitialConditionKind::ALL {
+                        ui.selectable_value(&mut kind, option, option.name());
+                    }
+                });
+            ui.end_row();
+
+            if kind != condition.kind() {
+                condition = InitialCondition::preset(kind, &config);
+            }
+
+            let amplitude = match &mut condition {
+                InitialCondition::GaussianBump {
+                    center,
+                    radius,
+                    amplitude,
+                } => {
+                    position_ui(ui, "Center", center);
+                    length_ui(ui, "Radius", radius);
+                    Some(amplitude)
+                }
+                InitialCondition::Ring {
+                    center,
+                    radius,
+                    width,
+                    amplitude,
+                } => {
+                    position_ui(ui, "Center", center);
+                    length_ui(ui, "Radius", radius);
+                    length_ui(ui, "Width", width);
+                    Some(amplitude)
+                }
+                InitialCondition::PlaneWave {
+                    center,
+                    direction,
+                    wavelength,
+                    length,
+                    amplitude,
+                } => {
+                    position_ui(ui, "Center", center);
+
+                    ui.label("Direction");
+                    ui.drag_angle(direction);
+                    ui.end_row();
+
+                    length_ui(ui, "Wavelength", wavelength);
+                    length_ui(ui, "Packet length", length);
+                    Some(amplitude)
+                }
+                InitialCondition::Eigenmode { m, n, amplitude } => {
+                    ui.label("Mode (m, n)");
+                    ui.horizontal(|ui| {
+                        ui.add(DragValue::new(m).range(1..=100));
+                        ui.add(DragValue::new(n).range(1..=100));
+                    });
+                    ui.end_row();
+                    Some(amplitude)
+                }
+                InitialCondition::Noise {
+                    seed,
+                    scale,
+                    amplitude,
+                } => {
+                    ui.label("Seed");
+                    ui.add(DragValue::new(seed));
+                    ui.end_row();
+
+                    length_ui(ui, "Scale", scale);
+                    Some(amplitude)
+                }
+                InitialCondition::Expression { displacement } => {
+                    ui.label("u(x, z, t)");
+                    expression_ui(ui, "initial_displacement", displacement);
+                    ui.end_row();
+                    None
+                }
+            };
+
+            if let Some(amplitude) = amplitude {
+                ui.label("Amplitude");
+                ui.add(DragValue::new(amplitude).speed(0.005));
+                ui.end_row();
+            }
+        });
+
+        if condition != *self.simulation.initial_condition() {
+            self.simulation
+                .set_initial_condition(&self.renderer.gpu.queue, condition);
+            self.timestep.reset();
+        }
+    }
+
+    /// Renders the controls for choosing the medium the waves travel through.
+    fn medium_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        Grid::new("simulation_medium").show(ui, |ui| {
+            ui.label("Medium");
+            ComboBox::from_id_salt("speed_preset")
+                .selected_text(self.speed_preset.name())
+                .show_ui(ui, |ui| {
+                    for preset in SpeedMapPreset::ALL {
+                        ui.selectable_value(&mut self.speed_preset, preset, preset.name());
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Wave speed");
+            ui.add(
+                DragValue::new(&mut self.base_speed)
+                    .range(0.05..=10.0)
+                    .speed(0.01),
+            );
+            ui.end_row();
+        });
+
+        if ui.button("Generate medium").clicked() {
+            let speed_map =
+                SpeedMap::from_preset(self.simulation.config(), self.speed_preset, self.base_speed);
+
+            self.simulation
+                .set_speed_map(&self.renderer.gpu.queue, speed_map);
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.medium = Medium {
+                    preset: Some(self.speed_preset),
+                    image: None,
+                    expression: None,
+                    speed: self.base_speed,
+                };
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("c(x, z)");
+            expression_ui(ui, "speed_expression", &mut self.speed_expression);
+
+            if ui.button("Generate").clicked() {
+                match SpeedMap::from_expression(self.simulation.config(), &self.speed_expression) {
+                    Ok(speed_map) => {
+                        self.simulation
+                            .set_speed_map(&self.renderer.gpu.queue, speed_map);
+
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            self.medium = Medium {
+                                preset: None,
+                                image: None,
+                                expression: Some(self.speed_expression.clone()),
+                                speed: self.base_speed,
+                            };
+                        }
+                    }
+                    Err(error) => log::error!("Failed to generate medium: {error:#}"),
+                }
+            }
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.speed_map_path)
+                .on_hover_text(
+                    "A PGM image mapping black to half and white to the full wave speed",
+                );
+
+            if ui.button("Load").clicked() {
+                let speed_map = std::fs::read(&self.speed_map_path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|bytes| {
+                        SpeedMap::from_pgm(
+                            self.simulation.config(),
+                            &bytes,
+                            0.5 * self.base_speed,
+                            self.base_speed,
+                        )
+                    });
+
+                match speed_map {
+                    Ok(speed_map) => {
+                        self.simulation
+                            .set_speed_map(&self.renderer.gpu.queue, speed_map);
+
+                        self.medium = Medium {
+                            preset: None,
+                            image: Some(self.speed_map_path.clone().into()),
+                            expression: None,
+                            speed: self.base_speed,
+                        };
+                        self.speed_map_error = None;
+                    }
+                    Err(error) => {
+                        log::error!("Failed to load speed map: {error:#}");
+                        self.speed_map_error = Some(format!("Failed to load: {error:#}"));
+                    }
+                }
+            }
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(error) = &self.speed_map_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        ui.separator();
+        self.stability_ui(ui);
+
+        ui.separator();
+
+        Grid::new("simulation_obstacles").show(ui, |ui| {
+            ui.label("Obstacles");
+            ComboBox::from_id_salt("obstacle_preset")
+                .selected_text(self.obstacle_preset.name())
+                .show_ui(ui, |ui| {
+                    for preset in ObstaclePreset::ALL {
+                        ui.selectable_value(&mut self.obstacle_preset, preset, preset.name());
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Walls");
+            ComboBox::from_id_salt("wall")
+                .selected_text(self.wall.name())
+                .show_ui(ui, |ui| {
+                    for wall in Wall::ALL {
+                        ui.selectable_value(&mut self.wall, wall, wall.name());
+                    }
+                });
+            ui.end_row();
+        });
+
+        if ui.button("Place obstacles").clicked() {
+            let obstacles = ObstacleMask::from_preset(
+                self.simulation.config(),
+                self.obstacle_preset,
+                self.wall,
+            );
+
+            self.simulation
+                .set_obstacles(&self.renderer.gpu.queue, obstacles);
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.obstacles = Obstacles {
+                    preset: self.obstacle_preset,
+                    wall: self.wall,
+                };
+            }
+        }
+    }
+
+    /// Returns the most cells the GPU supports along either axis of the grid.
+    fn max_grid_cells(&self) -> u32 {
+        self.renderer.gpu.device.limits().max_texture_dimension_2d
+    }
+
+    /// Rebuilds the simulation and its surface mesh to match a new domain.
+    fn set_simulation_config(&mut self, config: SimulationConfig) {
+        self.simulation.reconfigure(
+            &self.renderer.gpu.device,
+            &self.renderer.gpu.queue,
+            &self.renderer.pipelines,
+            config,
+        );
+
+        self.renderer.set_simulation_config(&config);
+        self.timestep.reset();
+    }
+
+    /// Restores the initial state of the wave, keeping the medium, obstacles and sources.
+    fn reset_simulation(&mut self) {
+        self.simulation.reset(&self.renderer.gpu.queue);
+        self.timestep.reset();
+    }
+
+    /// Resizes the state of the app to match the new window size.
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        self.renderer.resize(size);
+        self.camera.resize(size);
+
+        // frames stop while minimised, and resume once the window is restored
+        self.window.request_redraw();
+    }
+}
+
+/// Manages the creation and lifecycle of the actual [`App`].
+pub struct AppHandler {
+    /// A proxy to create the app when dealing with async events (only needed on web).
+    #[cfg(target_arch = "wasm32")]
+    proxy: Option<EventLoopProxy<App>>,
+
+    /// The initialized Some(app), or None if the window hasn't been created yet.
+    app: Option<App>,
+}
+
+impl AppHandler {
+    /// Creates a new [`AppHandler`], the main entry point to the app.
+    pub fn new(#[cfg(target_arch = "wasm32")] proxy: EventLoopProxy<App>) -> Self {
+        Self {
+            #[cfg(target_arch = "wasm32")]
+            proxy: Some(proxy),
+            app: None,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for AppHandler {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -0,0 +1,530 @@
+use std::{fmt::Write as _, path::PathBuf};
+
+use anyhow::{Context, bail, ensure};
+use web_time::Instant;
+use wgpu::*;
+
+use crate::{
+    renderer::{gpu_context::GpuContext, pipelines::Pipelines, shaders::Shaders},
+    scenario::Scenario,
+    simulation::{
+        MAX_SUBSTEPS, WaveSimulation,
+        config::SimulationConfig,
+        cpu::CpuSimulation,
+        export::{ExportFields, ExportFormat, ExportSeries, FieldExport},
+        snapshot::Snapshot,
+        speed_map::SpeedMap,
+        statistics::WaveStatistics,
+    },
+};
+
+/// The usage of the `batch` binary, printed for `--help`.
+pub const USAGE: &str = "\
+Runs a wave simulation without a window and writes its results to disk.
+
+Usage: batch [options]
+
+Scenario:
+  --scenario <path>          Run the experiment described by a scenario file
+  --snapshot <path>          Start from a saved snapshot instead of the default state
+  --width <units>            The width of the domain, unless starting from a file
+  --depth <units>            The depth of the domain, unless starting from a file
+  --cells-per-unit <cells>   The resolution of the domain, unless starting from a file
+  --ticks <ticks>            The number of ticks to run, overriding the scenario's
+                             [default: 1000]
+  --cpu                      Run on the CPU solver even if a GPU is available
+
+Output:
+  --export <path>            Export the field to files derived from this path
+  --format <npy|vti>         The format of exported fields [default: npy]
+  --velocity                 Also export the velocity
+  --energy-density           Also export the energy density
+  --interval <ticks>         Export a numbered series every this many ticks, instead of only
+                             the final state
+  --metrics <path>           Write the statistics of the wave over time as CSV
+  --metrics-interval <ticks> The ticks between two rows of statistics [default: 10]
+  --save-snapshot <path>     Save a snapshot of the final state
+  -h, --help                 Print this help";
+
+/// What a headless run simulates, and what it writes to disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOptions {
+    /// The scenario file describing the experiment, if any.
+    pub scenario: Option<PathBuf>,
+    /// The snapshot the run starts from, if any.
+    pub snapshot: Option<PathBuf>,
+    /// The extent and resolution of the domain, unless starting from a file.
+    pub config: SimulationConfig,
+    /// The number of ticks to run, or `None` for the length of the scenario.
+    pub ticks: Option<u32>,
+    /// Whether to run on the CPU solver even if a GPU is available.
+    pub force_cpu: bool,
+
+    /// The path exported file names are derived from, if the field is exported.
+    pub export: Option<PathBuf>,
+    /// The format the field is exported in.
+    pub format: ExportFormat,
+    /// The fields exported alongside the displacement u.
+    pub fields: ExportFields,
+    /// The ticks between two frames of an exported series, or `None` to only export the final
+    /// state.
+    pub interval: Option<u32>,
+
+    /// The path of the CSV file the statistics are written to, if any.
+    pub metrics: Option<PathBuf>,
+    /// The ticks between two rows of statistics.
+    pub metrics_interval: u32,
+
+    /// The path the snapshot of the final state is saved to, if any.
+    pub save_snapshot: Option<PathBuf>,
+}
+
+/// A simulation run on the GPU if one is available, or on the [`CpuSimulation`] otherwise.
+pub enum BatchSimulation {
+    /// The simulation runs on a device without any surface.
+    Gpu {
+        device: Device,
+        queue: Queue,
+        pipelines: Box<Pipelines>,
+        simulation: Box<WaveSimulation>,
+    },
+    /// The simulation runs on the CPU reference solver.
+    Cpu(Box<CpuSimulation>),
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            scenario: None,
+            snapshot: None,
+            config: SimulationConfig::default(),
+            ticks: None,
+            force_cpu: false,
+            export: None,
+            format: ExportFormat::default(),
+            fields: ExportFields::default(),
+            interval: None,
+            metrics: None,
+            metrics_interval: 10,
+            save_snapshot: None,
+        }
+    }
+}
+
+impl BatchOptions {
+    /// Parses the command line arguments following the name of the binary.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("missing a value for {arg}"))
+            };
+
+            match arg.as_str() {
+                "--scenario" => options.scenario = Some(value()?.into()),
+                "--snapshot" => options.snapshot = Some(value()?.into()),
+                "--width" => options.config.width = parse_value(&arg, &value()?)?,
+                "--depth" => options.config.depth = parse_value(&arg, &value()?)?,
+                "--cells-per-unit" => options.config.cells_per_unit = parse_value(&arg, &value()?)?,
+                "--ticks" => options.ticks = Some(parse_value(&arg, &value()?)?),
+                "--cpu" => options.force_cpu = true,
+                "--export" => options.export = Some(value()?.into()),
+                "--format" => {
+                    options.format = match value()?.as_str() {
+                        "npy" => ExportFormat::Npy,
+                        "vti" => ExportFormat::Vti,
+                        format => bail!("unknown export format {format:?}, expected npy or vti"),
+                    }
+                }
+                "--velocity" => options.fields.velocity = true,
+                "--energy-density" => options.fields.energy_density = true,
+                "--interval" => options.interval = Some(parse_value(&arg, &value()?)?),
+                "--metrics" => options.metrics = Some(value()?.into()),
+                "--metrics-interval" => options.metrics_interval = parse_value(&arg, &value()?)?,
+                "--save-snapshot" => options.save_snapshot = Some(value()?.into()),
+                _ => bail!("unknown argument {arg:?}, see --help"),
+            }
+        }
+
+        let config = &options.config;
+        ensure!(
+            config.width > 0.0 && config.depth > 0.0 && config.cells_per_unit > 0.0,
+            "the extent and resolution of the domain must be positive"
+        );
+        ensure!(
+            options.scenario.is_none() || options.snapshot.is_none(),
+            "--scenario and --snapshot cannot be combined"
+        );
+        ensure!(options.interval != Some(0), "--interval must be at least 1");
+        ensure!(
+            options.metrics_interval > 0,
+            "--metrics-interval must be at least 1"
+        );
+        ensure!(
+            options.interval.is_none() || options.export.is_some(),
+            "--interval requires --export"
+        );
+
+        Ok(options)
+    }
+}
+
+impl BatchOptions {
+    /// The number of ticks run if neither the options nor the scenario set one.
+    pub const DEFAULT_TICKS: u32 = 1000;
+}
+
+impl BatchSimulation {
+    /// Creates a simulation of the default state on a device without any surface, falling back to
+    /// the CPU solver if no suitable GPU is available or `force_cpu` is set.
+    pub fn new(config: SimulationConfig, force_cpu: bool) -> Self {
+        if force_cpu {
+            return Self::Cpu(Box::new(CpuSimulation::new(config)));
+        }
+
+        match pollster::block_on(GpuContext::new_headless()) {
+            Ok((device, _)) if let Err(error) = config.ensure_fits(max_grid_cells(&device)) => {
+                log::warn!("{error}, falling back to the CPU solver");
+
+                Self::Cpu(Box::new(CpuSimulation::new(config)))
+            }
+            Ok((device, queue)) => {
+                let shaders = Shaders::new(&device);
+                let pipelines = Pipelines::new(&device, &shaders);
+                let simulation = WaveSimulation::new(&device, &queue, &pipelines, config);
+
+                Self::Gpu {
+                    device,
+                    queue,
+                    pipelines: Box::new(pipelines),
+                    simulation: Box::new(simulation),
+                }
+            }
+            Err(error) => {
+                log::warn!("No usable GPU, falling back to the CPU solver: {error:#}");
+
+                Self::Cpu(Box::new(CpuSimulation::new(config)))
+            }
+        }
+    }
+
+    /// Returns a human readable name of the solver the simulation runs on.
+    pub fn backend_name(&self) -> &'static str {
+        match self {
+            Self::Gpu { .. } => "GPU",
+            Self::Cpu(_) => "CPU",
+        }
+    }
+
+    /// Returns the extent and resolution of the simulated domain.
+    pub fn config(&self) -> &SimulationConfig {
+        match self {
+            Self::Gpu { simulation, .. } => simulation.config(),
+            Self::Cpu(simulation) => simulation.config(),
+        }
+    }
+
+    /// Returns the simulation time elapsed since the initial state.
+    pub fn time(&self) -> f32 {
+        match self {
+            Self::Gpu { simulation, .. } => simulation.time(),
+            Self::Cpu(simulation) => simulation.time(),
+        }
+    }
+
+    /// Returns the wave speed of every cell.
+    pub fn speed_map(&self) -> &SpeedMap {
+        match self {
+            Self::Gpu { simulation, .. } => simulation.speed_map(),
+            Self::Cpu(simulation) => simulation.speed_map(),
+        }
+    }
+
+    /// Returns the duration of a single tick that is actually run, after any subdivision.
+    pub fn tick_dt(&self) -> f32 {
+        match self {
+            Self::Gpu { simulation, .. } => simulation.tick_dt(),
+            Self::Cpu(simulation) => simulation.tick_dt(),
+        }
+    }
+
+    /// Returns whether ticks are stable, or the simulation refuses to run.
+    pub fn tick_is_stable(&self) -> bool {
+        match self {
+            Self::Gpu { simulation, .. } => simulation.tick_is_stable(),
+            Self::Cpu(simulation) => simulation.tick_is_stable(),
+        }
+    }
+
+    /// Restores the full state of the simulation from a [`Snapshot`].
+    ///
+    /// Fails if the GPU cannot hold the grid of the snapshot.
+    pub fn restore(&mut self, snapshot: Snapshot) -> anyhow::Result<()> {
+        match self {
+            Self::Gpu {
+                device,
+                queue,
+                pipelines,
+                simulation,
+            } => {
+                snapshot.config.ensure_fits(max_grid_cells(device))?;
+                simulation.restore(device, queue, pipelines, snapshot);
+            }
+            Self::Cpu(simulation) => simulation.restore(snapshot),
+        }
+
+        Ok(())
+    }
+
+    /// Captures the full state of the simulation as a [`Snapshot`], blocking until it has been
+    /// read back.
+    pub fn snapshot(&self) -> anyhow::Result<Snapshot> {
+        match self {
+            Self::Gpu {
+                device,
+                queue,
+                simulation,
+                ..
+            } => simulation.snapshot(device, queue),
+            Self::Cpu(simulation) => Ok(simulation.snapshot()),
+        }
+    }
+
+    /// Returns u(t) and u(t - dt) of every cell, blocking until it has been read back.
+    pub fn state(&self) -> anyhow::Result<Vec<[f32; 2]>> {
+        match self {
+            Self::Gpu {
+                device,
+                queue,
+                simulation,
+                ..
+            } => simulation.read_state(device, queue),
+            Self::Cpu(simulation) => Ok(simulation.state().to_vec()),
+        }
+    }
+
+    /// Measures the [`WaveStatistics`] of the current state, blocking until they have been read
+    /// back.
+    pub fn statistics(&mut self) -> anyhow::Result<WaveStatistics> {
+        match self {
+            Self::Gpu {
+                device,
+                queue,
+                pipelines,
+                simulation,
+            } => {
+                let mut encoder =
+                    device.create_command_encoder(&CommandEncoderDescriptor::default());
+                simulation.measure_statistics(queue, &mut encoder, pipelines);
+                queue.submit([encoder.finish()]);
+
+                simulation.begin_statistics_readback();
+                device.poll(PollType::wait_indefinitely())?;
+
+                simulation
+                    .poll_statistics(device)
+                    .context("failed to read back the statistics")
+            }
+            Self::Cpu(simulation) => Ok(simulation.statistics()),
+        }
+    }
+
+    /// Advances the simulation by `ticks` ticks.
+    pub fn step(&mut self, ticks: u32) {
+        let mut remaining = ticks;
+
+        // a single step runs at most `MAX_SUBSTEPS` ticks on either backend
+        while remaining > 0 {
+            let substeps = remaining.min(MAX_SUBSTEPS);
+
+            match self {
+                Self::Gpu {
+                    device,
+                    queue,
+                    pipelines,
+                    simulation,
+                } => {
+                    let mut encoder =
+                        device.create_command_encoder(&CommandEncoderDescriptor::default());
+                    simulation.step(device, queue, &mut encoder, pipelines, substeps);
+                    queue.submit([encoder.finish()]);
+                }
+                Self::Cpu(simulation) => simulation.step(substeps),
+            }
+
+            remaining -= substeps;
+        }
+    }
+
+    /// Derives the requested fields from the current state, blocking until it has been read
+    /// back.
+    pub fn export(&self, fields: ExportFields) -> anyhow::Result<FieldExport> {
+        Ok(FieldExport::from_state(
+            self.config(),
+            &self.state()?,
+            self.speed_map(),
+            self.tick_dt(),
+            self.time(),
+            fields,
+        ))
+    }
+}
+
+/// Runs the scenario described by `options` to completion, writing all requested outputs.
+///
+/// The statistics and the exported series are sampled before the first tick, and then every
+/// interval until the last one.
+pub fn run(options: &BatchOptions) -> anyhow::Result<()> {
+    let scenario = options
+        .scenario
+        .as_deref()
+        .map(Scenario::load)
+        .transpose()?;
+
+    let config = scenario
+        .as_ref()
+        .map_or(options.config, |scenario| scenario.domain);
+    let run_length = options
+        .ticks
+        .or(scenario.as_ref().and_then(|scenario| scenario.ticks))
+        .unwrap_or(BatchOptions::DEFAULT_TICKS);
+
+    let mut simulation = BatchSimulation::new(config, options.force_cpu);
+
+    if let Some(scenario) = &scenario {
+        simulation.restore(scenario.to_snapshot()?)?;
+    }
+
+    if let Some(path) = &options.snapshot {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("failed to read the snapshot {}", path.display()))?;
+        let snapshot = Snapshot::from_bytes(&bytes)
+            .with_context(|| format!("failed to load the snapshot {}", path.display()))?;
+
+        simulation
+            .restore(snapshot)
+            .with_context(|| format!("failed to restore the snapshot {}", path.display()))?;
+    }
+
+    ensure!(
+        simulation.tick_is_stable(),
+        "the time step of the scenario is unstable, so the simulation would not advance"
+    );
+
+    let (width, depth) = simulation.config().grid_size();
+    log::info!(
+        "Running {run_length} ticks on a {width}x{depth} grid on the {}",
+        simulation.backend_name()
+    );
+
+    let mut series = options
+        .export
+        .clone()
+        .zip(options.interval)
+        .map(|(stem, interval)| ExportSeries::new(stem, options.format, options.fields, interval));
+
+    let mut metrics = options.metrics.as_ref().map(|_| {
+        String::from("time,kinetic_energy,potential_energy,total_energy,min,max,mean,rms\n")
+    });
+
+    let start = Instant::now();
+    let mut ticks_run = 0;
+
+    loop {
+        if let Some(metrics) = &mut metrics
+            && ticks_run % options.metrics_interval == 0
+        {
+            let statistics = simulation.statistics()?;
+
+            writeln!(
+                metrics,
+                "{},{},{},{},{},{},{},{}",
+                statistics.time,
+                statistics.kinetic_energy,
+                statistics.potential_energy,
+                statistics.total_energy(),
+                statistics.min,
+                statistics.max,
+                statistics.mean,
+                statistics.rms
+            )
+            .unwrap();
+        }
+
+        if let Some(series) = &mut series
+            && series.is_due()
+        {
+            series.write_frame(&simulation.export(series.fields)?)?;
+        }
+
+        if ticks_run == run_length {
+            break;
+        }
+
+        // run up to the next tick anything is sampled at
+        let mut ticks = (run_length - ticks_run)
+            .min(options.metrics_interval - ticks_run % options.metrics_interval);
+
+        if let Some(series) = &series {
+            ticks = ticks.min(series.ticks_until_next());
+        }
+
+        simulation.step(ticks);
+        ticks_run += ticks;
+
+        if let Some(series) = &mut series {
+            series.advance(ticks);
+        }
+    }
+
+    log::info!(
+        "Ran {ticks_run} ticks in {:.2?}, reaching t = {}",
+        start.elapsed(),
+        simulation.time()
+    );
+
+    if let Some(series) = &series {
+        log::info!(
+            "Exported {} frames to {}",
+            series.frame_count(),
+            series.stem.display()
+        );
+    } else if let Some(stem) = &options.export {
+        let paths = simulation
+            .export(options.fields)?
+            .write(options.format, stem)?;
+        log::info!("Exported the field to {paths:?}");
+    }
+
+    if let (Some(path), Some(metrics)) = (&options.metrics, metrics) {
+        std::fs::write(path, metrics)
+            .with_context(|| format!("failed to write the metrics to {}", path.display()))?;
+        log::info!("Wrote the statistics to {}", path.display());
+    }
+
+    if let Some(path) = &options.save_snapshot {
+        std::fs::write(path, simulation.snapshot()?.to_bytes())
+            .with_context(|| format!("failed to save the snapshot to {}", path.display()))?;
+        log::info!("Saved the final state to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Returns the most cells `device` supports along either axis of the grid.
+fn max_grid_cells(device: &Device) -> u32 {
+    device.limits().max_texture_dimension_2d
+}
+
+/// Parses the value of a command line option, naming the option if it is invalid.
+fn parse_value<T: std::str::FromStr>(option: &str, value: &str) -> anyhow::Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value
+        .parse()
+        .with_context(|| format!("invalid value {value:?} for {option}"))
+}
@@ -0,0 +1,26 @@
+use std::process::ExitCode;
+
+use gpu_template::batch::{self, BatchOptions};
+
+/// Runs a scenario without a window, for servers and scripts.
+fn main() -> ExitCode {
+    env_logger::builder()
+        .filter(Some("gpu_template"), log::LevelFilter::Info)
+        .format_timestamp(None)
+        .init();
+
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", batch::USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match BatchOptions::parse(args).and_then(|options| batch::run(&options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
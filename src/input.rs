@@ -1,15 +1,38 @@
 use std::{collections::HashSet, sync::Arc};
 
 use winit::{
-    event::{DeviceEvent, ElementState, KeyEvent, WindowEvent},
+    event::{DeviceEvent, ElementState, KeyEvent, MouseButton, WindowEvent},
     keyboard::{KeyCode, PhysicalKey},
     window::{CursorGrabMode, Window},
 };
 
+/// What clicking inside the window does.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InteractionMode {
+    /// Clicking grabs the cursor to look and move around.
+    #[default]
+    Camera,
+    /// Left clicking (and dragging) pokes the water surface, while right clicking still grabs the
+    /// cursor.
+    Poke,
+    /// Left clicking places a probe on the water surface, while right clicking still grabs the
+    /// cursor.
+    Probe,
+    /// Left clicking and dragging draws the line cut across the water surface, while right
+    /// clicking still grabs the cursor.
+    LineCut,
+}
+
 /// Manages an up to date representation of all input devices.
 pub struct InputState {
     /// The keys currently being held down.
     pub keys_held: HashSet<KeyCode>,
+    /// The keys pressed (or repeated) since the last frame, used for shortcuts.
+    keys_pressed: HashSet<KeyCode>,
+    /// The mouse buttons currently being held down.
+    pub buttons_held: HashSet<MouseButton>,
+    /// The mouse buttons pressed since the last frame.
+    buttons_pressed: HashSet<MouseButton>,
 
     /// The last known mouse position.
     pub last_mouse: Option<(f32, f32)>,
@@ -22,6 +45,23 @@ pub struct InputState {
 
     /// Represents whether the app currently has focus or not.
     pub focused: bool,
+    /// What clicking inside the window currently does.
+    pub interaction: InteractionMode,
+}
+
+impl InteractionMode {
+    /// All interaction modes, in the order they are presented to the user.
+    pub const ALL: [Self; 4] = [Self::Camera, Self::Poke, Self::Probe, Self::LineCut];
+
+    /// Returns a human readable name of the interaction mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Camera => "Camera",
+            Self::Poke => "Poke",
+            Self::Probe => "Place probes",
+            Self::LineCut => "Draw line cut",
+        }
+    }
 }
 
 impl InputState {
@@ -29,9 +69,13 @@ impl InputState {
     pub fn new(window: Arc<Window>) -> Self {
         Self {
             keys_held: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            buttons_held: HashSet::new(),
+            buttons_pressed: HashSet::new(),
             last_mouse: None,
             mouse_delta: (0.0, 0.0),
             focused: false,
+            interaction: InteractionMode::default(),
             window,
         }
     }
@@ -53,15 +97,46 @@ impl InputState {
                     return;
                 }
 
+                if *code == KeyCode::KeyP && *state == ElementState::Pressed {
+                    self.set_interaction(match self.interaction {
+                        InteractionMode::Camera => InteractionMode::Poke,
+                        InteractionMode::Poke => InteractionMode::Probe,
+                        InteractionMode::Probe => InteractionMode::LineCut,
+                        InteractionMode::LineCut => InteractionMode::Camera,
+                    });
+                }
+
                 match state {
-                    ElementState::Pressed => self.keys_held.insert(*code),
-                    ElementState::Released => self.keys_held.remove(code),
+                    ElementState::Pressed => {
+                        self.keys_held.insert(*code);
+                        self.keys_pressed.insert(*code);
+                    }
+                    ElementState::Released => {
+                        self.keys_held.remove(code);
+                    }
                 };
             }
 
-            WindowEvent::MouseInput { .. } => {
-                self.set_focused(true);
-            }
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => {
+                    self.buttons_held.insert(*button);
+                    self.buttons_pressed.insert(*button);
+
+                    let grabs_cursor = match self.interaction {
+                        InteractionMode::Camera => true,
+                        InteractionMode::Poke
+                        | InteractionMode::Probe
+                        | InteractionMode::LineCut => *button == MouseButton::Right,
+                    };
+
+                    if grabs_cursor {
+                        self.set_focused(true);
+                    }
+                }
+                ElementState::Released => {
+                    self.buttons_held.remove(button);
+                }
+            },
 
             WindowEvent::CursorMoved { position, .. } => self.last_mouse = Some((*position).into()),
 
@@ -76,6 +151,33 @@ impl InputState {
         }
     }
 
+    /// Returns whether the key was pressed (or repeated) since the last frame.
+    pub fn was_pressed(&self, code: KeyCode) -> bool {
+        self.keys_pressed.contains(&code)
+    }
+
+    /// Returns whether the mouse button was pressed since the last frame.
+    pub fn was_clicked(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    /// Forgets about the keys and buttons pressed during the frame, to be called once it has been
+    /// handled.
+    pub fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.buttons_pressed.clear();
+    }
+
+    /// Switches to the given [`InteractionMode`], releasing the cursor so it can be used to poke,
+    /// place probes or draw the line cut.
+    pub fn set_interaction(&mut self, interaction: InteractionMode) {
+        self.interaction = interaction;
+
+        if interaction != InteractionMode::Camera && self.focused {
+            self.set_focused(false);
+        }
+    }
+
     /// Sets the state of focused, updating the cursor state as needed.
     pub fn set_focused(&mut self, focused: bool) {
         self.focused = focused;
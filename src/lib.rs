@@ -1,6 +1,9 @@
 pub mod application;
+pub mod batch;
 pub mod input;
+pub mod plot;
 pub mod renderer;
+pub mod scenario;
 pub mod simulation;
 pub mod timer;
 
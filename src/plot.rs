@@ -0,0 +1,269 @@
+use egui::{Align2, Color32, FontId, Pos2, Rect, Response, Sense, Shape, Stroke, Ui, pos2, vec2};
+
+/// A named line drawn by a [`LinePlot`].
+#[derive(Debug, Clone)]
+pub struct Series {
+    /// The name of the line shown in the legend.
+    pub name: String,
+    /// The color the line is drawn in.
+    pub color: Color32,
+    /// The (x, y) points of the line, in increasing order of x.
+    pub points: Vec<[f32; 2]>,
+}
+
+/// A minimal line plot drawn with the egui painter, fitting both axes to the plotted points.
+///
+/// Hovering the plot shows the value of every line closest to the cursor.
+#[derive(Debug, Clone)]
+pub struct LinePlot {
+    /// The lines being plotted.
+    series: Vec<Series>,
+    /// The height of the plot (in points).
+    height: f32,
+    /// The unit appended to values along the X axis.
+    x_unit: String,
+}
+
+impl Default for LinePlot {
+    fn default() -> Self {
+        Self {
+            series: Vec::new(),
+            height: 120.0,
+            x_unit: String::new(),
+        }
+    }
+}
+
+impl LinePlot {
+    /// The space left around the plotted lines (in points).
+    const MARGIN: f32 = 6.0;
+
+    /// Creates an empty [`LinePlot`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the height of the plot (in points).
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Sets the unit appended to values along the X axis.
+    pub fn x_unit(mut self, unit: impl Into<String>) -> Self {
+        self.x_unit = unit.into();
+        self
+    }
+
+    /// Adds a line through `points` to the plot.
+    pub fn series(
+        mut self,
+        name: impl Into<String>,
+        color: Color32,
+        points: impl IntoIterator<Item = [f32; 2]>,
+    ) -> Self {
+        self.series.push(Series {
+            name: name.into(),
+            color,
+            points: points
+                .into_iter()
+                .filter(|point| point.iter().all(|value| value.is_finite()))
+                .collect(),
+        });
+        self
+    }
+
+    /// Draws the plot across the available width.
+    pub fn show(self, ui: &mut Ui) -> Response {
+        let (rect, response) =
+            ui.allocate_exact_size(vec2(ui.available_width(), self.height), Sense::hover());
+
+        let visuals = ui.visuals();
+        let painter = ui.painter_at(rect);
+        let font = FontId::monospace(10.0);
+        let text_color = visuals.weak_text_color();
+
+        painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+        let Some((x_range, y_range)) = self.bounds() else {
+            painter.text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                "No data",
+                font,
+                text_color,
+            );
+
+            return response;
+        };
+
+        let area = rect.shrink(Self::MARGIN);
+        let to_screen = |[x, y]: [f32; 2]| {
+            pos2(
+                area.left() + (x - x_range[0]) / (x_range[1] - x_range[0]) * area.width(),
+                area.bottom() - (y - y_range[0]) / (y_range[1] - y_range[0]) * area.height(),
+            )
+        };
+
+        // the zero line, if in view
+        if y_range[0] < 0.0 && y_range[1] > 0.0 {
+            let y = to_screen([x_range[0], 0.0]).y;
+
+            painter.hline(
+                area.x_range(),
+                y,
+                Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color),
+            );
+        }
+
+        for series in &self.series {
+            let points = series.points.iter().copied().map(to_screen).collect();
+
+            painter.add(Shape::line(points, Stroke::new(1.5, series.color)));
+        }
+
+        // axis extents
+        painter.text(
+            area.left_top(),
+            Align2::LEFT_TOP,
+            format_value(y_range[1]),
+            font.clone(),
+            text_color,
+        );
+        painter.text(
+            area.left_bottom(),
+            Align2::LEFT_BOTTOM,
+            format_value(y_range[0]),
+            font.clone(),
+            text_color,
+        );
+        painter.text(
+            area.right_bottom(),
+            Align2::RIGHT_BOTTOM,
+            format!("{}{}", format_value(x_range[1]), self.x_unit),
+            font.clone(),
+            text_color,
+        );
+
+        // legend
+        for (i, series) in self.series.iter().enumerate() {
+            painter.text(
+                area.right_top() + vec2(0.0, i as f32 * font.size * 1.2),
+                Align2::RIGHT_TOP,
+                &series.name,
+                font.clone(),
+                series.color,
+            );
+        }
+
+        if let Some(cursor) = response.hover_pos() {
+            self.show_cursor_values(ui, area, cursor, x_range, &font);
+        }
+
+        response
+    }
+
+    /// Marks the cursor position and lists the value of every line closest to it.
+    fn show_cursor_values(
+        &self,
+        ui: &Ui,
+        area: Rect,
+        cursor: Pos2,
+        x_range: [f32; 2],
+        font: &FontId,
+    ) {
+        let painter = ui.painter_at(area.expand(Self::MARGIN));
+        let visuals = ui.visuals();
+
+        let x = x_range[0] + (cursor.x - area.left()) / area.width() * (x_range[1] - x_range[0]);
+
+        painter.vline(
+            cursor.x,
+            area.y_range(),
+            Stroke::new(1.0, visuals.weak_text_color()),
+        );
+
+        let lines = std::iter::once((
+            format!("{}{}", format_value(x), self.x_unit),
+            visuals.text_color(),
+        ))
+        .chain(self.series.iter().filter_map(|series| {
+            // the points are ordered by x, so the closest one neighbours the insertion point
+            let index = series.points.partition_point(|point| point[0] < x);
+            let closest = [index.saturating_sub(1), index]
+                .into_iter()
+                .filter_map(|index| series.points.get(index))
+                .min_by(|a, b| (a[0] - x).abs().total_cmp(&(b[0] - x).abs()))?;
+
+            Some((
+                format!("{}: {}", series.name, format_value(closest[1])),
+                series.color,
+            ))
+        }));
+
+        // keep the readout on the side of the cursor with more room
+        let (anchor, offset) = if cursor.x < area.center().x {
+            (Align2::LEFT_TOP, 6.0)
+        } else {
+            (Align2::RIGHT_TOP, -6.0)
+        };
+
+        for (i, (text, color)) in lines.enumerate() {
+            painter.text(
+                pos2(cursor.x + offset, area.top() + i as f32 * font.size * 1.2),
+                anchor,
+                text,
+                font.clone(),
+                color,
+            );
+        }
+    }
+
+    /// Returns the extent of all plotted points across the X and Y axes, leaving some headroom
+    /// above and below the lines, or `None` if there are no points.
+    fn bounds(&self) -> Option<([f32; 2], [f32; 2])> {
+        let points = self.series.iter().flat_map(|series| &series.points);
+
+        let (min, max) = points.fold(
+            ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]),
+            |(min, max), point| {
+                (
+                    [min[0].min(point[0]), min[1].min(point[1])],
+                    [max[0].max(point[0]), max[1].max(point[1])],
+                )
+            },
+        );
+
+        if min[0] > max[0] {
+            return None;
+        }
+
+        // a single point or a flat line still needs a non-empty range to be drawn in
+        let widen = |min: f32, max: f32| {
+            let padding = if max > min {
+                0.05 * (max - min)
+            } else {
+                0.1 * min.abs().max(1e-6)
+            };
+
+            [min - padding, max + padding]
+        };
+
+        let x_range = if max[0] > min[0] {
+            [min[0], max[0]]
+        } else {
+            widen(min[0], max[0])
+        };
+
+        Some((x_range, widen(min[1], max[1])))
+    }
+}
+
+/// Formats a value compactly, switching to scientific notation for very large or small values.
+fn format_value(value: f32) -> String {
+    if value == 0.0 || (1e-2..1e4).contains(&value.abs()) {
+        format!("{value:.3}")
+    } else {
+        format!("{value:.2e}")
+    }
+}
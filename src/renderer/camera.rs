@@ -1,6 +1,6 @@
 use std::f32::consts::FRAC_PI_2;
 
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3, Vec3Swizzles, Vec4Swizzles, vec4};
 use wgpu::{BindGroupDescriptor, BindGroupEntry, BufferDescriptor, BufferUsages, Device, Queue};
 use winit::{dpi::PhysicalSize, keyboard::KeyCode};
 
@@ -54,6 +54,45 @@ impl Camera {
         )
     }
 
+    /// Returns the direction of the ray leaving the camera through the given cursor position (in
+    /// physical pixels) on a surface of the given size.
+    pub fn cursor_ray(&self, cursor: (f32, f32), size: PhysicalSize<u32>) -> Vec3 {
+        let ndc_x = 2.0 * cursor.0 / size.width as f32 - 1.0;
+        let ndc_y = 1.0 - 2.0 * cursor.1 / size.height as f32;
+
+        // any depth beyond the near plane lies on the ray, as it passes through the camera
+        let far_point = self.view_projection().inverse() * vec4(ndc_x, ndc_y, 0.5, 1.0);
+
+        (far_point.xyz() / far_point.w - self.position).normalize()
+    }
+
+    /// Returns where the ray leaving the camera through the given cursor position hits the y = 0
+    /// plane, as world space (x, z) coordinates.
+    pub fn cursor_plane_hit(&self, cursor: (f32, f32), size: PhysicalSize<u32>) -> Option<Vec2> {
+        let direction = self.cursor_ray(cursor, size);
+
+        let t = -self.position.y / direction.y;
+
+        (t.is_finite() && t > 0.0).then(|| (self.position + t * direction).xz())
+    }
+
+    /// Returns where the world space `position` appears on a surface of the given size (in
+    /// physical pixels), or `None` if it lies behind the camera.
+    pub fn world_to_screen(&self, position: Vec3, size: PhysicalSize<u32>) -> Option<Vec2> {
+        let clip = self.view_projection() * position.extend(1.0);
+
+        if clip.w <= 0.0 {
+            return None;
+        }
+
+        let ndc = clip.xy() / clip.w;
+
+        Some(Vec2::new(
+            (ndc.x + 1.0) / 2.0 * size.width as f32,
+            (1.0 - ndc.y) / 2.0 * size.height as f32,
+        ))
+    }
+
     /// Updates the camera's position based on the user's input.
     pub fn update_position(&mut self, key_down: impl Fn(&KeyCode) -> bool, dt: f32) {
         let up = Vec3::Y;
@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 
 use wgpu::*;
 use winit::{dpi::PhysicalSize, window::Window};
@@ -15,12 +18,17 @@ pub struct GpuContext {
     /// A queue by which commands are sent to the rendering device.
     pub queue: Queue,
 
+    /// The wgpu instance the `surface` and `device` were created from.
+    instance: Instance,
     /// The window being rendered onto.
     pub window: Arc<Window>,
     /// The primary surface texture being rendered onto.
     pub surface: Surface<'static>,
     /// The configuration of the `surface`.
     pub surface_config: SurfaceConfiguration,
+
+    /// Set once the `device` has been lost, after which it has to be recreated.
+    device_lost: Arc<AtomicBool>,
 }
 
 impl GpuContext {
@@ -41,15 +49,8 @@ impl GpuContext {
             })
             .await?;
 
-        let (device, queue) = adapter
-            .request_device(&DeviceDescriptor {
-                required_features: Features {
-                    features_webgpu: FeaturesWebGPU::FLOAT32_FILTERABLE,
-                    ..Default::default()
-                },
-                ..Default::default()
-            })
-            .await?;
+        let (device, queue) = Self::request_device(&adapter).await?;
+        let device_lost = Self::watch_device(&device);
 
         let PhysicalSize { width, height } = window.inner_size();
 
@@ -69,19 +70,153 @@ impl GpuContext {
         Ok(Self {
             device,
             queue,
+            instance,
             window,
             surface,
             surface_config,
+            device_lost,
         })
     }
 
+    /// Replaces the lost `device` and `queue` with new ones, keeping the `surface`.
+    ///
+    /// Everything created from the previous device has to be recreated as well.
+    pub async fn recreate_device(&mut self) -> anyhow::Result<()> {
+        // the adapter usually goes away with the device, for instance when the driver restarts
+        let adapter = self
+            .instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: PowerPreference::HighPerformance,
+                force_fallback_adapter: false,
+                compatible_surface: Some(&self.surface),
+            })
+            .await?;
+
+        let (device, queue) = Self::request_device(&adapter).await?;
+
+        self.device_lost = Self::watch_device(&device);
+        self.device = device;
+        self.queue = queue;
+
+        self.reconfigure();
+
+        Ok(())
+    }
+
+    /// Keeps track of whether `device` has been lost, returning the flag set once it is.
+    ///
+    /// Errors of the device are fatal, except for the ones following its loss.
+    fn watch_device(device: &Device) -> Arc<AtomicBool> {
+        let device_lost = Arc::new(AtomicBool::new(false));
+
+        device.set_device_lost_callback({
+            let device_lost = Arc::clone(&device_lost);
+
+            move |reason, message| {
+                log::error!("The GPU device was lost ({reason:?}): {message}");
+                device_lost.store(true, Ordering::Release);
+            }
+        });
+
+        device.on_uncaptured_error({
+            let device_lost = Arc::clone(&device_lost);
+
+            Arc::new(move |error| {
+                // every call fails once the device is lost, until it is recreated
+                if device_lost.load(Ordering::Acquire) {
+                    log::debug!("Ignoring an error of the lost GPU device: {error}");
+                    return;
+                }
+
+                panic!("wgpu error: {error}");
+            })
+        });
+
+        device_lost
+    }
+
+    /// Creates a device able to run the simulation without presenting to any surface, for running
+    /// simulations without a window.
+    ///
+    /// Fails if no adapter is available, or if it cannot use [`TextureFormat::Rg32Float`] textures
+    /// as storage.
+    pub async fn new_headless() -> anyhow::Result<(Device, Queue)> {
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: Backends::PRIMARY,
+            ..Default::default()
+        });
+
+        let adapter = instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: PowerPreference::HighPerformance,
+                force_fallback_adapter: false,
+                compatible_surface: None,
+            })
+            .await?;
+
+        let storage = adapter
+            .get_texture_format_features(TextureFormat::Rg32Float)
+            .allowed_usages
+            .contains(TextureUsages::STORAGE_BINDING);
+
+        anyhow::ensure!(
+            storage,
+            "{} cannot write Rg32Float storage textures",
+            adapter.get_info().name
+        );
+
+        log::info!("Running headless on {}", adapter.get_info().name);
+
+        Self::request_device(&adapter).await
+    }
+
+    /// Requests a device with all features the simulation relies on from `adapter`.
+    async fn request_device(adapter: &Adapter) -> anyhow::Result<(Device, Queue)> {
+        let device = adapter
+            .request_device(&DeviceDescriptor {
+                required_features: Features {
+                    features_webgpu: FeaturesWebGPU::FLOAT32_FILTERABLE,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(device)
+    }
+
     /// Resizes the target [`Surface`] to match the new window size.
+    ///
+    /// An empty size, as reported while the window is minimised, leaves the surface as it is until
+    /// the window is restored.
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
         let PhysicalSize { width, height } = size;
 
+        if width == 0 || height == 0 {
+            return;
+        }
+
         self.surface_config.width = width;
         self.surface_config.height = height;
 
+        self.reconfigure();
+    }
+
+    /// Configures the [`Surface`] anew, after it has been lost or no longer matches the window.
+    pub fn reconfigure(&self) {
         self.surface.configure(&self.device, &self.surface_config);
     }
+
+    /// Returns whether the window is minimised, in which case nothing can be presented to it.
+    pub fn is_minimised(&self) -> bool {
+        let PhysicalSize { width, height } = self.window.inner_size();
+
+        self.window.is_minimized().unwrap_or(false) || width == 0 || height == 0
+    }
+
+    /// Returns whether the `device` has been lost, in which case the whole [`GpuContext`] and
+    /// everything created from it has to be recreated.
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Acquire)
+    }
 }
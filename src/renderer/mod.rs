@@ -19,9 +19,12 @@ use crate::{
         shaders::Shaders,
         surface::SurfaceMesh,
     },
-    simulation::WaveSimulation,
+    simulation::{WaveSimulation, config::SimulationConfig},
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::renderer::shaders::ShaderFile;
+
 /// Manages all GPU state and renders all game content.
 #[allow(unused)]
 pub struct Renderer {
@@ -48,7 +51,7 @@ pub struct Renderer {
 
 impl Renderer {
     /// Initializes the rendering context, creating a new [`Renderer`].
-    pub async fn new(window: Arc<Window>) -> anyhow::Result<Self> {
+    pub async fn new(window: Arc<Window>, config: &SimulationConfig) -> anyhow::Result<Self> {
         let gpu = GpuContext::new(window).await?;
 
         let shaders = Shaders::new(&gpu.device);
@@ -64,7 +67,7 @@ impl Renderer {
 
         let camera = CameraGpuState::new(&gpu.device, &pipelines);
 
-        let surface = SurfaceMesh::new(&gpu.device);
+        let surface = SurfaceMesh::new(&gpu.device, config);
 
         Ok(Self {
             gpu,
@@ -77,20 +80,50 @@ impl Renderer {
         })
     }
 
+    /// Recreates the lost GPU device along with everything created from it, keeping the window
+    /// surface.
+    ///
+    /// The shaders are compiled from the ones embedded in the binary again, and the fonts of
+    /// `ui_context` are uploaded anew.
+    pub async fn recover(
+        &mut self,
+        ui_context: &egui::Context,
+        config: &SimulationConfig,
+    ) -> anyhow::Result<()> {
+        self.gpu.recreate_device().await?;
+
+        let device = &self.gpu.device;
+
+        self.shaders = Shaders::new(device);
+        self.pipelines = Pipelines::new(device, &self.shaders);
+        self.frame_targets = FrameTargets::new(self.gpu.window.inner_size(), device);
+        self.ui_renderer = egui_wgpu::Renderer::new(
+            device,
+            SURFACE_VIEW_FORMAT,
+            egui_wgpu::RendererOptions::default(),
+        );
+        self.camera = CameraGpuState::new(device, &self.pipelines);
+        self.surface = SurfaceMesh::new(device, config);
+
+        self.upload_ui_fonts(ui_context);
+
+        Ok(())
+    }
+
     /// Renders all world content onto the surface and runs all compute passes.
+    ///
+    /// If no surface texture can be acquired, for instance because the surface is outdated after
+    /// a resize, the frame is skipped while the compute passes still run.
     pub fn render(
         &mut self,
         camera: &Camera,
         ui_context: &egui::Context,
-        ui: egui::FullOutput,
+        mut ui: egui::FullOutput,
         simulation: &mut WaveSimulation,
+        substeps: u32,
         pre_present: impl FnOnce(),
     ) {
-        let output = self.gpu.surface.get_current_texture().unwrap();
-        let view = output.texture.create_view(&TextureViewDescriptor {
-            format: Some(SURFACE_VIEW_FORMAT),
-            ..Default::default()
-        });
+        let output = self.acquire_frame();
 
         let mut encoder = self
             .gpu
@@ -99,19 +132,133 @@ impl Renderer {
 
         self.camera.update_buffer(&self.gpu.queue, camera);
 
-        simulation.tick(&mut encoder, &self.pipelines);
+        simulation.step(
+            &self.gpu.device,
+            &self.gpu.queue,
+            &mut encoder,
+            &self.pipelines,
+            substeps,
+        );
+        simulation.measure_statistics(&self.gpu.queue, &mut encoder, &self.pipelines);
+        simulation.measure_line_cut(&self.gpu.queue, &mut encoder, &self.pipelines);
+
+        // textures are updated even when the frame is skipped, as egui only sends their changes
+        let textures_delta = std::mem::take(&mut ui.textures_delta);
+        self.update_ui_textures(&textures_delta);
 
-        self.render_surface(&view, &mut encoder, simulation);
-        self.render_ui(&view, &mut encoder, ui_context, ui);
+        let view = output.as_ref().map(|output| {
+            output.texture.create_view(&TextureViewDescriptor {
+                format: Some(SURFACE_VIEW_FORMAT),
+                ..Default::default()
+            })
+        });
+
+        if let Some(view) = &view {
+            self.render_surface(view, &mut encoder, simulation);
+            self.render_ui(view, &mut encoder, ui_context, ui);
+        }
 
         self.gpu.queue.submit([encoder.finish()]);
+        simulation.begin_statistics_readback();
+        simulation.begin_probe_readback();
+        simulation.begin_line_cut_readback();
+
+        for id in &textures_delta.free {
+            self.ui_renderer.free_texture(id);
+        }
+
+        let Some(output) = output else {
+            return;
+        };
+
+        let suboptimal = output.suboptimal;
 
         pre_present();
         output.present();
+
+        // the surface still works, but a reconfigured one better matches the window
+        if suboptimal {
+            self.gpu.reconfigure();
+        }
+    }
+
+    /// Acquires the surface texture of the next frame, or returns `None` if the frame has to be
+    /// skipped.
+    fn acquire_frame(&mut self) -> Option<SurfaceTexture> {
+        match self.gpu.surface.get_current_texture() {
+            Ok(output) => Some(output),
+            // the surface no longer matches the window, and works again once reconfigured
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                log::debug!("The surface is out of date, reconfiguring it");
+                self.gpu.reconfigure();
+                None
+            }
+            Err(SurfaceError::Timeout) => {
+                log::warn!("Timed out acquiring the next frame, skipping it");
+                None
+            }
+            Err(error) => {
+                log::error!("Failed to acquire the next frame: {error}");
+                None
+            }
+        }
+    }
+
+    /// Uploads the full font atlas of `context` into a freshly created UI renderer, as egui only
+    /// sends it once.
+    fn upload_ui_fonts(&mut self, context: &egui::Context) {
+        let atlas = context.fonts(|fonts| fonts.image());
+
+        self.ui_renderer.update_texture(
+            &self.gpu.device,
+            &self.gpu.queue,
+            egui::TextureId::default(),
+            &egui::epaint::ImageDelta::full(atlas, egui::epaint::TextureAtlas::texture_options()),
+        );
+    }
+
+    /// Replaces the source of the shader or include file with the given name, then recompiles
+    /// every shader depending on it and rebuilds the pipelines using them.
+    ///
+    /// Returns the outcome for every recompiled shader. On failure the error holds the offending
+    /// line of the source if the shader itself is invalid, and the previous pipeline is kept
+    /// running.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_shader(
+        &mut self,
+        file_name: &str,
+        source: String,
+    ) -> Vec<(ShaderFile, anyhow::Result<()>)> {
+        let mut results = Vec::new();
+
+        for file in self.shaders.update_source(file_name, source) {
+            let result = self
+                .shaders
+                .recompile(&self.gpu.device, file)
+                .and_then(|()| {
+                    self.pipelines
+                        .rebuild(&self.gpu.device, &self.shaders, file)
+                });
+
+            results.push((file, result));
+        }
+
+        results
+    }
+
+    /// Rebuilds the surface mesh to match a new simulation domain.
+    pub fn set_simulation_config(&mut self, config: &SimulationConfig) {
+        self.surface = SurfaceMesh::new(&self.gpu.device, config);
     }
 
     /// Resizes the internal rendering surface to match the new target size.
+    ///
+    /// An empty size, as reported while the window is minimised, is ignored.
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
         self.gpu.resize(size);
         self.frame_targets.resize(&self.gpu.device, size);
     }
@@ -130,7 +277,7 @@ impl Renderer {
         let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
             label: Some("Renderer::main_render_pass"),
             color_attachments: &[Some(RenderPassColorAttachment {
-                view: &view,
+                view,
                 depth_slice: None,
                 resolve_target: None,
                 ops: Operations {
@@ -159,6 +306,7 @@ impl Renderer {
 
         pass.set_bind_group(0, &self.camera.bind_group, &[]);
         pass.set_bind_group(1, simulation.get_active_texture(), &[]);
+        pass.set_bind_group(2, simulation.get_parameters(), &[]);
 
         pass.set_vertex_buffer(0, self.surface.vertex_buffer.slice(..));
         pass.set_index_buffer(self.surface.index_buffer.slice(..), IndexFormat::Uint32);
@@ -166,6 +314,14 @@ impl Renderer {
         pass.draw_indexed(0..self.surface.index_count, 0, 0..1);
     }
 
+    /// Uploads the textures egui created or changed during the frame.
+    fn update_ui_textures(&mut self, textures_delta: &egui::TexturesDelta) {
+        for (id, image_delta) in &textures_delta.set {
+            self.ui_renderer
+                .update_texture(&self.gpu.device, &self.gpu.queue, *id, image_delta);
+        }
+    }
+
     fn render_ui(
         &mut self,
         view: &TextureView,
@@ -175,11 +331,6 @@ impl Renderer {
     ) {
         let tris = context.tessellate(output.shapes, output.pixels_per_point);
 
-        for (id, image_delta) in &output.textures_delta.set {
-            self.ui_renderer
-                .update_texture(&self.gpu.device, &self.gpu.queue, *id, &image_delta);
-        }
-
         let screen_descriptor = egui_wgpu::ScreenDescriptor {
             size_in_pixels: self.gpu.window.inner_size().into(),
             pixels_per_point: self.gpu.window.scale_factor() as _,
@@ -215,9 +366,5 @@ impl Renderer {
             .render(&mut pass, &tris, &screen_descriptor);
 
         drop(pass);
-
-        for x in &output.textures_delta.free {
-            self.ui_renderer.free_texture(x)
-        }
     }
 }
@@ -1,5 +1,7 @@
 use wgpu::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::renderer::shaders::ShaderFile;
 use crate::renderer::{
     frame::DEPTH_FORMAT, gpu_context::SURFACE_VIEW_FORMAT, shaders::Shaders, surface::SurfaceVertex,
 };
@@ -8,13 +10,53 @@ use crate::renderer::{
 pub struct Pipelines {
     /// The pipeline used for rendering a [`SurfaceMesh`].
     pub surface_pipeline: RenderPipeline,
+    /// The layout of the `surface_pipeline`, kept around for rebuilding it.
+    surface_pipeline_layout: PipelineLayout,
     /// The bind group layout for holding a camera's transformation matrix.
     pub camera_bind_group_layout: BindGroupLayout,
 
     /// The compute pipeline used for advancing the state of the wave simulation by one "tick".
     pub simulation_pipeline: ComputePipeline,
-    /// The bind group layout for one texture being read from, and the other being written to.
+    /// The layout of the `simulation_pipeline`, shared by its variants with drive expressions
+    /// compiled in.
+    pub simulation_pipeline_layout: PipelineLayout,
+    /// The source the `simulation_pipeline` was compiled from, which its variants with drive
+    /// expressions are injected into.
+    simulation_source: String,
+    /// How many times the `simulation_pipeline` has been rebuilt, so that its variants know when
+    /// they are out of date.
+    simulation_revision: u32,
+    /// The bind group layout for one texture being read from, and the other being written to,
+    /// alongside their auxiliary fields.
     pub texture_read_write_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for holding the physical parameters of the wave simulation, the
+    /// wave speed of each cell, the obstacle mask and the wave sources.
+    pub simulation_parameters_bind_group_layout: BindGroupLayout,
+
+    /// The compute pipeline used for reducing the state of the wave simulation into its
+    /// statistics.
+    pub statistics_pipeline: ComputePipeline,
+    /// The layout of the `statistics_pipeline`, kept around for rebuilding it.
+    statistics_pipeline_layout: PipelineLayout,
+    /// The bind group layout for holding the reduction parameters, the state being reduced, the
+    /// wave speed of each cell, the obstacle mask and the per-workgroup results.
+    pub statistics_bind_group_layout: BindGroupLayout,
+
+    /// The compute pipeline used for recording the state of the wave simulation at every probe.
+    pub probes_pipeline: ComputePipeline,
+    /// The layout of the `probes_pipeline`, kept around for rebuilding it.
+    probes_pipeline_layout: PipelineLayout,
+    /// The bind group layout for holding the recording parameters, the state being recorded, the
+    /// cell of every probe and the ring buffer of samples.
+    pub probes_bind_group_layout: BindGroupLayout,
+
+    /// The compute pipeline used for sampling the state of the wave simulation along a line cut.
+    pub line_cut_pipeline: ComputePipeline,
+    /// The layout of the `line_cut_pipeline`, kept around for rebuilding it.
+    line_cut_pipeline_layout: PipelineLayout,
+    /// The bind group layout for holding the line cut parameters, the state being sampled and the
+    /// buffer of samples.
+    pub line_cut_bind_group_layout: BindGroupLayout,
 }
 
 impl Pipelines {
@@ -61,6 +103,79 @@ impl Pipelines {
                         },
                         count: None,
                     },
+                    // the "read" auxiliary texture
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // the "write" auxiliary texture
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::StorageTexture {
+                            access: StorageTextureAccess::WriteOnly,
+                            format: TextureFormat::Rg32Float,
+                            view_dimension: TextureViewDimension::D2,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let simulation_parameters_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Pipelines::simulation_parameters_bind_group_layout"),
+                entries: &[
+                    // the simulation uniforms
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // the wave speed map
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::COMPUTE | ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // the obstacle mask
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::COMPUTE | ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Uint,
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // the wave sources
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
@@ -69,21 +184,369 @@ impl Pipelines {
             bind_group_layouts: &[
                 &camera_bind_group_layout,
                 &texture_read_write_bind_group_layout,
+                &simulation_parameters_bind_group_layout,
             ],
             push_constant_ranges: &[],
         });
 
-        let surface_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+        let surface_pipeline =
+            Self::surface_pipeline(device, &surface_pipeline_layout, &shaders.triangle_shader);
+
+        let simulation_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Pipelines::simulation_pipeline_layout"),
+            bind_group_layouts: &[
+                &texture_read_write_bind_group_layout,
+                &simulation_parameters_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let simulation_pipeline = Self::compute_pipeline(
+            device,
+            "Pipelines::simulation_pipeline",
+            &simulation_pipeline_layout,
+            &shaders.simulation_shader,
+        );
+
+        let statistics_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Pipelines::statistics_bind_group_layout"),
+                entries: &[
+                    // the reduction uniforms
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // the state being reduced
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // the wave speed map
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // the obstacle mask
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Uint,
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // the per-workgroup results
+                    BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let statistics_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Pipelines::statistics_pipeline_layout"),
+            bind_group_layouts: &[&statistics_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let statistics_pipeline = Self::compute_pipeline(
+            device,
+            "Pipelines::statistics_pipeline",
+            &statistics_pipeline_layout,
+            &shaders.statistics_shader,
+        );
+
+        let probes_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Pipelines::probes_bind_group_layout"),
+                entries: &[
+                    // the recording uniforms
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // the state being recorded
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // the cell of every probe
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // the ring buffer of samples
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let probes_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Pipelines::probes_pipeline_layout"),
+            bind_group_layouts: &[&probes_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let probes_pipeline = Self::compute_pipeline(
+            device,
+            "Pipelines::probes_pipeline",
+            &probes_pipeline_layout,
+            &shaders.probes_shader,
+        );
+
+        let line_cut_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Pipelines::line_cut_bind_group_layout"),
+                entries: &[
+                    // the line cut uniforms
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // the state being sampled
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // the samples along the line
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let line_cut_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Pipelines::line_cut_pipeline_layout"),
+            bind_group_layouts: &[&line_cut_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let line_cut_pipeline = Self::compute_pipeline(
+            device,
+            "Pipelines::line_cut_pipeline",
+            &line_cut_pipeline_layout,
+            &shaders.line_cut_shader,
+        );
+
+        Self {
+            surface_pipeline,
+            surface_pipeline_layout,
+            camera_bind_group_layout,
+            simulation_pipeline,
+            simulation_pipeline_layout,
+            simulation_source: shaders.simulation_source.clone(),
+            simulation_revision: 0,
+            texture_read_write_bind_group_layout,
+            simulation_parameters_bind_group_layout,
+            statistics_pipeline,
+            statistics_pipeline_layout,
+            statistics_bind_group_layout,
+            probes_pipeline,
+            probes_pipeline_layout,
+            probes_bind_group_layout,
+            line_cut_pipeline,
+            line_cut_pipeline_layout,
+            line_cut_bind_group_layout,
+        }
+    }
+
+    /// Rebuilds the pipeline using the shader of the given file, after it has been reloaded.
+    ///
+    /// If the shader doesn't fit the pipeline, for instance because its bindings no longer match
+    /// the bind group layouts, the error is returned and the previous pipeline is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn rebuild(
+        &mut self,
+        device: &Device,
+        shaders: &Shaders,
+        file: ShaderFile,
+    ) -> anyhow::Result<()> {
+        let shader = shaders.get(file);
+
+        match file {
+            ShaderFile::Triangle => {
+                self.surface_pipeline = catch_errors(device, file, || {
+                    Self::surface_pipeline(device, &self.surface_pipeline_layout, shader)
+                })?;
+            }
+            ShaderFile::Simulation => {
+                self.simulation_pipeline = catch_errors(device, file, || {
+                    Self::compute_pipeline(
+                        device,
+                        "Pipelines::simulation_pipeline",
+                        &self.simulation_pipeline_layout,
+                        shader,
+                    )
+                })?;
+
+                self.simulation_source = shaders.simulation_source.clone();
+                self.simulation_revision += 1;
+            }
+            ShaderFile::Statistics => {
+                self.statistics_pipeline = catch_errors(device, file, || {
+                    Self::compute_pipeline(
+                        device,
+                        "Pipelines::statistics_pipeline",
+                        &self.statistics_pipeline_layout,
+                        shader,
+                    )
+                })?;
+            }
+            ShaderFile::Probes => {
+                self.probes_pipeline = catch_errors(device, file, || {
+                    Self::compute_pipeline(
+                        device,
+                        "Pipelines::probes_pipeline",
+                        &self.probes_pipeline_layout,
+                        shader,
+                    )
+                })?;
+            }
+            ShaderFile::LineCut => {
+                self.line_cut_pipeline = catch_errors(device, file, || {
+                    Self::compute_pipeline(
+                        device,
+                        "Pipelines::line_cut_pipeline",
+                        &self.line_cut_pipeline_layout,
+                        shader,
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns how many times the `simulation_pipeline` has been rebuilt, which changes whenever
+    /// its variants created with [`Pipelines::create_simulation_pipeline`] go out of date.
+    pub fn simulation_revision(&self) -> u32 {
+        self.simulation_revision
+    }
+
+    /// Creates a variant of the `simulation_pipeline` with the drive expressions of the sources
+    /// injected into `expression_signal`.
+    ///
+    /// If the expressions don't compile, the error is returned with the offending line of the
+    /// shader.
+    ///
+    /// See [`expression_signal_wgsl`](crate::simulation::sources::expression_signal_wgsl) for
+    /// generating the `expression_signals`.
+    pub fn create_simulation_pipeline(
+        &self,
+        device: &Device,
+        expression_signals: &str,
+    ) -> anyhow::Result<ComputePipeline> {
+        let create = || {
+            let shader = Shaders::create_simulation_shader(
+                device,
+                &self.simulation_source,
+                Some(expression_signals),
+            )?;
+
+            Ok(Self::compute_pipeline(
+                device,
+                "Pipelines::simulation_pipeline",
+                &self.simulation_pipeline_layout,
+                &shader,
+            ))
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        return catch_errors(device, ShaderFile::Simulation, create)?;
+
+        #[cfg(target_arch = "wasm32")]
+        create()
+    }
+
+    /// Creates the render pipeline drawing the surface from `shader`.
+    fn surface_pipeline(
+        device: &Device,
+        layout: &PipelineLayout,
+        shader: &ShaderModule,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
             label: Some("Pipelines::surface_pipeline"),
-            layout: Some(&surface_pipeline_layout),
+            layout: Some(layout),
             vertex: VertexState {
-                module: &shaders.triangle_shader,
+                module: shader,
                 entry_point: Some("vs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
                 buffers: &[SurfaceVertex::LAYOUT],
             },
             fragment: Some(FragmentState {
-                module: &shaders.triangle_shader,
+                module: shader,
                 entry_point: Some("fs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
                 targets: &[Some(ColorTargetState {
@@ -106,28 +569,42 @@ impl Pipelines {
             }),
             multiview: None,
             cache: None,
-        });
-
-        let simulation_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("Pipelines::simulation_pipeline_layout"),
-            bind_group_layouts: &[&texture_read_write_bind_group_layout],
-            push_constant_ranges: &[],
-        });
+        })
+    }
 
-        let simulation_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
-            label: Some("Pipelines::simulation_pipeline_layout"),
-            layout: Some(&simulation_pipeline_layout),
-            module: &shaders.simulation_shader,
+    /// Creates a compute pipeline running the `main` entry point of `shader`.
+    fn compute_pipeline(
+        device: &Device,
+        label: &str,
+        layout: &PipelineLayout,
+        shader: &ShaderModule,
+    ) -> ComputePipeline {
+        device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            module: shader,
             entry_point: Some("main"),
             compilation_options: PipelineCompilationOptions::default(),
             cache: None,
-        });
+        })
+    }
+}
 
-        Self {
-            surface_pipeline,
-            camera_bind_group_layout,
-            simulation_pipeline,
-            texture_read_write_bind_group_layout,
-        }
+/// Runs `create`, returning the validation error it raised on the device instead of panicking.
+#[cfg(not(target_arch = "wasm32"))]
+fn catch_errors<T>(
+    device: &Device,
+    file: ShaderFile,
+    create: impl FnOnce() -> T,
+) -> anyhow::Result<T> {
+    use anyhow::Context;
+
+    device.push_error_scope(ErrorFilter::Validation);
+    let created = create();
+
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(error) => Err(anyhow::anyhow!("{error}"))
+            .with_context(|| format!("Failed to build the pipeline of {}", file.file_name())),
+        None => Ok(created),
     }
 }
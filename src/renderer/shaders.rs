@@ -1,4 +1,97 @@
-use wgpu::{Device, ShaderModule, include_wgsl};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Context, bail, ensure};
+use wgpu::{Device, ShaderModule, ShaderModuleDescriptor, ShaderSource};
+
+use crate::simulation::{MAX_IMPULSES, WORKGROUP_SIZE, probes::MAX_PROBES};
+
+/// The comment in `simulation.wgsl` replaced with the generated body of `expression_signal`.
+const EXPRESSION_SIGNALS_MARKER: &str = "// EXPRESSION SIGNALS";
+
+/// The directory the shaders are loaded from, and watched for changes while running natively.
+#[cfg(not(target_arch = "wasm32"))]
+pub const ASSETS_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets");
+
+/// The name and source of every WGSL file in `assets/` that is only ever `#include`d into
+/// shaders, as it was when the application was built.
+pub const INCLUDE_FILES: [(&str, &str); 1] = [(
+    "boundaries.wgsl",
+    include_str!("../../assets/boundaries.wgsl"),
+)];
+
+/// One of the WGSL files in `assets/` making up a shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderFile {
+    /// `triangle_shader.wgsl`, drawing the surface.
+    Triangle,
+    /// `simulation.wgsl`, advancing the wave simulation.
+    Simulation,
+    /// `statistics.wgsl`, reducing the simulation state into its statistics.
+    Statistics,
+    /// `probes.wgsl`, recording the simulation state at every probe.
+    Probes,
+    /// `line_cut.wgsl`, sampling the simulation state along a line cut.
+    LineCut,
+}
+
+impl ShaderFile {
+    /// Every shader file, in the order they are compiled in.
+    pub const ALL: [Self; 5] = [
+        Self::Triangle,
+        Self::Simulation,
+        Self::Statistics,
+        Self::Probes,
+        Self::LineCut,
+    ];
+
+    /// Returns the shader file with the given name within `assets/`, if any.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|file| file.file_name() == name)
+    }
+
+    /// Returns the name of the file within `assets/`.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Triangle => "triangle_shader.wgsl",
+            Self::Simulation => "simulation.wgsl",
+            Self::Statistics => "statistics.wgsl",
+            Self::Probes => "probes.wgsl",
+            Self::LineCut => "line_cut.wgsl",
+        }
+    }
+
+    /// Returns the source of the file as it was when the application was built, before
+    /// preprocessing.
+    pub fn embedded_source(self) -> &'static str {
+        match self {
+            Self::Triangle => include_str!("../../assets/triangle_shader.wgsl"),
+            Self::Simulation => include_str!("../../assets/simulation.wgsl"),
+            Self::Statistics => include_str!("../../assets/statistics.wgsl"),
+            Self::Probes => include_str!("../../assets/probes.wgsl"),
+            Self::LineCut => include_str!("../../assets/line_cut.wgsl"),
+        }
+    }
+
+    /// Returns the label of the shader module compiled from the file.
+    fn label(self) -> &'static str {
+        match self {
+            Self::Triangle => "assets/triangle_shader.wgsl",
+            Self::Simulation => "assets/simulation.wgsl",
+            Self::Statistics => "assets/statistics.wgsl",
+            Self::Probes => "assets/probes.wgsl",
+            Self::LineCut => "assets/line_cut.wgsl",
+        }
+    }
+}
 
 /// All compiled and hot reloadable shaders used in the application.
 pub struct Shaders {
@@ -7,20 +100,651 @@ pub struct Shaders {
 
     /// The shader used for running a wave simulation compute pass.
     pub simulation_shader: ShaderModule,
+    /// The preprocessed source the `simulation_shader` was compiled from, which its variants with
+    /// drive expressions are injected into.
+    pub simulation_source: String,
+
+    /// The shader used for reducing the simulation state into its statistics.
+    pub statistics_shader: ShaderModule,
+
+    /// The shader used for recording the simulation state at every probe.
+    pub probes_shader: ShaderModule,
+
+    /// The shader used for sampling the simulation state along a line cut.
+    pub line_cut_shader: ShaderModule,
+
+    /// Expands the directives of every shader, holding the latest version of the include files.
+    #[cfg(not(target_arch = "wasm32"))]
+    preprocessor: Preprocessor,
+    /// The latest source of every shader file, before preprocessing.
+    #[cfg(not(target_arch = "wasm32"))]
+    sources: HashMap<ShaderFile, String>,
 }
 
 impl Shaders {
-    /// Creates and compiles all shaders.
+    /// Creates and compiles all shaders from the sources embedded into the application.
     pub fn new(device: &Device) -> Self {
-        let triangle_shader =
-            device.create_shader_module(include_wgsl!("../../assets/triangle_shader.wgsl"));
+        let preprocessor = Self::preprocessor();
+
+        let preprocess = |file: ShaderFile| {
+            preprocessor
+                .process(file.file_name(), file.embedded_source())
+                .expect("the embedded shaders are valid")
+                .source
+        };
+
+        let create = |file: ShaderFile| {
+            device.create_shader_module(ShaderModuleDescriptor {
+                label: Some(file.label()),
+                source: ShaderSource::Wgsl(preprocess(file).into()),
+            })
+        };
+
+        Self {
+            triangle_shader: create(ShaderFile::Triangle),
+            simulation_shader: create(ShaderFile::Simulation),
+            simulation_source: preprocess(ShaderFile::Simulation),
+            statistics_shader: create(ShaderFile::Statistics),
+            probes_shader: create(ShaderFile::Probes),
+            line_cut_shader: create(ShaderFile::LineCut),
+            #[cfg(not(target_arch = "wasm32"))]
+            preprocessor,
+            #[cfg(not(target_arch = "wasm32"))]
+            sources: ShaderFile::ALL
+                .into_iter()
+                .map(|file| (file, file.embedded_source().to_owned()))
+                .collect(),
+        }
+    }
+
+    /// Returns the preprocessor every shader is expanded with, knowing the include files embedded
+    /// into the application and defining the constants the shaders share with Rust.
+    pub fn preprocessor() -> Preprocessor {
+        let preprocessor = INCLUDE_FILES
+            .into_iter()
+            .fold(Preprocessor::new(), |preprocessor, (name, source)| {
+                preprocessor.include(name, source)
+            });
+
+        preprocessor
+            .define("WORKGROUP_SIZE", format!("{WORKGROUP_SIZE}u"))
+            .define("MAX_IMPULSES", format!("{MAX_IMPULSES}u"))
+            .define("MAX_PROBES", format!("{MAX_PROBES}u"))
+    }
+
+    /// Returns the compiled shader of the given file.
+    pub fn get(&self, file: ShaderFile) -> &ShaderModule {
+        match file {
+            ShaderFile::Triangle => &self.triangle_shader,
+            ShaderFile::Simulation => &self.simulation_shader,
+            ShaderFile::Statistics => &self.statistics_shader,
+            ShaderFile::Probes => &self.probes_shader,
+            ShaderFile::LineCut => &self.line_cut_shader,
+        }
+    }
+
+    /// Replaces the source of the shader or include file with the given name, returning every
+    /// shader that needs to be recompiled for the change to take effect.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn update_source(&mut self, file_name: &str, source: String) -> Vec<ShaderFile> {
+        if let Some(file) = ShaderFile::from_file_name(file_name) {
+            self.sources.insert(file, source);
+            return vec![file];
+        }
+
+        self.preprocessor = std::mem::take(&mut self.preprocessor).include(file_name, source);
+
+        // shaders that no longer preprocess are recompiled as well, so that their error shows up
+        ShaderFile::ALL
+            .into_iter()
+            .filter(|file| {
+                self.preprocessor
+                    .process(file.file_name(), &self.sources[file])
+                    .ok()
+                    .is_none_or(|preprocessed| preprocessed.includes(file_name))
+            })
+            .collect()
+    }
+
+    /// Recompiles the shader of the given file from its latest source.
+    ///
+    /// The source is preprocessed and validated before being handed to the device, and on
+    /// failure the error is returned with the offending line of the source while the previous
+    /// shader is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn recompile(&mut self, device: &Device, file: ShaderFile) -> anyhow::Result<()> {
+        let preprocessed = self
+            .preprocessor
+            .process(file.file_name(), &self.sources[&file])?;
+
+        preprocessed.validate()?;
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(file.label()),
+            source: ShaderSource::Wgsl(preprocessed.source.as_str().into()),
+        });
+
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(anyhow::anyhow!("{error}"))
+                .with_context(|| format!("Failed to compile {}", file.file_name()));
+        }
+
+        match file {
+            ShaderFile::Triangle => self.triangle_shader = shader,
+            ShaderFile::Simulation => {
+                self.simulation_shader = shader;
+                self.simulation_source = preprocessed.source;
+            }
+            ShaderFile::Statistics => self.statistics_shader = shader,
+            ShaderFile::Probes => self.probes_shader = shader,
+            ShaderFile::LineCut => self.line_cut_shader = shader,
+        }
+
+        Ok(())
+    }
+
+    /// Compiles the wave simulation shader from the preprocessed `simulation_source`, with the
+    /// drive expressions of the sources injected into `expression_signal` if given.
+    ///
+    /// When running natively, the source is validated before being handed to the device, so an
+    /// expression the shader compiler rejects is returned as an error instead of panicking.
+    ///
+    /// See [`expression_signal_wgsl`](crate::simulation::sources::expression_signal_wgsl) for
+    /// generating the `expression_signals`.
+    pub fn create_simulation_shader(
+        device: &Device,
+        simulation_source: &str,
+        expression_signals: Option<&str>,
+    ) -> anyhow::Result<ShaderModule> {
+        let source = Self::inject_expression_signals(simulation_source, expression_signals);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        Preprocessor::new()
+            .process(ShaderFile::Simulation.file_name(), &source)?
+            .validate()?;
+
+        Ok(device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(ShaderFile::Simulation.label()),
+            source: ShaderSource::Wgsl(source.into()),
+        }))
+    }
+
+    /// Returns the WGSL source of the wave simulation shader, with the drive expressions of the
+    /// sources injected into `expression_signal` if given.
+    pub fn inject_expression_signals(
+        simulation_source: &str,
+        expression_signals: Option<&str>,
+    ) -> String {
+        match expression_signals {
+            Some(signals) => simulation_source.replacen(EXPRESSION_SIGNALS_MARKER, signals, 1),
+            None => simulation_source.to_owned(),
+        }
+    }
+}
+
+/// Expands the directives of a WGSL source before it is compiled:
+///
+/// - `#include "file.wgsl"` pastes in one of the files added with [`Preprocessor::include`],
+///   unless it has already been pasted into the same shader.
+/// - `#define NAME value` replaces every following occurrence of the identifier `NAME` outside of
+///   line or (nested) block comments with `value`, while `#define NAME` merely defines `NAME`.
+/// - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif` keep or drop the lines in between
+///   depending on whether `NAME` is defined.
+///
+/// Constants shared with Rust are injected with [`Preprocessor::define`], as if every shader
+/// started with their `#define`. Directives and dropped lines are left blank, so every line keeps
+/// its number unless a file is included above it. Lines starting inside a block comment are
+/// never taken as directives.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    /// The source of every file that can be included, by name.
+    includes: HashMap<String, String>,
+    /// The value of every name defined from Rust, which is empty for names without one.
+    defines: HashMap<String, String>,
+}
+
+impl Preprocessor {
+    /// Creates a preprocessor without any include files or defines.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the file with the given source includable as `#include "<name>"`, replacing any
+    /// previous file of the same name.
+    pub fn include(mut self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.includes.insert(name.into(), source.into());
+        self
+    }
+
+    /// Defines `name` as `value` in every shader, which may be empty to only define the name.
+    pub fn define(mut self, name: impl Into<String>, value: impl Display) -> Self {
+        self.defines.insert(name.into(), value.to_string());
+        self
+    }
+
+    /// Expands the directives of the `source` of the file at `path`.
+    ///
+    /// Errors name the file and line of the offending directive.
+    pub fn process(&self, path: &str, source: &str) -> anyhow::Result<Preprocessed> {
+        let mut expansion = Expansion {
+            preprocessor: self,
+            defines: self.defines.clone(),
+            included: HashSet::from([path.to_owned()]),
+            output: Preprocessed {
+                source: String::with_capacity(source.len()),
+                files: Vec::new(),
+                lines: Vec::new(),
+            },
+        };
+
+        expansion.expand(path, source)?;
+
+        Ok(expansion.output)
+    }
+}
+
+/// A WGSL source with all of its directives expanded by a [`Preprocessor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preprocessed {
+    /// The expanded source, ready to be compiled.
+    pub source: String,
+    /// The name of every file making up the `source`, starting with the one preprocessed.
+    files: Vec<String>,
+    /// The index into `files` and the line number within that file of every line of the
+    /// `source`.
+    lines: Vec<(usize, usize)>,
+}
+
+impl Preprocessed {
+    /// Returns whether the file with the given name was included into the source.
+    pub fn includes(&self, name: &str) -> bool {
+        self.files.iter().skip(1).any(|file| file == name)
+    }
+
+    /// Returns the file and the line number within it that the given (1-based) line of the
+    /// expanded `source` came from.
+    pub fn locate(&self, line: usize) -> Option<(&str, usize)> {
+        let &(file, line) = self.lines.get(line.checked_sub(1)?)?;
+
+        Some((&self.files[file], line))
+    }
+
+    /// Parses and validates the expanded source without involving the GPU.
+    ///
+    /// Errors point at the offending line of the file it came from.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn validate(&self) -> anyhow::Result<()> {
+        use wgpu::naga::{
+            front::wgsl,
+            valid::{Capabilities, ValidationFlags, Validator},
+        };
+
+        let module = wgsl::parse_str(&self.source).map_err(|error| {
+            let labels = error.labels().map(|(span, label)| (span, label.to_owned()));
+
+            self.report(error.message(), labels)
+        })?;
 
-        let simulation_shader =
-            device.create_shader_module(include_wgsl!("../../assets/simulation.wgsl"));
+        Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|error| {
+                let mut message = error.as_inner().to_string();
+                let mut cause = std::error::Error::source(error.as_inner());
+
+                while let Some(error) = cause {
+                    let _ = write!(message, ": {error}");
+                    cause = error.source();
+                }
+
+                self.report(&message, error.spans().cloned())
+            })?;
+
+        Ok(())
+    }
+
+    /// Renders an error of the expanded source, quoting the line of every labelled span
+    /// alongside the file and line it came from.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn report(
+        &self,
+        message: &str,
+        labels: impl Iterator<Item = (wgpu::naga::Span, String)>,
+    ) -> anyhow::Error {
+        let mut report = format!("error: {message}");
+
+        for (span, label) in labels {
+            if !span.is_defined() {
+                continue;
+            }
+
+            let location = span.location(&self.source);
+            let line = location.line_number as usize;
+            let column = location.line_position as usize;
+
+            let Some((file, file_line)) = self.locate(line) else {
+                continue;
+            };
+
+            let text = self.source.lines().nth(line - 1).unwrap_or_default();
+            let remaining = text.len().saturating_sub(column - 1).max(1);
+            let length = (location.length as usize).clamp(1, remaining);
+            let margin = " ".repeat(file_line.to_string().len());
+
+            let _ = write!(
+                report,
+                "\n{margin}--> {file}:{file_line}:{column}\n\
+                {margin} |\n\
+                {file_line} | {text}\n\
+                {margin} | {}{} {label}",
+                " ".repeat(column - 1),
+                "^".repeat(length),
+            );
+        }
+
+        anyhow::anyhow!(report)
+    }
+}
+
+/// An `#ifdef` or `#ifndef` whose `#endif` hasn't been reached yet.
+struct Conditional {
+    /// The line number of the directive.
+    line: usize,
+    /// Whether the lines before the `#else` are kept, if the enclosing lines are.
+    condition: bool,
+    /// Whether the enclosing lines are kept.
+    enclosing: bool,
+    /// Whether the `#else` has been reached.
+    in_else: bool,
+}
+
+impl Conditional {
+    /// Returns whether the lines at this point of the conditional are kept.
+    fn active(&self) -> bool {
+        self.enclosing && self.condition != self.in_else
+    }
+}
+
+/// The state of a single [`Preprocessor::process`].
+struct Expansion<'a> {
+    /// The preprocessor holding the include files.
+    preprocessor: &'a Preprocessor,
+    /// Every name defined so far, from Rust or by a `#define`.
+    defines: HashMap<String, String>,
+    /// The name of every file already pasted in, including the one being processed.
+    included: HashSet<String>,
+    /// The expanded source so far.
+    output: Preprocessed,
+}
+
+impl Expansion<'_> {
+    /// Appends the expanded `source` of the file with the given name to the output.
+    fn expand(&mut self, file: &str, source: &str) -> anyhow::Result<()> {
+        let file_index = self.output.files.len();
+        self.output.files.push(file.to_owned());
+
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        // how many (nested) block comments the current line starts out in
+        let mut comment_depth = 0;
+
+        for (index, line) in source.lines().enumerate() {
+            let number = index + 1;
+            let active = conditionals.last().is_none_or(Conditional::active);
+
+            // lines within block comments are never directives
+            let directive = match comment_depth {
+                0 => line.trim_start().strip_prefix('#'),
+                _ => None,
+            };
+
+            let Some(directive) = directive else {
+                // dropped lines are scanned as well, to keep track of the comments they open
+                let text = self.substitute(line, &mut comment_depth);
+                let text = if active { text } else { String::new() };
+
+                self.push_line(&text, file_index, number);
+                continue;
+            };
+
+            let (name, argument) = directive
+                .split_once(char::is_whitespace)
+                .unwrap_or((directive, ""));
+            let argument = argument.trim();
+
+            match name {
+                "include" if active => {
+                    let include = argument
+                        .strip_prefix('"')
+                        .and_then(|argument| argument.strip_suffix('"'))
+                        .with_context(|| {
+                            format!("{file}:{number}: expected a quoted file name after `#include`")
+                        })?;
+
+                    let included = self.preprocessor.includes.get(include).with_context(|| {
+                        format!("{file}:{number}: unknown include file `{include}`")
+                    })?;
+
+                    if self.included.insert(include.to_owned()) {
+                        self.expand(include, included)?;
+                    } else {
+                        self.push_line("", file_index, number);
+                    }
+
+                    continue;
+                }
+                "define" if active => {
+                    let (name, value) = argument
+                        .split_once(char::is_whitespace)
+                        .unwrap_or((argument, ""));
+
+                    ensure!(
+                        is_identifier(name),
+                        "{file}:{number}: expected a name after `#define`"
+                    );
+
+                    self.defines
+                        .insert(name.to_owned(), value.trim().to_owned());
+                }
+                "ifdef" | "ifndef" => {
+                    ensure!(
+                        is_identifier(argument),
+                        "{file}:{number}: expected a name after `#{name}`"
+                    );
+
+                    conditionals.push(Conditional {
+                        line: number,
+                        condition: self.defines.contains_key(argument) == (name == "ifdef"),
+                        enclosing: active,
+                        in_else: false,
+                    });
+                }
+                "else" => {
+                    let Some(conditional) = conditionals.last_mut() else {
+                        bail!("{file}:{number}: `#else` without a matching `#ifdef`");
+                    };
+
+                    ensure!(
+                        !conditional.in_else,
+                        "{file}:{number}: `#else` after another `#else`"
+                    );
+
+                    conditional.in_else = true;
+                }
+                "endif" => {
+                    ensure!(
+                        conditionals.pop().is_some(),
+                        "{file}:{number}: `#endif` without a matching `#ifdef`"
+                    );
+                }
+                // directives within dropped lines are only checked for nesting
+                "include" | "define" => {}
+                _ => bail!("{file}:{number}: unknown directive `#{name}`"),
+            }
+
+            self.push_line("", file_index, number);
+        }
+
+        if let Some(conditional) = conditionals.last() {
+            bail!(
+                "{file}:{}: `#ifdef` is never closed by an `#endif`",
+                conditional.line
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Appends a single line to the output, coming from the given line of the given file.
+    fn push_line(&mut self, text: &str, file: usize, line: usize) {
+        self.output.source.push_str(text);
+        self.output.source.push('\n');
+        self.output.lines.push((file, line));
+    }
+
+    /// Replaces every identifier of the `line` that has been defined with a value by that value,
+    /// leaving comments and the suffixes of numbers alone.
+    ///
+    /// Block comments may span several lines and be nested like in WGSL, with `comment_depth`
+    /// holding how many of them are open at the start and end of the line.
+    fn substitute(&self, line: &str, comment_depth: &mut usize) -> String {
+        let mut output = String::with_capacity(line.len());
+        let mut rest = line;
+
+        while let Some(first) = rest.chars().next() {
+            if *comment_depth == 0 && rest.starts_with("//") {
+                output.push_str(rest);
+                break;
+            }
+
+            if rest.starts_with("/*") {
+                *comment_depth += 1;
+                output.push_str("/*");
+                rest = &rest[2..];
+                continue;
+            }
+
+            if *comment_depth > 0 {
+                let length = if rest.starts_with("*/") {
+                    *comment_depth -= 1;
+                    2
+                } else {
+                    first.len_utf8()
+                };
+
+                output.push_str(&rest[..length]);
+                rest = &rest[length..];
+                continue;
+            }
+
+            let length = if first.is_ascii_alphabetic() || first == '_' {
+                rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            } else if first.is_ascii_digit() {
+                rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+            } else {
+                Some(first.len_utf8())
+            };
+
+            let (word, remainder) = rest.split_at(length.unwrap_or(rest.len()));
+
+            match self.defines.get(word) {
+                Some(value) if !value.is_empty() => output.push_str(value),
+                _ => output.push_str(word),
+            }
+
+            rest = remainder;
+        }
+
+        output
+    }
+}
+
+/// Returns whether `text` is a valid name for a define.
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Polls the shader and include files in a directory for changes.
+///
+/// The modification time of every file is checked at most twice a second, so the watcher can be
+/// polled every frame.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ShaderWatcher {
+    /// The directory holding the shader files.
+    directory: PathBuf,
+    /// The name and last modification time of every file, if it could be read.
+    modified: Vec<(&'static str, Option<SystemTime>)>,
+    /// When the files were last checked.
+    last_poll: Instant,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ShaderWatcher {
+    /// How long to wait between two checks of the files.
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Starts watching every [`ShaderFile`] and include file in `directory`, as they are now.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        let directory = directory.into();
+
+        let modified = ShaderFile::ALL
+            .into_iter()
+            .map(ShaderFile::file_name)
+            .chain(INCLUDE_FILES.into_iter().map(|(name, _)| name))
+            .map(|name| (name, Self::modified(&directory, name)))
+            .collect();
 
         Self {
-            triangle_shader,
-            simulation_shader,
+            directory,
+            modified,
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Returns the directory being watched.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Returns the name of every file modified since the last poll, alongside its new source.
+    pub fn poll(&mut self) -> Vec<(&'static str, anyhow::Result<String>)> {
+        if self.last_poll.elapsed() < Self::POLL_INTERVAL {
+            return Vec::new();
+        }
+
+        self.last_poll = Instant::now();
+
+        let mut changed = Vec::new();
+
+        for (name, modified) in &mut self.modified {
+            let latest = Self::modified(&self.directory, name);
+
+            // files that are missing or being replaced are picked up once they reappear
+            if latest.is_none() || latest == *modified {
+                continue;
+            }
+
+            *modified = latest;
+
+            let path = self.directory.join(*name);
+            let source = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()));
+
+            changed.push((*name, source));
         }
+
+        changed
+    }
+
+    /// Returns the last modification time of the file with the given name in `directory`.
+    fn modified(directory: &Path, name: &str) -> Option<SystemTime> {
+        std::fs::metadata(directory.join(name))
+            .and_then(|metadata| metadata.modified())
+            .ok()
     }
 }
@@ -6,7 +6,7 @@ use wgpu::{
     vertex_attr_array,
 };
 
-use crate::simulation::{SIMULATION_LENGTH, SIMULATION_RESOLUTION};
+use crate::simulation::config::SimulationConfig;
 
 /// A vertex on a [`SurfaceMesh`].
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Zeroable, Pod)]
@@ -18,7 +18,7 @@ pub struct SurfaceVertex {
     pub uv: [f32; 2],
 }
 
-/// The mesh for a flat, subdivided plane centered at the origin.
+/// The mesh for a flat, subdivided plane spanning the simulated domain from the origin.
 pub struct SurfaceMesh {
     /// The vertices making up the mesh stored on the GPU.
     pub vertex_buffer: Buffer,
@@ -30,37 +30,45 @@ pub struct SurfaceMesh {
 }
 
 impl SurfaceMesh {
-    /// Creates a new [`SurfaceMesh`].
-    pub fn new(device: &Device) -> Self {
-        // the vertex positions along a single axis
-        let axis_vertices = (0..SIMULATION_RESOLUTION)
-            .map(|i| (i as f32 / SIMULATION_RESOLUTION as f32)) // map to [0, 1]
-            .map(|t| (t, t * SIMULATION_LENGTH));
+    /// Creates a new [`SurfaceMesh`] with one vertex per cell of the simulation grid.
+    pub fn new(device: &Device, config: &SimulationConfig) -> Self {
+        let (width, depth) = config.grid_size();
 
-        let vertices = iproduct!(axis_vertices.clone(), axis_vertices)
+        // the uv and world space position of each vertex along a single axis, with the uv
+        // pointing at the center of its texel
+        let axis_vertices = |cells: u32| {
+            (0..cells).map(move |i| {
+                let uv = (i as f32 + 0.5) / cells as f32;
+                (uv, i as f32 * config.grid_spacing())
+            })
+        };
+
+        let vertices = iproduct!(axis_vertices(width), axis_vertices(depth))
             .map(|((uv_x, x), (uv_z, z))| SurfaceVertex {
                 position: [x, 0.0, z],
                 uv: [uv_x, uv_z],
             })
             .collect_vec();
 
-        let indices = (0..SIMULATION_RESOLUTION - 1)
+        // vertices are laid out row by row along the x axis, with `depth` vertices per row
+        let stride = depth;
+
+        let indices = (0..width - 1)
             .flat_map(move |row| {
-                (0..SIMULATION_RESOLUTION - 1).flat_map(move |col| {
-                    let row_offset = row * SIMULATION_RESOLUTION;
+                (0..depth - 1).flat_map(move |col| {
+                    let row_offset = row * stride;
                     [
                         // triangle 1
-                        row_offset + SIMULATION_RESOLUTION + col, // top left
-                        row_offset + col,                         // bottom left
-                        row_offset + col + 1,                     // bottom right
+                        row_offset + stride + col, // top left
+                        row_offset + col,          // bottom left
+                        row_offset + col + 1,      // bottom right
                         // triangle 2
-                        row_offset + col + 1, // bottom right
-                        row_offset + SIMULATION_RESOLUTION + col + 1, // top right
-                        row_offset + SIMULATION_RESOLUTION + col, // top left
+                        row_offset + col + 1,          // bottom right
+                        row_offset + stride + col + 1, // top right
+                        row_offset + stride + col,     // top left
                     ]
                 })
             })
-            .map(|index| index as u32)
             .collect_vec();
 
         let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
@@ -80,7 +88,7 @@ impl SurfaceMesh {
         Self {
             vertex_buffer,
             index_buffer,
-            index_count: index_count,
+            index_count,
         }
     }
 }
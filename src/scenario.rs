@@ -0,0 +1,438 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail, ensure};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    renderer::camera::Camera,
+    simulation::{
+        WaveParameters,
+        boundary::{BoundaryConditions, PmlSettings},
+        config::SimulationConfig,
+        expression::{Expression, Variable},
+        initial::{InitialCondition, InitialConditionKind},
+        obstacles::{ObstacleMask, ObstaclePreset, Wall},
+        snapshot::Snapshot,
+        sources::{MAX_SOURCES, Source, SourceShape, Waveform},
+        speed_map::{SpeedMap, SpeedMapPreset},
+    },
+};
+
+/// A whole experiment, from the domain and medium down to the camera pose, stored as a TOML file
+/// meant to be checked into version control.
+///
+/// Every section but the `domain` may be left out, falling back to the same defaults as a new
+/// simulation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// The number of ticks a headless run lasts, if the experiment has a fixed length.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticks: Option<u32>,
+    /// The extent and resolution of the simulated domain.
+    pub domain: SimulationConfig,
+    /// The duration of a single tick.
+    #[serde(default)]
+    pub time: TimeStep,
+    /// How waves behave when reaching each edge of the domain.
+    #[serde(default)]
+    pub boundaries: BoundaryConditions,
+    /// The perfectly matched layer lining any `pml` edges.
+    #[serde(default)]
+    pub pml: PmlSettings,
+    /// The wave speed of every cell.
+    #[serde(default)]
+    pub medium: Medium,
+    /// The solid obstacles inside the domain.
+    #[serde(default)]
+    pub obstacles: Obstacles,
+    /// The state the wave starts out from, or a gaussian bump in the middle of the domain if
+    /// `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial: Option<InitialCondition>,
+    /// The emitters injecting waves into the domain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<Source>,
+    /// Where the camera looks at the domain from in the interactive app.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<CameraPose>,
+}
+
+/// The duration of a single tick of a [`Scenario`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeStep {
+    /// The requested duration of a single tick, or `None` for a comfortably stable one in the
+    /// fastest part of the medium.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dt: Option<f32>,
+    /// Whether ticks exceeding the stability limit are split into several smaller ticks.
+    pub auto_subdivide: bool,
+}
+
+/// The wave speed of every cell of a [`Scenario`], either procedurally generated, loaded from an
+/// image or given by an expression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Medium {
+    /// The procedurally generated medium, or a uniform one if neither this, an `image` nor an
+    /// `expression` is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<SpeedMapPreset>,
+    /// A PGM image mapping black to half and white to the full `speed`, relative to the scenario
+    /// file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<PathBuf>,
+    /// The wave speed c(x, z) as an expression of the world space position.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expression: Option<Expression>,
+    /// The wave speed of the surrounding medium.
+    pub speed: f32,
+}
+
+/// The solid obstacles inside the domain of a [`Scenario`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Obstacles {
+    /// The arrangement of the obstacles.
+    pub preset: ObstaclePreset,
+    /// How the walls of the obstacles interact with the wave.
+    pub wall: Wall,
+}
+
+/// Where the camera looks at the domain from.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraPose {
+    /// The world space position of the camera.
+    pub position: [f32; 3],
+    /// The rotation around the Y axis (in degrees).
+    pub yaw: f32,
+    /// The rotation around the X axis (in degrees).
+    pub pitch: f32,
+    /// The vertical field of view (in degrees).
+    pub fov: f32,
+}
+
+impl Default for TimeStep {
+    fn default() -> Self {
+        Self {
+            dt: None,
+            auto_subdivide: true,
+        }
+    }
+}
+
+impl Default for Medium {
+    fn default() -> Self {
+        Self {
+            preset: None,
+            image: None,
+            expression: None,
+            speed: 1.0,
+        }
+    }
+}
+
+impl Scenario {
+    /// Parses and validates a scenario from the contents of a TOML file.
+    ///
+    /// Paths inside the scenario are left as they are, see [`Scenario::load`].
+    pub fn from_toml(toml: &str) -> anyhow::Result<Self> {
+        let scenario: Self = toml::from_str(toml)?;
+        scenario.validate()?;
+
+        Ok(scenario)
+    }
+
+    /// Encodes the scenario as the contents of a TOML file.
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// Reads and validates the scenario file at `path`, resolving the paths inside it relative to
+    /// the directory of the file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let toml = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read the scenario {}", path.display()))?;
+
+        let mut scenario = Self::from_toml(&toml)
+            .with_context(|| format!("invalid scenario {}", path.display()))?;
+
+        if let (Some(image), Some(directory)) = (&mut scenario.medium.image, path.parent()) {
+            *image = directory.join(&*image);
+        }
+
+        Ok(scenario)
+    }
+
+    /// Writes the scenario to a TOML file at `path`.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_toml()?)
+            .with_context(|| format!("failed to write the scenario {}", path.display()))
+    }
+
+    /// Checks that every field describes a sensible experiment, naming the offending field
+    /// otherwise.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let domain = &self.domain;
+
+        for (field, value) in [
+            ("domain.width", domain.width),
+            ("domain.depth", domain.depth),
+            ("domain.cells_per_unit", domain.cells_per_unit),
+        ] {
+            ensure_positive(field, value)?;
+        }
+
+        // devices are requested with the default limits, which every adapter supports
+        domain
+            .ensure_fits(wgpu::Limits::default().max_texture_dimension_2d)
+            .context("domain is too large")?;
+
+        if let Some(ticks) = self.ticks {
+            ensure!(ticks > 0, "ticks must be at least 1");
+        }
+
+        if let Some(dt) = self.time.dt {
+            ensure_positive("time.dt", dt)?;
+        }
+
+        ensure!(self.pml.thickness > 0, "pml.thickness must be at least 1");
+        ensure_positive("pml.order", self.pml.order)?;
+        ensure!(
+            self.pml.reflection > 0.0 && self.pml.reflection < 1.0,
+            "pml.reflection must lie between 0 and 1, got {}",
+            self.pml.reflection
+        );
+
+        ensure_positive("medium.speed", self.medium.speed)?;
+        let given = [
+            self.medium.preset.is_some(),
+            self.medium.image.is_some(),
+            self.medium.expression.is_some(),
+        ];
+
+        ensure!(
+            given.into_iter().filter(|given| *given).count() <= 1,
+            "only one of medium.preset, medium.image and medium.expression can be given"
+        );
+
+        if let Some(expression) = &self.medium.expression {
+            expression
+                .ensure_variables(&[Variable::X, Variable::Z])
+                .context("invalid medium.expression")?;
+        }
+
+        ensure!(
+            self.sources.len() <= MAX_SOURCES,
+            "sources holds {} sources, but at most {MAX_SOURCES} are supported",
+            self.sources.len()
+        );
+
+        for (index, source) in self.sources.iter().enumerate() {
+            self.validate_source(source)
+                .with_context(|| format!("invalid sources[{index}]"))?;
+        }
+
+        if let Some(initial) = &self.initial {
+            self.validate_initial_condition(initial)
+                .context("invalid initial")?;
+        }
+
+        if let Some(camera) = &self.camera {
+            ensure!(
+                camera.fov > 0.0 && camera.fov < 180.0,
+                "camera.fov must lie between 0 and 180 degrees, got {}",
+                camera.fov
+            );
+            ensure!(
+                camera.position.iter().all(|value| value.is_finite()),
+                "camera.position must be finite"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Generates the wave speed of every cell.
+    pub fn speed_map(&self) -> anyhow::Result<SpeedMap> {
+        let medium = &self.medium;
+
+        if let Some(image) = &medium.image {
+            let bytes = std::fs::read(image)
+                .with_context(|| format!("failed to read medium.image {}", image.display()))?;
+
+            return SpeedMap::from_pgm(&self.domain, &bytes, 0.5 * medium.speed, medium.speed)
+                .with_context(|| format!("invalid medium.image {}", image.display()));
+        }
+
+        if let Some(expression) = &medium.expression {
+            return SpeedMap::from_expression(&self.domain, expression)
+                .context("invalid medium.expression");
+        }
+
+        let preset = medium.preset.unwrap_or(SpeedMapPreset::Uniform);
+
+        Ok(SpeedMap::from_preset(&self.domain, preset, medium.speed))
+    }
+
+    /// Places the solid obstacles inside the domain.
+    pub fn obstacle_mask(&self) -> ObstacleMask {
+        ObstacleMask::from_preset(&self.domain, self.obstacles.preset, self.obstacles.wall)
+    }
+
+    /// Returns the state the wave starts out from.
+    pub fn initial_condition(&self) -> InitialCondition {
+        self.initial.clone().unwrap_or_else(|| {
+            InitialCondition::preset(InitialConditionKind::GaussianBump, &self.domain)
+        })
+    }
+
+    /// Builds the full state of the simulation at the start of the experiment, from which either
+    /// solver can be restored.
+    pub fn to_snapshot(&self) -> anyhow::Result<Snapshot> {
+        let config = self.domain;
+        let speed_map = self.speed_map()?;
+
+        let parameters = match self.time.dt {
+            Some(dt) => WaveParameters {
+                dt,
+                auto_subdivide: self.time.auto_subdivide,
+            },
+            None => WaveParameters {
+                auto_subdivide: self.time.auto_subdivide,
+                ..WaveParameters::for_config(&config, speed_map.max_speed())
+            },
+        };
+
+        let initial_condition = self.initial_condition();
+        let tick_dt = parameters.tick_dt(&config, speed_map.max_speed());
+        let state = initial_condition.generate(&config, &speed_map, tick_dt);
+        let auxiliary = vec![[0.0; 2]; config.cell_count()];
+
+        Ok(Snapshot {
+            config,
+            parameters,
+            boundaries: self.boundaries,
+            pml: self.pml,
+            sources: self.sources.clone(),
+            initial_condition,
+            obstacles: self.obstacle_mask(),
+            speed_map,
+            time: 0.0,
+            active: 0,
+            states: [state.clone(), state],
+            auxiliary: [auxiliary.clone(), auxiliary],
+        })
+    }
+
+    /// Checks that a source has a sensible signal and lies inside the domain.
+    fn validate_source(&self, source: &Source) -> anyhow::Result<()> {
+        ensure_positive("frequency", source.frequency)?;
+        ensure!(source.amplitude.is_finite(), "amplitude must be finite");
+        ensure!(
+            source.start_time <= source.end_time,
+            "start_time must not come after end_time"
+        );
+
+        if let Waveform::Expression(drive) = &source.waveform {
+            drive.to_wgsl().context("invalid waveform.expression")?;
+        }
+
+        match source.shape {
+            SourceShape::Point { position } => self.ensure_inside("shape.position", position),
+            SourceShape::Line { start, end } => {
+                self.ensure_inside("shape.start", start)?;
+                self.ensure_inside("shape.end", end)
+            }
+            SourceShape::Ring { center, radius } => {
+                ensure_positive("shape.radius", radius)?;
+                self.ensure_inside("shape.center", center)
+            }
+        }
+    }
+
+    /// Checks that the extents of an initial condition are positive.
+    fn validate_initial_condition(&self, initial: &InitialCondition) -> anyhow::Result<()> {
+        match *initial {
+            InitialCondition::GaussianBump { center, radius, .. } => {
+                ensure_positive("radius", radius)?;
+                self.ensure_inside("center", center)
+            }
+            InitialCondition::Ring {
+                center,
+                radius,
+                width,
+                ..
+            } => {
+                ensure_positive("radius", radius)?;
+                ensure_positive("width", width)?;
+                self.ensure_inside("center", center)
+            }
+            InitialCondition::PlaneWave {
+                center,
+                wavelength,
+                length,
+                ..
+            } => {
+                ensure_positive("wavelength", wavelength)?;
+                ensure_positive("length", length)?;
+                self.ensure_inside("center", center)
+            }
+            InitialCondition::Eigenmode { m, n, .. } => {
+                ensure!(m > 0 && n > 0, "m and n must be at least 1");
+                Ok(())
+            }
+            InitialCondition::Noise { scale, .. } => ensure_positive("scale", scale),
+            // any variable is meaningful, and the expression was type checked when parsed
+            InitialCondition::Expression { .. } => Ok(()),
+        }
+    }
+
+    /// Checks that the world space `position` lies inside the domain.
+    fn ensure_inside(&self, field: &str, position: [f32; 2]) -> anyhow::Result<()> {
+        let [x, z] = position;
+
+        if !(0.0..=self.domain.width).contains(&x) || !(0.0..=self.domain.depth).contains(&z) {
+            bail!(
+                "{field} ({x}, {z}) lies outside of the {} by {} domain",
+                self.domain.width,
+                self.domain.depth
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl CameraPose {
+    /// Captures the pose of the `camera`.
+    pub fn from_camera(camera: &Camera) -> Self {
+        Self {
+            position: camera.position.to_array(),
+            yaw: camera.yaw.to_degrees(),
+            pitch: camera.pitch.to_degrees(),
+            fov: camera.fov.to_degrees(),
+        }
+    }
+
+    /// Moves the `camera` into this pose, keeping its other settings.
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.position = Vec3::from_array(self.position);
+        camera.yaw = self.yaw.to_radians();
+        camera.pitch = self.pitch.to_radians();
+        camera.fov = self.fov.to_radians();
+    }
+}
+
+/// Checks that the value of `field` is positive and finite.
+fn ensure_positive(field: &str, value: f32) -> anyhow::Result<()> {
+    ensure!(
+        value.is_finite() && value > 0.0,
+        "{field} must be positive, got {value}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+
+/// How the wave behaves when reaching one edge of the simulated domain.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Boundary {
+    /// The edge is held fixed at zero (Dirichlet), like the rim of a drum.
+    #[default]
+    Fixed,
+    /// The edge is free to move with zero slope (Neumann), like water against a wall.
+    Free,
+    /// The edge wraps around to the opposite edge, tiling the domain.
+    Periodic,
+    /// The edge absorbs incoming waves using a first order Mur condition, like open water.
+    Absorbing,
+    /// The edge is lined with a perfectly matched layer, absorbing waves at any angle of
+    /// incidence. The outermost cells of the layer are held fixed.
+    Pml,
+}
+
+/// Describes the perfectly matched layer lining every [`Boundary::Pml`] edge.
+///
+/// The damping inside the layer grows from zero at its inner edge to its maximum at the outer edge
+/// following a polynomial profile, with the maximum chosen so that a wave crossing the layer and
+/// back is attenuated by the `reflection` factor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PmlSettings {
+    /// The thickness of the layer (in cells).
+    pub thickness: u32,
+    /// The order of the polynomial damping profile, typically between 2 and 4.
+    pub order: f32,
+    /// The theoretical reflection coefficient of the layer at normal incidence.
+    pub reflection: f32,
+}
+
+/// One of the four edges of the rectangular domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edge {
+    /// The edge at x = 0.
+    XMin,
+    /// The edge at x = width.
+    XMax,
+    /// The edge at z = 0.
+    ZMin,
+    /// The edge at z = depth.
+    ZMax,
+}
+
+/// The [`Boundary`] applied on each of the four edges of the domain.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoundaryConditions {
+    /// The edge at x = 0.
+    pub x_min: Boundary,
+    /// The edge at x = width.
+    pub x_max: Boundary,
+    /// The edge at z = 0.
+    pub z_min: Boundary,
+    /// The edge at z = depth.
+    pub z_max: Boundary,
+}
+
+impl Boundary {
+    /// All boundary kinds, in the order they are presented to the user.
+    pub const ALL: [Self; 5] = [
+        Self::Fixed,
+        Self::Free,
+        Self::Periodic,
+        Self::Absorbing,
+        Self::Pml,
+    ];
+
+    /// Returns the identifier of the boundary kind used by `simulation.wgsl`.
+    pub fn gpu_id(self) -> u32 {
+        match self {
+            Self::Fixed => 0,
+            Self::Free => 1,
+            Self::Periodic => 2,
+            Self::Absorbing => 3,
+            Self::Pml => 4,
+        }
+    }
+
+    /// Returns a human readable name of the boundary kind.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fixed => "Fixed (Dirichlet)",
+            Self::Free => "Free (Neumann)",
+            Self::Periodic => "Periodic",
+            Self::Absorbing => "Absorbing (Mur)",
+            Self::Pml => "Perfectly matched layer",
+        }
+    }
+}
+
+impl Default for PmlSettings {
+    fn default() -> Self {
+        Self {
+            thickness: 20,
+            order: 3.0,
+            reflection: 1e-4,
+        }
+    }
+}
+
+impl PmlSettings {
+    /// Returns the damping coefficient at the outermost cell of the layer, for waves travelling
+    /// at `wave_speed` on a grid with the given spacing.
+    pub fn max_damping(&self, wave_speed: f32, dx: f32) -> f32 {
+        let width = self.thickness.max(1) as f32 * dx;
+
+        (self.order + 1.0) * wave_speed * (1.0 / self.reflection).ln() / (2.0 * width)
+    }
+}
+
+impl Edge {
+    /// All edges, in the order x_min, x_max, z_min, z_max.
+    pub const ALL: [Self; 4] = [Self::XMin, Self::XMax, Self::ZMin, Self::ZMax];
+
+    /// Returns the edge on the opposite side of the same axis.
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::XMin => Self::XMax,
+            Self::XMax => Self::XMin,
+            Self::ZMin => Self::ZMax,
+            Self::ZMax => Self::ZMin,
+        }
+    }
+
+    /// Returns a short human readable name of the edge.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::XMin => "-X",
+            Self::XMax => "+X",
+            Self::ZMin => "-Z",
+            Self::ZMax => "+Z",
+        }
+    }
+}
+
+impl BoundaryConditions {
+    /// Applies the same [`Boundary`] to every edge.
+    pub fn uniform(boundary: Boundary) -> Self {
+        Self {
+            x_min: boundary,
+            x_max: boundary,
+            z_min: boundary,
+            z_max: boundary,
+        }
+    }
+
+    /// Returns the [`Boundary`] applied on the given edge.
+    pub fn get(&self, edge: Edge) -> Boundary {
+        match edge {
+            Edge::XMin => self.x_min,
+            Edge::XMax => self.x_max,
+            Edge::ZMin => self.z_min,
+            Edge::ZMax => self.z_max,
+        }
+    }
+
+    /// Sets the [`Boundary`] of the given edge.
+    ///
+    /// Periodic edges always come in pairs, so switching an edge to or from [`Boundary::Periodic`]
+    /// switches its opposite edge as well.
+    pub fn set(&mut self, edge: Edge, boundary: Boundary) {
+        let previous = self.get(edge);
+
+        *self.get_mut(edge) = boundary;
+
+        if boundary == Boundary::Periodic || previous == Boundary::Periodic {
+            *self.get_mut(edge.opposite()) = boundary;
+        }
+    }
+
+    /// Returns whether any edge uses the given [`Boundary`].
+    pub fn contains(&self, boundary: Boundary) -> bool {
+        Edge::ALL.into_iter().any(|edge| self.get(edge) == boundary)
+    }
+
+    /// Returns the GPU identifiers of each edge, in the order x_min, x_max, z_min, z_max.
+    pub fn gpu_ids(&self) -> [u32; 4] {
+        Edge::ALL.map(|edge| self.get(edge).gpu_id())
+    }
+
+    /// Returns a mutable reference to the [`Boundary`] of the given edge.
+    fn get_mut(&mut self, edge: Edge) -> &mut Boundary {
+        match edge {
+            Edge::XMin => &mut self.x_min,
+            Edge::XMax => &mut self.x_max,
+            Edge::ZMin => &mut self.z_min,
+            Edge::ZMax => &mut self.z_max,
+        }
+    }
+}
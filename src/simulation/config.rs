@@ -0,0 +1,69 @@
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+
+/// Describes the extent and resolution of the simulated domain.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimulationConfig {
+    /// The extent of the domain across the X axis (in world units).
+    pub width: f32,
+    /// The extent of the domain across the Z axis (in world units).
+    pub depth: f32,
+    /// How many grid cells each world unit is subdivided into.
+    pub cells_per_unit: f32,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            width: 5.0,
+            depth: 5.0,
+            cells_per_unit: 100.0,
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// The smallest number of cells allowed along either axis.
+    pub const MIN_CELLS: u32 = 2;
+
+    /// Returns the number of grid cells across the X and Z axes respectively.
+    pub fn grid_size(&self) -> (u32, u32) {
+        let cells =
+            |extent: f32| ((extent * self.cells_per_unit).round() as u32).max(Self::MIN_CELLS);
+
+        (cells(self.width), cells(self.depth))
+    }
+
+    /// Checks that the grid fits into textures of at most `max_cells` texels per side, such as the
+    /// `max_texture_dimension_2d` limit of a device.
+    pub fn ensure_fits(&self, max_cells: u32) -> anyhow::Result<()> {
+        let (width, depth) = self.grid_size();
+
+        ensure!(
+            width <= max_cells && depth <= max_cells,
+            "a grid of {width}x{depth} cells exceeds the GPU limit of {max_cells} cells per side"
+        );
+
+        Ok(())
+    }
+
+    /// Returns the distance between two adjacent cells of the grid (dx).
+    pub fn grid_spacing(&self) -> f32 {
+        1.0 / self.cells_per_unit
+    }
+
+    /// Returns the total number of cells in the grid.
+    pub fn cell_count(&self) -> usize {
+        let (width, depth) = self.grid_size();
+
+        width as usize * depth as usize
+    }
+
+    /// Returns the world space position (x, z) of the cell at the given grid coordinates.
+    pub fn cell_position(&self, x: u32, z: u32) -> (f32, f32) {
+        let dx = self.grid_spacing();
+
+        (x as f32 * dx, z as f32 * dx)
+    }
+}
@@ -0,0 +1,696 @@
+use crate::simulation::{
+    Impulse, MAX_IMPULSES, MAX_SUBSTEPS, WaveParameters,
+    boundary::{Boundary, BoundaryConditions, Edge, PmlSettings},
+    config::SimulationConfig,
+    initial::{InitialCondition, InitialConditionKind},
+    obstacles::{ObstacleMask, Wall},
+    snapshot::Snapshot,
+    sources::{MAX_SOURCES, Source, Waveform, source_falloff},
+    speed_map::SpeedMap,
+    statistics::{StatisticsPartial, WaveStatistics},
+};
+
+/// A pure CPU implementation of the same scheme as [`WaveSimulation`](super::WaveSimulation),
+/// serving as a reference to validate `simulation.wgsl` against, and as a fallback where no GPU
+/// is available.
+///
+/// Every helper of the tick mirrors the function of the same name in `simulation.wgsl`, so any
+/// change to the shader must be reflected here.
+#[derive(Debug, Clone)]
+pub struct CpuSimulation {
+    /// The physical parameters of the simulated medium.
+    pub parameters: WaveParameters,
+    /// How waves behave when reaching each edge of the domain.
+    pub boundaries: BoundaryConditions,
+    /// The perfectly matched layer lining any edges with a [`Boundary::Pml`] condition.
+    pub pml: PmlSettings,
+    /// The emitters injecting waves into the domain, of which at most [`MAX_SOURCES`] are used.
+    pub sources: Vec<Source>,
+
+    /// The simulation time elapsed since the initial state.
+    time: f32,
+    /// The impulses waiting to be applied during the next tick.
+    pending_impulses: Vec<Impulse>,
+    /// The extent and resolution of the simulated domain.
+    config: SimulationConfig,
+    /// The state the wave starts out from, and is restored to on reset.
+    initial_condition: InitialCondition,
+    /// The wave speed of every cell.
+    speed_map: SpeedMap,
+    /// The solid cells of the domain.
+    obstacles: ObstacleMask,
+
+    /// u(t) and u(t - dt) of every cell, stored row by row along the X axis.
+    state: Vec<[f32; 2]>,
+    /// The (x, z) components of the perfectly matched layer's auxiliary field of every cell.
+    auxiliary: Vec<[f32; 2]>,
+}
+
+/// The inputs of a single tick, mirroring the bindings of `simulation.wgsl`.
+struct Tick<'a> {
+    /// The simulation being advanced, whose state is read from.
+    simulation: &'a CpuSimulation,
+    /// The number of cells across the X axis.
+    width: i32,
+    /// The number of cells across the Z axis.
+    depth: i32,
+    /// The duration of the tick (dt).
+    dt: f32,
+    /// The distance between two adjacent cells of the grid (dx).
+    dx: f32,
+    /// The damping coefficient at the outermost cell of the perfectly matched layer.
+    pml_max_damping: f32,
+    /// The simulation time at the start of the tick.
+    time: f32,
+    /// The enabled sources alongside their signal during the tick, skipping silent ones, or
+    /// [`None`] for sources driven by an expression, whose signal varies from cell to cell.
+    signals: Vec<(&'a Source, Option<f32>)>,
+    /// The impulses to apply during the tick.
+    impulses: &'a [Impulse],
+}
+
+impl CpuSimulation {
+    /// Creates a [`CpuSimulation`] in the same default state as a new
+    /// [`WaveSimulation`](super::WaveSimulation).
+    pub fn new(config: SimulationConfig) -> Self {
+        let speed_map = SpeedMap::uniform(&config, 1.0);
+
+        let mut simulation = Self {
+            parameters: WaveParameters::for_config(&config, speed_map.max_speed()),
+            boundaries: BoundaryConditions::default(),
+            pml: PmlSettings::default(),
+            sources: Vec::new(),
+            time: 0.0,
+            pending_impulses: Vec::new(),
+            initial_condition: InitialCondition::preset(
+                InitialConditionKind::GaussianBump,
+                &config,
+            ),
+            obstacles: ObstacleMask::empty(&config),
+            speed_map,
+            config,
+            state: Vec::new(),
+            auxiliary: Vec::new(),
+        };
+
+        simulation.reset();
+        simulation
+    }
+
+    /// Returns the extent and resolution of the simulated domain.
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    /// Returns the simulation time elapsed since the initial state.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Returns the wave speed of every cell.
+    pub fn speed_map(&self) -> &SpeedMap {
+        &self.speed_map
+    }
+
+    /// Replaces the wave speed of every cell, resampling the map onto the grid if needed.
+    pub fn set_speed_map(&mut self, speed_map: SpeedMap) {
+        self.speed_map = if speed_map.size() == self.config.grid_size() {
+            speed_map
+        } else {
+            speed_map.resample(&self.config)
+        };
+    }
+
+    /// Returns the solid obstacles inside the domain.
+    pub fn obstacles(&self) -> &ObstacleMask {
+        &self.obstacles
+    }
+
+    /// Replaces the solid obstacles inside the domain, resampling the mask onto the grid if needed.
+    pub fn set_obstacles(&mut self, obstacles: ObstacleMask) {
+        self.obstacles = if obstacles.size() == self.config.grid_size() {
+            obstacles
+        } else {
+            obstacles.resample(&self.config)
+        };
+    }
+
+    /// Returns the state the wave starts out from.
+    pub fn initial_condition(&self) -> &InitialCondition {
+        &self.initial_condition
+    }
+
+    /// Replaces the state the wave starts out from, resetting the simulation to it.
+    pub fn set_initial_condition(&mut self, initial_condition: InitialCondition) {
+        self.initial_condition = initial_condition;
+        self.reset();
+    }
+
+    /// Changes the extent and resolution of the simulated domain, resampling the medium and
+    /// obstacles and resetting the simulation.
+    ///
+    /// Like [`WaveSimulation::reconfigure`](super::WaveSimulation::reconfigure), the timestep is
+    /// kept unless `auto_subdivide` is off, in which case it is replaced by a stable one.
+    pub fn reconfigure(&mut self, config: SimulationConfig) {
+        self.config = config;
+        self.speed_map = self.speed_map.resample(&config);
+        self.obstacles = self.obstacles.resample(&config);
+
+        // a finer grid would otherwise halt the simulation, with nothing to subdivide its ticks
+        if !self.parameters.auto_subdivide {
+            self.parameters.dt = WaveParameters::stable_dt(&config, self.speed_map.max_speed());
+        }
+
+        self.reset();
+    }
+
+    /// Restores the initial state of the wave, rewinding the simulation time to zero.
+    ///
+    /// The medium, obstacles and sources are kept as they are.
+    pub fn reset(&mut self) {
+        self.time = 0.0;
+        self.pending_impulses.clear();
+
+        self.state = self
+            .initial_condition
+            .generate(&self.config, &self.speed_map, self.tick_dt());
+        self.auxiliary = vec![[0.0; 2]; self.config.cell_count()];
+    }
+
+    /// Returns the Courant number c·dt/dx of the requested `dt`, using the fastest wave speed of
+    /// the medium.
+    pub fn courant_number(&self) -> f32 {
+        self.parameters
+            .courant_number(&self.config, self.speed_map.max_speed())
+    }
+
+    /// Returns the duration of a single tick that is actually run, after any subdivision.
+    pub fn tick_dt(&self) -> f32 {
+        self.parameters
+            .tick_dt(&self.config, self.speed_map.max_speed())
+    }
+
+    /// Returns whether ticks of [`CpuSimulation::tick_dt`] are stable, or the simulation refuses
+    /// to run.
+    pub fn tick_is_stable(&self) -> bool {
+        self.parameters
+            .tick_is_stable(&self.config, self.speed_map.max_speed())
+    }
+
+    /// Returns u(t) and u(t - dt) of every cell, stored row by row along the X axis.
+    pub fn state(&self) -> &[[f32; 2]] {
+        &self.state
+    }
+
+    /// Captures the full state of the simulation as a [`Snapshot`].
+    ///
+    /// The state is stored as the one held by texture 'a', from which a
+    /// [`WaveSimulation`](super::WaveSimulation) continues exactly as this simulation would.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            config: self.config,
+            parameters: self.parameters,
+            boundaries: self.boundaries,
+            pml: self.pml,
+            sources: self.sources.clone(),
+            initial_condition: self.initial_condition.clone(),
+            speed_map: self.speed_map.clone(),
+            obstacles: self.obstacles.clone(),
+            time: self.time,
+            active: 0,
+            states: [self.state.clone(), self.state.clone()],
+            auxiliary: [self.auxiliary.clone(), self.auxiliary.clone()],
+        }
+    }
+
+    /// Restores the full state of the simulation from a [`Snapshot`], dropping any pending
+    /// impulses.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        let active = snapshot.active as usize % 2;
+        let [state_a, state_b] = snapshot.states;
+        let [auxiliary_a, auxiliary_b] = snapshot.auxiliary;
+
+        self.parameters = snapshot.parameters;
+        self.boundaries = snapshot.boundaries;
+        self.pml = snapshot.pml;
+        self.sources = snapshot.sources;
+        self.config = snapshot.config;
+        self.initial_condition = snapshot.initial_condition;
+        self.speed_map = snapshot.speed_map;
+        self.obstacles = snapshot.obstacles;
+
+        self.time = snapshot.time;
+        self.pending_impulses.clear();
+
+        (self.state, self.auxiliary) = if active == 0 {
+            (state_a, auxiliary_a)
+        } else {
+            (state_b, auxiliary_b)
+        };
+    }
+
+    /// Measures the [`WaveStatistics`] of the current state, mirroring the reduction in
+    /// `statistics.wgsl`.
+    pub fn statistics(&self) -> WaveStatistics {
+        let tick = Tick::new(self, self.time, &[]);
+
+        (0..tick.depth)
+            .flat_map(|z| (0..tick.width).map(move |x| (x, z)))
+            .map(|(x, z)| tick.cell_statistics(x, z))
+            .fold(StatisticsPartial::EMPTY, StatisticsPartial::combine)
+            .finish(self.config.cell_count(), self.time)
+    }
+
+    /// Queues an [`Impulse`] to be added to the wave during the next tick.
+    pub fn poke(&mut self, impulse: Impulse) {
+        self.pending_impulses.push(impulse);
+    }
+
+    /// Advances the simulation by `substeps` ticks (up to [`MAX_SUBSTEPS`]), applying pending
+    /// [`Impulse`]s during the first of them, mirroring [`WaveSimulation::step`].
+    ///
+    /// [`WaveSimulation::step`]: super::WaveSimulation::step
+    pub fn step(&mut self, substeps: u32) {
+        let substeps = substeps.min(MAX_SUBSTEPS);
+
+        // an unstable tick would blow up the whole wave within a few frames
+        if substeps == 0 || !self.tick_is_stable() {
+            return;
+        }
+
+        // impulses beyond the per-tick limit are left for the following steps
+        let impulse_count = self.pending_impulses.len().min(MAX_IMPULSES);
+        let impulses = self
+            .pending_impulses
+            .drain(..impulse_count)
+            .collect::<Vec<_>>();
+        let start_time = self.time;
+
+        for substep in 0..substeps {
+            let time = start_time + substep as f32 * self.tick_dt();
+            let impulses: &[Impulse] = if substep == 0 { &impulses } else { &[] };
+
+            self.tick(time, impulses);
+        }
+    }
+
+    /// Advances the simulation by a single tick starting at `time`, mirroring `main` in
+    /// `simulation.wgsl`.
+    fn tick(&mut self, time: f32, impulses: &[Impulse]) {
+        let tick = Tick::new(self, time, impulses);
+        let (width, depth) = (tick.width, tick.depth);
+
+        let mut state = Vec::with_capacity(self.state.len());
+        let mut auxiliary = Vec::with_capacity(self.auxiliary.len());
+
+        for z in 0..depth {
+            for x in 0..width {
+                state.push(tick.next_state(x, z));
+                auxiliary.push(tick.auxiliary_next(x, z));
+            }
+        }
+
+        self.state = state;
+        self.auxiliary = auxiliary;
+        self.time += self.tick_dt();
+    }
+}
+
+impl<'a> Tick<'a> {
+    /// Collects the inputs of a tick of `simulation` starting at `time`.
+    fn new(simulation: &'a CpuSimulation, time: f32, impulses: &'a [Impulse]) -> Self {
+        let (width, depth) = simulation.config.grid_size();
+        let dx = simulation.config.grid_spacing();
+
+        let signals = simulation
+            .sources
+            .iter()
+            .filter(|source| source.enabled)
+            .take(MAX_SOURCES)
+            .map(|source| match source.waveform {
+                Waveform::Expression(_) => (source, None),
+                _ => (source, Some(source.signal(time, 0.0, 0.0))),
+            })
+            .filter(|(_, signal)| *signal != Some(0.0))
+            .collect();
+
+        Self {
+            simulation,
+            width: width as i32,
+            depth: depth as i32,
+            dt: simulation.tick_dt(),
+            dx,
+            pml_max_damping: simulation
+                .pml
+                .max_damping(simulation.speed_map.max_speed(), dx),
+            time,
+            signals,
+            impulses,
+        }
+    }
+
+    /// Returns u(x, t+1) and u(x, t) of the given cell.
+    fn next_state(&self, x: i32, z: i32) -> [f32; 2] {
+        let boundaries = &self.simulation.boundaries;
+        let is_absorbing = |edge| boundaries.get(edge) == Boundary::Absorbing;
+
+        let u = self.state_at(x, z)[0];
+
+        // cells on an absorbing edge are extrapolated from their inner neighbour, every other
+        // cell is advanced by the interior stencil
+        let mut u_next = if self.obstacle_at(x, z).is_some() {
+            0.0
+        } else if x == 0 && is_absorbing(Edge::XMin) {
+            self.mur((x, z), (x + 1, z))
+        } else if x == self.width - 1 && is_absorbing(Edge::XMax) {
+            self.mur((x, z), (x - 1, z))
+        } else if z == 0 && is_absorbing(Edge::ZMin) {
+            self.mur((x, z), (x, z + 1))
+        } else if z == self.depth - 1 && is_absorbing(Edge::ZMax) {
+            self.mur((x, z), (x, z - 1))
+        } else {
+            self.leapfrog(x, z)
+        };
+
+        // impulses are added to both time levels so the bump starts out at rest
+        let mut bump = 0.0;
+
+        if self.obstacle_at(x, z).is_none() {
+            u_next += self.dt * self.dt * self.forcing(x, z);
+            bump = self.impulse_displacement(x, z);
+        }
+
+        [u_next + bump, u + bump]
+    }
+
+    /// Returns u(x, t+1) at the given cell using the leapfrog discretization of the wave equation.
+    fn leapfrog(&self, x: i32, z: i32) -> f32 {
+        let [u, u_previous] = self.state_at(x, z);
+
+        let laplacian = (self.neighbour_at(x + 1, z, u)
+            + self.neighbour_at(x - 1, z, u)
+            + self.neighbour_at(x, z + 1, u)
+            + self.neighbour_at(x, z - 1, u)
+            - 4.0 * u)
+            / (self.dx * self.dx);
+
+        let c = self.speed_at(x, z);
+        let dt = self.dt;
+        let [sigma_x, sigma_z] = self.pml_damping(x, z);
+
+        if sigma_x == 0.0 && sigma_z == 0.0 {
+            return 2.0 * u - u_previous + c * c * dt * dt * laplacian;
+        }
+
+        let divergence = (self.psi_at(x + 1, z)[0] - self.psi_at(x - 1, z)[0]
+            + self.psi_at(x, z + 1)[1]
+            - self.psi_at(x, z - 1)[1])
+            / (2.0 * self.dx);
+
+        // the first order damping term is discretized with central differences, which makes the
+        // update semi-implicit in u(x, t+1)
+        let a = 0.5 * dt * (sigma_x + sigma_z);
+
+        (2.0 * u - (1.0 - a) * u_previous - dt * dt * sigma_x * sigma_z * u
+            + dt * dt * (c * c * laplacian + divergence))
+            / (1.0 + a)
+    }
+
+    /// Returns the auxiliary field psi(x, t+1) of the perfectly matched layer at the given cell.
+    fn auxiliary_next(&self, x: i32, z: i32) -> [f32; 2] {
+        let [sigma_x, sigma_z] = self.pml_damping(x, z);
+
+        if sigma_x == 0.0 && sigma_z == 0.0 {
+            return [0.0; 2];
+        }
+
+        let [psi_x, psi_z] = self.psi_at(x, z);
+
+        let gradient_x = (self.u_at(x + 1, z) - self.u_at(x - 1, z)) / (2.0 * self.dx);
+        let gradient_z = (self.u_at(x, z + 1) - self.u_at(x, z - 1)) / (2.0 * self.dx);
+
+        let c = self.speed_at(x, z);
+        let dt = self.dt;
+
+        [
+            psi_x + dt * (-sigma_x * psi_x + c * c * (sigma_z - sigma_x) * gradient_x),
+            psi_z + dt * (-sigma_z * psi_z + c * c * (sigma_x - sigma_z) * gradient_z),
+        ]
+    }
+
+    /// Returns the sum of the forcing terms of all sources at the given cell.
+    fn forcing(&self, x: i32, z: i32) -> f32 {
+        let (position_x, position_z) = self.position(x, z);
+
+        self.signals
+            .iter()
+            .map(|(source, signal)| {
+                let signal =
+                    signal.unwrap_or_else(|| source.signal(self.time, position_x, position_z));
+
+                signal * source_falloff(source.shape.distance(position_x, position_z), self.dx)
+            })
+            .sum()
+    }
+
+    /// Returns the total height of all impulses applied during the tick at the given cell.
+    fn impulse_displacement(&self, x: i32, z: i32) -> f32 {
+        let (position_x, position_z) = self.position(x, z);
+
+        self.impulses
+            .iter()
+            .map(|impulse| {
+                let offset_x = (position_x - impulse.position[0]) / impulse.radius;
+                let offset_z = (position_z - impulse.position[1]) / impulse.radius;
+
+                impulse.amplitude * (-0.5 * (offset_x * offset_x + offset_z * offset_z)).exp()
+            })
+            .sum()
+    }
+
+    /// Returns the damping coefficients (sigma_x, sigma_z) of the perfectly matched layer at the
+    /// given cell, which are zero outside of the layer.
+    fn pml_damping(&self, x: i32, z: i32) -> [f32; 2] {
+        let pml = &self.simulation.pml;
+        let boundaries = &self.simulation.boundaries;
+
+        let thickness = pml.thickness as f32;
+
+        if thickness <= 0.0 {
+            return [0.0; 2];
+        }
+
+        // how far into the layer the cell lies on each edge, from 0 at its inner edge to 1 at the
+        // outermost cell
+        let axis_depth = |position: i32, length: i32, min_edge: Edge, max_edge: Edge| {
+            let position = position as f32;
+
+            let depth_min = (thickness - position) / thickness;
+            let depth_max = (position - (length as f32 - 1.0 - thickness)) / thickness;
+
+            let mut depth = 0.0f32;
+
+            if boundaries.get(min_edge) == Boundary::Pml {
+                depth = depth.max(depth_min);
+            }
+            if boundaries.get(max_edge) == Boundary::Pml {
+                depth = depth.max(depth_max);
+            }
+
+            depth
+        };
+
+        let damping = |depth: f32| {
+            if depth > 0.0 {
+                self.pml_max_damping * depth.clamp(1e-6, 1.0).powf(pml.order)
+            } else {
+                0.0
+            }
+        };
+
+        [
+            damping(axis_depth(x, self.width, Edge::XMin, Edge::XMax)),
+            damping(axis_depth(z, self.depth, Edge::ZMin, Edge::ZMax)),
+        ]
+    }
+
+    /// Returns the auxiliary field psi(x, t) at the given coordinates, which is zero outside the
+    /// grid.
+    fn psi_at(&self, x: i32, z: i32) -> [f32; 2] {
+        if !self.in_grid(x, z) {
+            return [0.0; 2];
+        }
+
+        self.simulation.auxiliary[self.index(x, z)]
+    }
+
+    /// Returns u(x, t+1) at a cell on an absorbing edge using the first order Mur condition.
+    fn mur(&self, (x, z): (i32, i32), (inner_x, inner_z): (i32, i32)) -> f32 {
+        let c_dt = self.speed_at(x, z) * self.dt;
+        let ratio = (c_dt - self.dx) / (c_dt + self.dx);
+
+        let u_edge = self.state_at(x, z)[0];
+        let u_inner = self.state_at(inner_x, inner_z)[0];
+
+        u_inner + ratio * (self.leapfrog(inner_x, inner_z) - u_edge)
+    }
+
+    /// Returns u(x, t) at the given coordinates, resolving cells outside of the grid according to
+    /// the boundary condition of the edge they lie beyond.
+    fn u_at(&self, x: i32, z: i32) -> f32 {
+        let (x, z) = self.resolve(x, z);
+
+        // fixed edges hold every cell beyond them at zero
+        if !self.in_grid(x, z) {
+            return 0.0;
+        }
+
+        self.state_at(x, z)[0]
+    }
+
+    /// Returns u(x, t) of the neighbour at the given coordinates of a cell whose own value is `u`.
+    ///
+    /// Reflecting obstacles mirror the value of the cell they border, giving them zero slope.
+    fn neighbour_at(&self, x: i32, z: i32, u: f32) -> f32 {
+        let (x, z) = self.resolve(x, z);
+
+        if !self.in_grid(x, z) {
+            return 0.0;
+        }
+
+        if self.obstacle_at(x, z) == Some(Wall::Reflecting) {
+            return u;
+        }
+
+        self.state_at(x, z)[0]
+    }
+
+    /// Computes the statistics of a single cell, mirroring `cell_partial` in `statistics.wgsl`.
+    fn cell_statistics(&self, x: i32, z: i32) -> StatisticsPartial {
+        let boundaries = &self.simulation.boundaries;
+        let values @ [u, u_previous] = self.state_at(x, z);
+        let c = self.speed_at(x, z);
+
+        let velocity = (u - u_previous) / self.dt;
+
+        // each cell accounts for the edges to its neighbours towards +X and +Z, while the cells
+        // along the min edges also account for the edges to the ghost cells behind them, unless
+        // these wrap around to the opposite side and are already accounted for there
+        let mut gradient_product = self.edge_gradient_product((x, z), (1, 0), values)
+            + self.edge_gradient_product((x, z), (0, 1), values);
+
+        if x == 0 && boundaries.get(Edge::XMin) != Boundary::Periodic {
+            gradient_product += self.edge_gradient_product((x, z), (-1, 0), values);
+        }
+
+        if z == 0 && boundaries.get(Edge::ZMin) != Boundary::Periodic {
+            gradient_product += self.edge_gradient_product((x, z), (0, -1), values);
+        }
+
+        let area = self.dx * self.dx;
+
+        StatisticsPartial {
+            kinetic_energy: 0.5 * velocity * velocity * area,
+            potential_energy: 0.5 * c * c * gradient_product * area,
+            min: u,
+            max: u,
+            sum: u,
+            sum_of_squares: u * u,
+        }
+    }
+
+    /// Returns the product of the gradients of u(t) and u(t - dt) across the edge between the
+    /// given cell holding `values` and its neighbour at `offset`.
+    ///
+    /// Reflecting obstacles give the wave zero slope, so edges touching them carry no energy.
+    fn edge_gradient_product(
+        &self,
+        (x, z): (i32, i32),
+        (offset_x, offset_z): (i32, i32),
+        values: [f32; 2],
+    ) -> f32 {
+        let (neighbour_x, neighbour_z) = self.resolve(x + offset_x, z + offset_z);
+        let neighbour_in_grid = self.in_grid(neighbour_x, neighbour_z);
+
+        let is_reflecting = |x, z| self.obstacle_at(x, z) == Some(Wall::Reflecting);
+
+        if is_reflecting(x, z) || (neighbour_in_grid && is_reflecting(neighbour_x, neighbour_z)) {
+            return 0.0;
+        }
+
+        // fixed edges hold every cell beyond them at zero
+        let neighbour_values = if neighbour_in_grid {
+            self.state_at(neighbour_x, neighbour_z)
+        } else {
+            [0.0; 2]
+        };
+
+        let gradient = |level: usize| (neighbour_values[level] - values[level]) / self.dx;
+
+        gradient(0) * gradient(1)
+    }
+
+    /// Maps coordinates outside of the grid back into it according to the boundary condition of
+    /// the edge they lie beyond, leaving them out of bounds if the cell is held at zero.
+    fn resolve(&self, x: i32, z: i32) -> (i32, i32) {
+        let boundaries = &self.simulation.boundaries;
+
+        let resolve_axis = |position: i32, length: i32, min_edge: Edge, max_edge: Edge| {
+            if position < 0 {
+                resolve_ghost(position, length, boundaries.get(min_edge))
+            } else if position >= length {
+                resolve_ghost(position, length, boundaries.get(max_edge))
+            } else {
+                position
+            }
+        };
+
+        (
+            resolve_axis(x, self.width, Edge::XMin, Edge::XMax),
+            resolve_axis(z, self.depth, Edge::ZMin, Edge::ZMax),
+        )
+    }
+
+    /// Returns whether the given coordinates lie inside the grid.
+    fn in_grid(&self, x: i32, z: i32) -> bool {
+        (0..self.width).contains(&x) && (0..self.depth).contains(&z)
+    }
+
+    /// Returns u(x, t) and u(x, t - dt) of the given cell, which must lie inside the grid.
+    fn state_at(&self, x: i32, z: i32) -> [f32; 2] {
+        self.simulation.state[self.index(x, z)]
+    }
+
+    /// Returns the wave speed c(x, z) of the given cell.
+    fn speed_at(&self, x: i32, z: i32) -> f32 {
+        self.simulation.speed_map.speed_at(x as u32, z as u32)
+    }
+
+    /// Returns the obstacle covering the given cell, which must lie inside the grid.
+    fn obstacle_at(&self, x: i32, z: i32) -> Option<Wall> {
+        self.simulation.obstacles.wall_at(x as u32, z as u32)
+    }
+
+    /// Returns the world space position (x, z) of the given cell.
+    fn position(&self, x: i32, z: i32) -> (f32, f32) {
+        (x as f32 * self.dx, z as f32 * self.dx)
+    }
+
+    /// Returns the index of the given cell in the row by row storage of the grid.
+    fn index(&self, x: i32, z: i32) -> usize {
+        (z * self.width + x) as usize
+    }
+}
+
+/// Maps a single coordinate lying beyond an edge of a `length` long axis back into the grid, or
+/// leaves it out of bounds if the cell is held at zero.
+fn resolve_ghost(position: i32, length: i32, boundary: Boundary) -> i32 {
+    match boundary {
+        Boundary::Periodic => (position + length) % length,
+        // absorbing edges only sample ghost cells at corners, where mirroring is a good enough
+        // approximation
+        Boundary::Free | Boundary::Absorbing => position.clamp(0, length - 1),
+        // the outermost cells of a perfectly matched layer are held fixed
+        Boundary::Fixed | Boundary::Pml => position,
+    }
+}
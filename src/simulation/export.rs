@@ -0,0 +1,381 @@
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use crate::simulation::{config::SimulationConfig, speed_map::SpeedMap};
+
+/// A file format the wave field can be exported to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportFormat {
+    /// NumPy arrays, with one `.npy` file of shape (depth, width) per field.
+    #[default]
+    Npy,
+    /// A VTK XML image data (`.vti`) file holding every field, for ParaView.
+    Vti,
+}
+
+/// The fields exported alongside the displacement u.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExportFields {
+    /// Whether to export the velocity (u(t) - u(t - dt)) / dt.
+    pub velocity: bool,
+    /// Whether to export the energy density ½·v² + ½·c²·|∇u|².
+    pub energy_density: bool,
+}
+
+/// The fields of the wave at a single point in time, ready to be written to disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldExport {
+    /// The grid the fields are sampled on.
+    pub config: SimulationConfig,
+    /// The simulation time the fields were sampled at.
+    pub time: f32,
+    /// The name and value of every cell of each field, stored row by row along the X axis.
+    pub fields: Vec<(&'static str, Vec<f32>)>,
+}
+
+/// Exports the wave field every `interval` ticks as a numbered series of files, alongside an
+/// index of the simulation time of every frame.
+///
+/// Frames are named `<stem>_00000`, `<stem>_00001` and so on. Series of `.vti` files are indexed
+/// by a ParaView collection (`<stem>.pvd`), and series of `.npy` files by a CSV file
+/// (`<stem>_times.csv`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportSeries {
+    /// The path every frame's file name is derived from.
+    pub stem: PathBuf,
+    /// The format every frame is written in.
+    pub format: ExportFormat,
+    /// The fields exported alongside the displacement u.
+    pub fields: ExportFields,
+    /// The number of ticks between two frames.
+    pub interval: u32,
+
+    /// The number of ticks run since the last frame was written.
+    ticks_since_frame: u32,
+    /// The simulation time of every frame written so far.
+    times: Vec<f32>,
+}
+
+impl ExportFormat {
+    /// All formats, in the order they are presented to the user.
+    pub const ALL: [Self; 2] = [Self::Npy, Self::Vti];
+
+    /// Returns a human readable name of the format.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Npy => "NumPy (.npy)",
+            Self::Vti => "VTK image data (.vti)",
+        }
+    }
+}
+
+impl FieldExport {
+    /// Derives the requested fields from u(t) and u(t - dt) of every cell, stored row by row
+    /// along the X axis, where the two time levels are `dt` apart.
+    ///
+    /// The energy density uses central differences for the gradient of u(t) inside the grid and
+    /// one sided differences along its edges.
+    pub fn from_state(
+        config: &SimulationConfig,
+        state: &[[f32; 2]],
+        speed_map: &SpeedMap,
+        dt: f32,
+        time: f32,
+        fields: ExportFields,
+    ) -> Self {
+        let (width, depth) = config.grid_size();
+        let (width, depth) = (width as usize, depth as usize);
+        let dx = config.grid_spacing();
+
+        let u = state.iter().map(|[u, _]| *u).collect::<Vec<_>>();
+        let velocity = state
+            .iter()
+            .map(|[u, previous]| (u - previous) / dt)
+            .collect::<Vec<_>>();
+
+        // the difference across the neighbours of a cell along one axis, clamped to the grid
+        let derivative = |index: usize, position: usize, length: usize, stride: usize| {
+            let before = position.saturating_sub(1);
+            let after = (position + 1).min(length - 1);
+
+            let difference =
+                u[index + (after - position) * stride] - u[index - (position - before) * stride];
+
+            difference / ((after - before) as f32 * dx)
+        };
+
+        let energy_density = fields.energy_density.then(|| {
+            (0..depth)
+                .flat_map(|z| (0..width).map(move |x| (x, z)))
+                .map(|(x, z)| {
+                    let index = z * width + x;
+                    let speed = speed_map.speed_at(x as u32, z as u32);
+
+                    let du_dx = derivative(index, x, width, 1);
+                    let du_dz = derivative(index, z, depth, width);
+
+                    0.5 * velocity[index] * velocity[index]
+                        + 0.5 * speed * speed * (du_dx * du_dx + du_dz * du_dz)
+                })
+                .collect()
+        });
+
+        let mut exported = vec![("u", u)];
+
+        if fields.velocity {
+            exported.push(("velocity", velocity));
+        }
+
+        if let Some(energy_density) = energy_density {
+            exported.push(("energy_density", energy_density));
+        }
+
+        Self {
+            config: *config,
+            time,
+            fields: exported,
+        }
+    }
+
+    /// Writes the fields in the given format, deriving the file names from `stem`, and returns the
+    /// paths of all files written.
+    ///
+    /// NumPy arrays are written to `<stem>_<field>.npy`, and VTK image data to `<stem>.vti`.
+    pub fn write(&self, format: ExportFormat, stem: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let paths = match format {
+            ExportFormat::Npy => self
+                .fields
+                .iter()
+                .map(|(name, values)| {
+                    let path = with_suffix(stem, &format!("_{name}.npy"));
+                    std::fs::write(&path, self.to_npy(values))?;
+
+                    Ok(path)
+                })
+                .collect::<std::io::Result<_>>(),
+            ExportFormat::Vti => {
+                let path = with_suffix(stem, ".vti");
+                std::fs::write(&path, self.to_vti()).map(|_| vec![path])
+            }
+        };
+
+        paths.with_context(|| format!("failed to export the field to {}", stem.display()))
+    }
+
+    /// Encodes a single field as a version 1.0 NumPy array of little endian 32 bit floats, of
+    /// shape (depth, width).
+    pub fn to_npy(&self, values: &[f32]) -> Vec<u8> {
+        let (width, depth) = self.config.grid_size();
+
+        let mut header =
+            format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({depth}, {width}), }}");
+
+        // the magic string, version and header length take up 10 bytes, and the whole header
+        // must be padded with spaces to a multiple of 64 bytes, ending in a newline
+        let length = (10 + header.len() + 1).next_multiple_of(64) - 10;
+        header.extend(std::iter::repeat_n(' ', length - header.len() - 1));
+        header.push('\n');
+
+        let mut bytes = Vec::with_capacity(10 + length + 4 * values.len());
+
+        bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+        bytes.extend_from_slice(&(length as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+
+        bytes
+    }
+
+    /// Encodes every field as point data of a VTK XML image data file.
+    ///
+    /// The grid lies in the XZ plane of world space like the simulated domain, with the spacing
+    /// of its cells in world units, and the simulation time is stored as the `TIME` field data.
+    pub fn to_vti(&self) -> String {
+        let (width, depth) = self.config.grid_size();
+        let dx = self.config.grid_spacing();
+
+        let extent = format!("0 {} 0 0 0 {}", width - 1, depth - 1);
+
+        let mut vti = String::new();
+
+        writeln!(vti, r#"<?xml version="1.0"?>"#).unwrap();
+        writeln!(
+            vti,
+            r#"<VTKFile type="ImageData" version="1.0" byte_order="LittleEndian">"#
+        )
+        .unwrap();
+        writeln!(
+            vti,
+            r#"  <ImageData WholeExtent="{extent}" Origin="0 0 0" Spacing="{dx} {dx} {dx}">"#
+        )
+        .unwrap();
+
+        writeln!(vti, "    <FieldData>").unwrap();
+        writeln!(
+            vti,
+            r#"      <DataArray type="Float32" Name="TIME" NumberOfTuples="1" format="ascii">{}</DataArray>"#,
+            self.time
+        )
+        .unwrap();
+        writeln!(vti, "    </FieldData>").unwrap();
+
+        writeln!(vti, r#"    <Piece Extent="{extent}">"#).unwrap();
+        writeln!(vti, r#"      <PointData Scalars="u">"#).unwrap();
+
+        for (name, values) in &self.fields {
+            writeln!(
+                vti,
+                r#"        <DataArray type="Float32" Name="{name}" format="ascii">"#
+            )
+            .unwrap();
+
+            for row in values.chunks(width as usize) {
+                vti.push_str("          ");
+
+                for value in row {
+                    write!(vti, "{value} ").unwrap();
+                }
+
+                vti.push('\n');
+            }
+
+            writeln!(vti, "        </DataArray>").unwrap();
+        }
+
+        writeln!(vti, "      </PointData>").unwrap();
+        writeln!(vti, "    </Piece>").unwrap();
+        writeln!(vti, "  </ImageData>").unwrap();
+        writeln!(vti, "</VTKFile>").unwrap();
+
+        vti
+    }
+}
+
+impl ExportSeries {
+    /// Creates a series writing its first frame as soon as possible.
+    pub fn new(stem: PathBuf, format: ExportFormat, fields: ExportFields, interval: u32) -> Self {
+        let interval = interval.max(1);
+
+        Self {
+            stem,
+            format,
+            fields,
+            interval,
+            ticks_since_frame: interval,
+            times: Vec::new(),
+        }
+    }
+
+    /// Returns the number of ticks that may run before the next frame is due.
+    pub fn ticks_until_next(&self) -> u32 {
+        self.interval.saturating_sub(self.ticks_since_frame)
+    }
+
+    /// Accounts for `ticks` more ticks having run.
+    pub fn advance(&mut self, ticks: u32) {
+        self.ticks_since_frame = self.ticks_since_frame.saturating_add(ticks);
+    }
+
+    /// Returns whether enough ticks have run for the next frame to be written.
+    pub fn is_due(&self) -> bool {
+        self.ticks_since_frame >= self.interval
+    }
+
+    /// Returns the number of frames written so far.
+    pub fn frame_count(&self) -> usize {
+        self.times.len()
+    }
+
+    /// Writes `export` as the next frame of the series and updates the index of all frames.
+    pub fn write_frame(&mut self, export: &FieldExport) -> anyhow::Result<()> {
+        let index = self.times.len();
+        let frame = with_suffix(&self.stem, &format!("_{index:05}"));
+
+        export.write(self.format, &frame)?;
+
+        self.times.push(export.time);
+        self.ticks_since_frame = 0;
+
+        let (path, contents) = match self.format {
+            ExportFormat::Npy => (with_suffix(&self.stem, "_times.csv"), self.times_csv()),
+            ExportFormat::Vti => (with_suffix(&self.stem, ".pvd"), self.collection()),
+        };
+
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write the series index {}", path.display()))
+    }
+
+    /// Formats the index of a `.npy` series as CSV, with a row of (frame, time) per frame.
+    fn times_csv(&self) -> String {
+        let mut csv = String::from("frame,time\n");
+
+        for (index, time) in self.times.iter().enumerate() {
+            writeln!(csv, "{index},{time}").unwrap();
+        }
+
+        csv
+    }
+
+    /// Formats the index of a `.vti` series as a ParaView collection, referring to every frame
+    /// relative to the collection itself.
+    fn collection(&self) -> String {
+        let name = self
+            .stem
+            .file_name()
+            .map(|name| escape_attribute(&name.to_string_lossy()))
+            .unwrap_or_default();
+
+        let mut pvd = String::new();
+
+        writeln!(pvd, r#"<?xml version="1.0"?>"#).unwrap();
+        writeln!(
+            pvd,
+            r#"<VTKFile type="Collection" version="1.0" byte_order="LittleEndian">"#
+        )
+        .unwrap();
+        writeln!(pvd, "  <Collection>").unwrap();
+
+        for (index, time) in self.times.iter().enumerate() {
+            writeln!(
+                pvd,
+                r#"    <DataSet timestep="{time}" part="0" file="{name}_{index:05}.vti"/>"#
+            )
+            .unwrap();
+        }
+
+        writeln!(pvd, "  </Collection>").unwrap();
+        writeln!(pvd, "</VTKFile>").unwrap();
+
+        pvd
+    }
+}
+
+/// Escapes the characters of `value` that cannot appear as is inside a quoted XML attribute.
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Appends `suffix` to the file name of `stem`, which may itself already contain dots.
+fn with_suffix(stem: &Path, suffix: &str) -> PathBuf {
+    let mut path = stem.as_os_str().to_owned();
+    path.push(suffix);
+
+    path.into()
+}
@@ -0,0 +1,1039 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use anyhow::{bail, ensure};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A variable an [`Expression`] can refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Variable {
+    /// The world space X coordinate.
+    X,
+    /// The world space Z coordinate.
+    Z,
+    /// The time, whose exact meaning depends on where the expression is used.
+    T,
+}
+
+/// A formula of the world space position (x, z) and the time t typed in by the user, such as
+/// `exp(-((x - 2.5)^2 + (z - 2.5)^2) / 0.05) * sin(10 * t)`.
+///
+/// Expressions are parsed and type checked once, after which they can either be evaluated on the
+/// CPU or translated into WGSL. Besides the usual arithmetic, with `^` for powers, they support
+/// the constants `pi` and `e`, comparisons and the boolean operators `&&`, `||` and `!`, whose
+/// results can only be consumed by `if(condition, then, else)`. See [`Function`] for the
+/// functions available.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    /// The text the expression was parsed from.
+    text: String,
+    /// The root of the syntax tree, which always evaluates to a number.
+    root: Node,
+}
+
+/// The built-in functions an [`Expression`] can call, all of which behave like their WGSL
+/// counterparts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    /// `atan2(y, x)`, the angle of the vector (x, y).
+    Atan2,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    /// The natural logarithm, also available as `ln`.
+    Log,
+    Sqrt,
+    Abs,
+    /// -1, 0 or 1 depending on the sign of the argument.
+    Sign,
+    Floor,
+    Ceil,
+    /// `x - floor(x)`.
+    Fract,
+    Min,
+    Max,
+    Pow,
+    /// `clamp(x, low, high)`.
+    Clamp,
+    /// `step(edge, x)`, 1 once x reaches the edge and 0 before.
+    Step,
+    /// `smoothstep(low, high, x)`, a smooth transition from 0 to 1 between low and high.
+    Smoothstep,
+    /// `if(condition, then, else)`, the only function taking a boolean.
+    If,
+}
+
+/// A node of the syntax tree of an [`Expression`].
+#[derive(Debug, Clone)]
+enum Node {
+    Number(f32),
+    Variable(Variable),
+    Negate(Box<Node>),
+    Not(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+/// A binary operator of an [`Expression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
+}
+
+/// The type of a value inside an [`Expression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Number,
+    Boolean,
+}
+
+/// A single token of an [`Expression`], with the column it starts at.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Identifier(String),
+    Symbol(&'static str),
+    End,
+}
+
+/// A parsed and type checked subtree, with the column it starts at.
+struct Typed {
+    node: Node,
+    ty: Type,
+    column: usize,
+}
+
+/// Turns a list of tokens into a type checked syntax tree by recursive descent.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+/// The symbols of an [`Expression`], with the longer ones first so they are matched greedily.
+const SYMBOLS: [&str; 19] = [
+    "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "^", "(", ")", ",", "<", ">", "!",
+    "=",
+];
+
+impl Variable {
+    /// All variables, in the order they are listed to the user.
+    pub const ALL: [Self; 3] = [Self::X, Self::Z, Self::T];
+
+    /// Returns the name the variable is referred to by.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::X => "x",
+            Self::Z => "z",
+            Self::T => "t",
+        }
+    }
+}
+
+impl Expression {
+    /// Parses and type checks an expression, which may refer to any [`Variable`].
+    ///
+    /// Errors point at the column (counted in characters, starting at 1) they were found at.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+        };
+
+        let root = parser.expression()?;
+
+        match parser.peek() {
+            Token::End => {}
+            token => bail!(
+                "unexpected {} at column {}",
+                token.describe(),
+                parser.column()
+            ),
+        }
+
+        ensure!(
+            root.ty == Type::Number,
+            "the expression must evaluate to a number, not a boolean"
+        );
+
+        Ok(Self {
+            text: text.to_owned(),
+            root: root.node,
+        })
+    }
+
+    /// Returns the text the expression was parsed from.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns whether the expression refers to the given variable.
+    pub fn uses(&self, variable: Variable) -> bool {
+        self.root.uses(variable)
+    }
+
+    /// Fails if the expression refers to any variable but the `allowed` ones.
+    pub fn ensure_variables(&self, allowed: &[Variable]) -> anyhow::Result<()> {
+        if let Some(variable) = Variable::ALL
+            .into_iter()
+            .find(|variable| !allowed.contains(variable) && self.uses(*variable))
+        {
+            let allowed = allowed
+                .iter()
+                .map(|variable| variable.name())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            bail!("`{}` cannot be used here, only {allowed}", variable.name());
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates the expression at the world space position (x, z) and time t.
+    ///
+    /// Arithmetic is carried out in `f32`, matching the WGSL translation up to the accuracy of the
+    /// built-in functions of the GPU.
+    pub fn eval(&self, x: f32, z: f32, t: f32) -> f32 {
+        self.root.number(x, z, t)
+    }
+
+    /// Translates the expression into a WGSL expression of type `f32`, which refers to the
+    /// variables by their names, so `x`, `z` and `t` must be in scope wherever it is used.
+    ///
+    /// Fails if a part of the expression that doesn't depend on any variable, such as `1 / 0` or
+    /// `sqrt(-1)`, is infinite or NaN, as the shader compiler folds it into a constant and rejects
+    /// the whole shader.
+    pub fn to_wgsl(&self) -> anyhow::Result<String> {
+        self.root.ensure_finite_constants()?;
+
+        Ok(self.root.to_wgsl())
+    }
+}
+
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
+
+impl Eq for Expression {}
+
+impl Hash for Expression {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.text.hash(state);
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+
+        Self::parse(&text).map_err(|error| serde::de::Error::custom(format!("{error:#}")))
+    }
+}
+
+impl Function {
+    /// All functions, in the order they are listed to the user.
+    pub const ALL: [Self; 25] = [
+        Self::Sin,
+        Self::Cos,
+        Self::Tan,
+        Self::Asin,
+        Self::Acos,
+        Self::Atan,
+        Self::Atan2,
+        Self::Sinh,
+        Self::Cosh,
+        Self::Tanh,
+        Self::Exp,
+        Self::Log,
+        Self::Sqrt,
+        Self::Abs,
+        Self::Sign,
+        Self::Floor,
+        Self::Ceil,
+        Self::Fract,
+        Self::Min,
+        Self::Max,
+        Self::Pow,
+        Self::Clamp,
+        Self::Step,
+        Self::Smoothstep,
+        Self::If,
+    ];
+
+    /// Returns the name the function is called by, which is also the name of its WGSL
+    /// counterpart for every function but `if`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sin => "sin",
+            Self::Cos => "cos",
+            Self::Tan => "tan",
+            Self::Asin => "asin",
+            Self::Acos => "acos",
+            Self::Atan => "atan",
+            Self::Atan2 => "atan2",
+            Self::Sinh => "sinh",
+            Self::Cosh => "cosh",
+            Self::Tanh => "tanh",
+            Self::Exp => "exp",
+            Self::Log => "log",
+            Self::Sqrt => "sqrt",
+            Self::Abs => "abs",
+            Self::Sign => "sign",
+            Self::Floor => "floor",
+            Self::Ceil => "ceil",
+            Self::Fract => "fract",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Pow => "pow",
+            Self::Clamp => "clamp",
+            Self::Step => "step",
+            Self::Smoothstep => "smoothstep",
+            Self::If => "if",
+        }
+    }
+
+    /// Looks up a function by the name it is called by.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ln" => Some(Self::Log),
+            _ => Self::ALL
+                .into_iter()
+                .find(|function| function.name() == name),
+        }
+    }
+
+    /// Returns the types of the arguments the function takes.
+    fn parameters(self) -> &'static [Type] {
+        use Type::*;
+
+        match self {
+            Self::Atan2 | Self::Min | Self::Max | Self::Pow | Self::Step => &[Number, Number],
+            Self::Clamp | Self::Smoothstep => &[Number, Number, Number],
+            Self::If => &[Boolean, Number, Number],
+            _ => &[Number],
+        }
+    }
+
+    /// Applies the function to already evaluated numeric arguments.
+    fn apply(self, arguments: &[f32]) -> f32 {
+        let x = arguments[0];
+        let argument = |i: usize| arguments[i];
+
+        match self {
+            Self::Sin => x.sin(),
+            Self::Cos => x.cos(),
+            Self::Tan => x.tan(),
+            Self::Asin => x.asin(),
+            Self::Acos => x.acos(),
+            Self::Atan => x.atan(),
+            Self::Atan2 => x.atan2(argument(1)),
+            Self::Sinh => x.sinh(),
+            Self::Cosh => x.cosh(),
+            Self::Tanh => x.tanh(),
+            Self::Exp => x.exp(),
+            Self::Log => x.ln(),
+            Self::Sqrt => x.sqrt(),
+            Self::Abs => x.abs(),
+            // unlike `f32::signum`, WGSL maps zero to zero
+            Self::Sign => {
+                if x > 0.0 {
+                    1.0
+                } else if x < 0.0 {
+                    -1.0
+                } else {
+                    0.0
+                }
+            }
+            Self::Floor => x.floor(),
+            Self::Ceil => x.ceil(),
+            Self::Fract => x - x.floor(),
+            Self::Min => x.min(argument(1)),
+            Self::Max => x.max(argument(1)),
+            Self::Pow => x.powf(argument(1)),
+            // unlike `f32::clamp`, this doesn't panic if the bounds are swapped
+            Self::Clamp => x.max(argument(1)).min(argument(2)),
+            Self::Step => {
+                let (edge, x) = (x, argument(1));
+
+                if edge <= x { 1.0 } else { 0.0 }
+            }
+            Self::Smoothstep => {
+                let (low, high, x) = (x, argument(1), argument(2));
+                let t = ((x - low) / (high - low)).clamp(0.0, 1.0);
+
+                t * t * (3.0 - 2.0 * t)
+            }
+            Self::If => unreachable!("`if` is evaluated lazily"),
+        }
+    }
+}
+
+impl Node {
+    /// Returns whether the subtree refers to the given variable.
+    fn uses(&self, variable: Variable) -> bool {
+        match self {
+            Self::Number(_) => false,
+            Self::Variable(used) => *used == variable,
+            Self::Negate(operand) | Self::Not(operand) => operand.uses(variable),
+            Self::Binary(_, left, right) => left.uses(variable) || right.uses(variable),
+            Self::Call(_, arguments) => arguments.iter().any(|argument| argument.uses(variable)),
+        }
+    }
+
+    /// Returns the type the subtree evaluates to.
+    fn ty(&self) -> Type {
+        match self {
+            Self::Not(_) => Type::Boolean,
+            Self::Binary(operator, ..) => operator.signature().1,
+            _ => Type::Number,
+        }
+    }
+
+    /// Fails if a numeric subtree that doesn't depend on any variable evaluates to an infinite or
+    /// NaN value, which the shader compiler would fold into a constant.
+    fn ensure_finite_constants(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Number(_) | Self::Variable(_) => {}
+            Self::Negate(operand) | Self::Not(operand) => operand.ensure_finite_constants()?,
+            Self::Binary(_, left, right) => {
+                left.ensure_finite_constants()?;
+                right.ensure_finite_constants()?;
+            }
+            Self::Call(_, arguments) => {
+                for argument in arguments {
+                    argument.ensure_finite_constants()?;
+                }
+            }
+        }
+
+        let constant = !Variable::ALL
+            .into_iter()
+            .any(|variable| self.uses(variable));
+
+        if constant && self.ty() == Type::Number {
+            let value = self.number(0.0, 0.0, 0.0);
+
+            ensure!(
+                value.is_finite(),
+                "a part of the expression without any variable evaluates to {value}, which the \
+                 GPU cannot compile"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates a subtree of type [`Type::Number`].
+    fn number(&self, x: f32, z: f32, t: f32) -> f32 {
+        match self {
+            Self::Number(value) => *value,
+            Self::Variable(Variable::X) => x,
+            Self::Variable(Variable::Z) => z,
+            Self::Variable(Variable::T) => t,
+            Self::Negate(operand) => -operand.number(x, z, t),
+            Self::Binary(operator, left, right) => {
+                let (left, right) = (left.number(x, z, t), right.number(x, z, t));
+
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide => left / right,
+                    // both Rust and WGSL truncate the quotient
+                    Operator::Remainder => left % right,
+                    Operator::Power => left.powf(right),
+                    _ => unreachable!("{operator:?} does not produce a number"),
+                }
+            }
+            Self::Call(Function::If, arguments) => {
+                if arguments[0].boolean(x, z, t) {
+                    arguments[1].number(x, z, t)
+                } else {
+                    arguments[2].number(x, z, t)
+                }
+            }
+            Self::Call(function, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.number(x, z, t))
+                    .collect::<Vec<_>>();
+
+                function.apply(&arguments)
+            }
+            Self::Not(_) => unreachable!("`!` does not produce a number"),
+        }
+    }
+
+    /// Evaluates a subtree of type [`Type::Boolean`].
+    fn boolean(&self, x: f32, z: f32, t: f32) -> bool {
+        match self {
+            Self::Not(operand) => !operand.boolean(x, z, t),
+            Self::Binary(Operator::And, left, right) => {
+                left.boolean(x, z, t) && right.boolean(x, z, t)
+            }
+            Self::Binary(Operator::Or, left, right) => {
+                left.boolean(x, z, t) || right.boolean(x, z, t)
+            }
+            Self::Binary(operator, left, right) => {
+                let (left, right) = (left.number(x, z, t), right.number(x, z, t));
+
+                match operator {
+                    Operator::Less => left < right,
+                    Operator::LessEqual => left <= right,
+                    Operator::Greater => left > right,
+                    Operator::GreaterEqual => left >= right,
+                    Operator::Equal => left == right,
+                    Operator::NotEqual => left != right,
+                    _ => unreachable!("{operator:?} does not produce a boolean"),
+                }
+            }
+            _ => unreachable!("{self:?} does not produce a boolean"),
+        }
+    }
+
+    /// Translates the subtree into a fully parenthesized WGSL expression.
+    fn to_wgsl(&self) -> String {
+        match self {
+            // the suffix keeps constants in f32, as abstract floats would be folded at a higher
+            // precision than the CPU evaluates them at
+            Self::Number(value) => format!("{value:?}f"),
+            Self::Variable(variable) => variable.name().to_owned(),
+            Self::Negate(operand) => format!("(-{})", operand.to_wgsl()),
+            Self::Not(operand) => format!("(!{})", operand.to_wgsl()),
+            Self::Binary(Operator::Power, base, exponent) => match **exponent {
+                // `pow` is undefined for negative bases on the GPU, so small integer powers such
+                // as the common `(x - a)^2` are spelled out as products instead
+                Self::Number(n) if n.fract() == 0.0 && (1.0..=4.0).contains(&n) => {
+                    let base = base.to_wgsl();
+                    let factors = vec![base; n as usize];
+
+                    format!("({})", factors.join(" * "))
+                }
+                _ => format!("pow({}, {})", base.to_wgsl(), exponent.to_wgsl()),
+            },
+            Self::Binary(operator, left, right) => {
+                format!(
+                    "({} {} {})",
+                    left.to_wgsl(),
+                    operator.symbol(),
+                    right.to_wgsl()
+                )
+            }
+            Self::Call(Function::If, arguments) => format!(
+                "select({}, {}, {})",
+                arguments[2].to_wgsl(),
+                arguments[1].to_wgsl(),
+                arguments[0].to_wgsl()
+            ),
+            Self::Call(function, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(Node::to_wgsl)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("{}({arguments})", function.name())
+            }
+        }
+    }
+}
+
+impl Operator {
+    /// Looks up a binary operator by its symbol.
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        Some(match symbol {
+            "+" => Self::Add,
+            "-" => Self::Subtract,
+            "*" => Self::Multiply,
+            "/" => Self::Divide,
+            "%" => Self::Remainder,
+            "^" => Self::Power,
+            "<" => Self::Less,
+            "<=" => Self::LessEqual,
+            ">" => Self::Greater,
+            ">=" => Self::GreaterEqual,
+            "==" => Self::Equal,
+            "!=" => Self::NotEqual,
+            "&&" => Self::And,
+            "||" => Self::Or,
+            _ => return None,
+        })
+    }
+
+    /// Returns the symbol of the operator, which is the same in WGSL for every operator but `^`.
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Remainder => "%",
+            Self::Power => "^",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::And => "&&",
+            Self::Or => "||",
+        }
+    }
+
+    /// Returns the types of both operands and of the result.
+    fn signature(self) -> (Type, Type) {
+        match self {
+            Self::Add
+            | Self::Subtract
+            | Self::Multiply
+            | Self::Divide
+            | Self::Remainder
+            | Self::Power => (Type::Number, Type::Number),
+            Self::Less
+            | Self::LessEqual
+            | Self::Greater
+            | Self::GreaterEqual
+            | Self::Equal
+            | Self::NotEqual => (Type::Number, Type::Boolean),
+            Self::And | Self::Or => (Type::Boolean, Type::Boolean),
+        }
+    }
+}
+
+impl Type {
+    /// Returns a human readable name of the type.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Number => "a number",
+            Self::Boolean => "a boolean",
+        }
+    }
+}
+
+impl Token {
+    /// Returns a human readable description of the token for error messages.
+    fn describe(&self) -> String {
+        match self {
+            Self::Number(value) => format!("number {value}"),
+            Self::Identifier(name) => format!("`{name}`"),
+            Self::Symbol(symbol) => format!("`{symbol}`"),
+            Self::End => "end of expression".to_owned(),
+        }
+    }
+}
+
+impl Parser {
+    /// Returns the next token without consuming it.
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].0
+    }
+
+    /// Returns the column the next token starts at.
+    fn column(&self) -> usize {
+        self.tokens[self.position].1
+    }
+
+    /// Consumes the next token.
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].0.clone();
+
+        if token != Token::End {
+            self.position += 1;
+        }
+
+        token
+    }
+
+    /// Consumes the next token if it is the given symbol.
+    fn eat(&mut self, symbol: &str) -> bool {
+        let matches = matches!(self.peek(), Token::Symbol(next) if *next == symbol);
+
+        if matches {
+            self.position += 1;
+        }
+
+        matches
+    }
+
+    /// Consumes the given symbol, failing if the next token is anything else.
+    fn expect(&mut self, symbol: &str) -> anyhow::Result<()> {
+        ensure!(
+            self.eat(symbol),
+            "expected `{symbol}` at column {}, found {}",
+            self.column(),
+            self.peek().describe()
+        );
+
+        Ok(())
+    }
+
+    /// Fails unless the subtree has the expected type.
+    fn check(typed: &Typed, expected: Type, context: &str) -> anyhow::Result<()> {
+        ensure!(
+            typed.ty == expected,
+            "{context} expects {}, but the operand at column {} is {}",
+            expected.name(),
+            typed.column,
+            typed.ty.name()
+        );
+
+        Ok(())
+    }
+
+    /// Parses a chain of left associative binary operators, each operand parsed by `operand`.
+    fn binary_chain(
+        &mut self,
+        symbols: &[&str],
+        operand: fn(&mut Self) -> anyhow::Result<Typed>,
+    ) -> anyhow::Result<Typed> {
+        let mut left = operand(self)?;
+
+        while let Token::Symbol(symbol) = *self.peek() {
+            if !symbols.contains(&symbol) {
+                break;
+            }
+
+            self.position += 1;
+
+            let right = operand(self)?;
+            left = Self::combine(symbol, left, right)?;
+        }
+
+        Ok(left)
+    }
+
+    /// Type checks the operands of a binary operator and joins them into a single subtree.
+    fn combine(symbol: &str, left: Typed, right: Typed) -> anyhow::Result<Typed> {
+        let operator = Operator::from_symbol(symbol).expect("only operators are combined");
+        let (operands, result) = operator.signature();
+        let context = format!("`{symbol}`");
+
+        Self::check(&left, operands, &context)?;
+        Self::check(&right, operands, &context)?;
+
+        Ok(Typed {
+            node: Node::Binary(operator, Box::new(left.node), Box::new(right.node)),
+            ty: result,
+            column: left.column,
+        })
+    }
+
+    /// expression := and ("||" and)*
+    fn expression(&mut self) -> anyhow::Result<Typed> {
+        self.binary_chain(&["||"], Self::and)
+    }
+
+    /// and := comparison ("&&" comparison)*
+    fn and(&mut self) -> anyhow::Result<Typed> {
+        self.binary_chain(&["&&"], Self::comparison)
+    }
+
+    /// comparison := sum (("<" | "<=" | ">" | ">=" | "==" | "!=") sum)?
+    fn comparison(&mut self) -> anyhow::Result<Typed> {
+        let left = self.sum()?;
+
+        if let Token::Symbol(symbol) = *self.peek()
+            && ["<", "<=", ">", ">=", "==", "!="].contains(&symbol)
+        {
+            self.position += 1;
+
+            let right = self.sum()?;
+
+            if let Token::Symbol(next) = *self.peek()
+                && ["<", "<=", ">", ">=", "==", "!="].contains(&next)
+            {
+                bail!(
+                    "comparisons cannot be chained, found `{next}` at column {}",
+                    self.column()
+                );
+            }
+
+            return Self::combine(symbol, left, right);
+        }
+
+        Ok(left)
+    }
+
+    /// sum := product (("+" | "-") product)*
+    fn sum(&mut self) -> anyhow::Result<Typed> {
+        self.binary_chain(&["+", "-"], Self::product)
+    }
+
+    /// product := unary (("*" | "/" | "%") unary)*
+    fn product(&mut self) -> anyhow::Result<Typed> {
+        self.binary_chain(&["*", "/", "%"], Self::unary)
+    }
+
+    /// unary := ("-" | "!") unary | power
+    fn unary(&mut self) -> anyhow::Result<Typed> {
+        let column = self.column();
+
+        if self.eat("-") {
+            let operand = self.unary()?;
+            Self::check(&operand, Type::Number, "`-`")?;
+
+            return Ok(Typed {
+                node: Node::Negate(Box::new(operand.node)),
+                ty: Type::Number,
+                column,
+            });
+        }
+
+        if self.eat("!") {
+            let operand = self.unary()?;
+            Self::check(&operand, Type::Boolean, "`!`")?;
+
+            return Ok(Typed {
+                node: Node::Not(Box::new(operand.node)),
+                ty: Type::Boolean,
+                column,
+            });
+        }
+
+        self.power()
+    }
+
+    /// power := atom ("^" unary)?
+    ///
+    /// Powers bind tighter than a leading minus and are right associative, so `-x^2` is `-(x^2)`
+    /// and `2^3^2` is `2^(3^2)`.
+    fn power(&mut self) -> anyhow::Result<Typed> {
+        let base = self.atom()?;
+
+        if self.eat("^") {
+            let exponent = self.unary()?;
+            return Self::combine("^", base, exponent);
+        }
+
+        Ok(base)
+    }
+
+    /// atom := number | variable | constant | function "(" arguments ")" | "(" expression ")"
+    fn atom(&mut self) -> anyhow::Result<Typed> {
+        let column = self.column();
+
+        let number = |value: f32| Typed {
+            node: Node::Number(value),
+            ty: Type::Number,
+            column,
+        };
+
+        match self.next() {
+            Token::Number(value) => Ok(number(value)),
+            Token::Identifier(name) => {
+                if let Some(function) = Function::from_name(&name) {
+                    return self.call(function, column);
+                }
+
+                match name.as_str() {
+                    "pi" => Ok(number(std::f32::consts::PI)),
+                    "e" => Ok(number(std::f32::consts::E)),
+                    _ if matches!(self.peek(), Token::Symbol("(")) => {
+                        bail!("unknown function `{name}` at column {column}")
+                    }
+                    _ => {
+                        let variable = Variable::ALL
+                            .into_iter()
+                            .find(|variable| variable.name() == name);
+
+                        match variable {
+                            Some(variable) => Ok(Typed {
+                                node: Node::Variable(variable),
+                                ty: Type::Number,
+                                column,
+                            }),
+                            None => bail!("unknown variable `{name}` at column {column}"),
+                        }
+                    }
+                }
+            }
+            Token::Symbol("(") => {
+                let inner = self.expression()?;
+                self.expect(")")?;
+
+                Ok(Typed { column, ..inner })
+            }
+            token => bail!("unexpected {} at column {column}", token.describe()),
+        }
+    }
+
+    /// Parses the parenthesized arguments of a call to `function`, whose name started at
+    /// `column`.
+    fn call(&mut self, function: Function, column: usize) -> anyhow::Result<Typed> {
+        ensure!(
+            self.eat("("),
+            "expected `(` after `{}` at column {}",
+            function.name(),
+            self.column()
+        );
+
+        let mut arguments = Vec::new();
+
+        if !self.eat(")") {
+            loop {
+                arguments.push(self.expression()?);
+
+                if self.eat(")") {
+                    break;
+                }
+
+                ensure!(
+                    self.eat(","),
+                    "expected `,` or `)` at column {}, found {}",
+                    self.column(),
+                    self.peek().describe()
+                );
+            }
+        }
+
+        let parameters = function.parameters();
+
+        ensure!(
+            arguments.len() == parameters.len(),
+            "`{}` at column {column} takes {} argument{}, got {}",
+            function.name(),
+            parameters.len(),
+            if parameters.len() == 1 { "" } else { "s" },
+            arguments.len()
+        );
+
+        let context = format!("`{}`", function.name());
+
+        for (argument, parameter) in arguments.iter().zip(parameters) {
+            Self::check(argument, *parameter, &context)?;
+        }
+
+        Ok(Typed {
+            node: Node::Call(
+                function,
+                arguments
+                    .into_iter()
+                    .map(|argument| argument.node)
+                    .collect(),
+            ),
+            ty: Type::Number,
+            column,
+        })
+    }
+}
+
+/// Splits the text of an expression into tokens, each paired with the column it starts at.
+fn tokenize(text: &str) -> anyhow::Result<Vec<(Token, usize)>> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let column = i + 1;
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let start = i;
+
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+
+            // an exponent is only taken if digits follow, so `2e` is left as `2` followed by `e`
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                let digits = match chars.get(i + 1) {
+                    Some('+' | '-') => i + 2,
+                    _ => i + 1,
+                };
+
+                if chars.get(digits).is_some_and(char::is_ascii_digit) {
+                    i = digits;
+
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+
+            let literal = chars[start..i].iter().collect::<String>();
+            let value = literal
+                .parse::<f32>()
+                .map_err(|_| anyhow::anyhow!("invalid number `{literal}` at column {column}"))?;
+
+            ensure!(
+                value.is_finite(),
+                "number `{literal}` at column {column} is too large"
+            );
+
+            tokens.push((Token::Number(value), column));
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+
+            tokens.push((Token::Identifier(chars[start..i].iter().collect()), column));
+            continue;
+        }
+
+        let symbol = SYMBOLS.into_iter().find(|symbol| {
+            symbol
+                .chars()
+                .enumerate()
+                .all(|(offset, c)| chars.get(i + offset) == Some(&c))
+        });
+
+        match symbol {
+            Some("=") => bail!("unexpected `=` at column {column}, did you mean `==`?"),
+            Some(symbol) => {
+                tokens.push((Token::Symbol(symbol), column));
+                i += symbol.len();
+            }
+            None => bail!("unexpected character `{c}` at column {column}"),
+        }
+    }
+
+    tokens.push((Token::End, chars.len() + 1));
+
+    Ok(tokens)
+}
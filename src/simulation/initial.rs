@@ -0,0 +1,353 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::{config::SimulationConfig, expression::Expression, speed_map::SpeedMap};
+
+/// A generator for the state of the wave the simulation starts out from (or is reset to).
+///
+/// Positions and extents are in world space (x, z) coordinates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum InitialCondition {
+    /// A gaussian bump at rest, splitting into an outgoing ring.
+    GaussianBump {
+        /// The center of the bump.
+        center: [f32; 2],
+        /// The standard deviation of the bump.
+        radius: f32,
+        /// The height of the bump at its center.
+        amplitude: f32,
+    },
+    /// A thin circular crest already travelling outwards.
+    Ring {
+        /// The center of the ring.
+        center: [f32; 2],
+        /// The radius of the crest.
+        radius: f32,
+        /// The standard deviation of the crest across the ring.
+        width: f32,
+        /// The height of the crest.
+        amplitude: f32,
+    },
+    /// A wave packet with straight crests, travelling along `direction`.
+    PlaneWave {
+        /// The center of the packet.
+        center: [f32; 2],
+        /// The direction of travel, as an angle from the X axis (in radians).
+        direction: f32,
+        /// The distance between two crests.
+        wavelength: f32,
+        /// The standard deviation of the gaussian envelope along the direction of travel.
+        length: f32,
+        /// The height of the central crest.
+        amplitude: f32,
+    },
+    /// The standing mode (m, n) of the rectangular domain with fixed edges, at the peak of its
+    /// oscillation.
+    Eigenmode {
+        /// The number of half wavelengths across the X axis.
+        m: u32,
+        /// The number of half wavelengths across the Z axis.
+        n: u32,
+        /// The height of the antinodes.
+        amplitude: f32,
+    },
+    /// Smooth random bumps at rest, reproducible from their seed.
+    Noise {
+        /// The seed of the random number generator.
+        seed: u32,
+        /// The distance between two independent random values.
+        scale: f32,
+        /// The largest height of the noise.
+        amplitude: f32,
+    },
+    /// A displacement u(x, z, t) typed in by the user.
+    ///
+    /// It is sampled at t = 0 and t = -dt, so any dependence on t gives the wave an initial
+    /// velocity.
+    Expression {
+        /// The displacement at the world space position (x, z) and time t.
+        displacement: Expression,
+    },
+}
+
+/// The kinds of [`InitialCondition`]s, without their parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InitialConditionKind {
+    /// See [`InitialCondition::GaussianBump`].
+    GaussianBump,
+    /// See [`InitialCondition::Ring`].
+    Ring,
+    /// See [`InitialCondition::PlaneWave`].
+    PlaneWave,
+    /// See [`InitialCondition::Eigenmode`].
+    Eigenmode,
+    /// See [`InitialCondition::Noise`].
+    Noise,
+    /// See [`InitialCondition::Expression`].
+    Expression,
+}
+
+impl InitialCondition {
+    /// Creates an [`InitialCondition`] of the given kind with parameters suiting the domain.
+    pub fn preset(kind: InitialConditionKind, config: &SimulationConfig) -> Self {
+        let center = [config.width / 2.0, config.depth / 2.0];
+        let size = config.width.min(config.depth);
+
+        match kind {
+            InitialConditionKind::GaussianBump => Self::GaussianBump {
+                center,
+                radius: 0.15,
+                amplitude: 0.5,
+            },
+            InitialConditionKind::Ring => Self::Ring {
+                center,
+                radius: 0.2 * size,
+                width: 0.05,
+                amplitude: 0.3,
+            },
+            InitialConditionKind::PlaneWave => Self::PlaneWave {
+                center: [0.25 * config.width, center[1]],
+                direction: 0.0,
+                wavelength: 0.1 * size,
+                length: 0.1 * size,
+                amplitude: 0.3,
+            },
+            InitialConditionKind::Eigenmode => Self::Eigenmode {
+                m: 2,
+                n: 3,
+                amplitude: 0.3,
+            },
+            InitialConditionKind::Noise => Self::Noise {
+                seed: 1,
+                scale: 0.05 * size,
+                amplitude: 0.1,
+            },
+            InitialConditionKind::Expression => {
+                let [x, z] = center;
+                let text = format!("0.5 * exp(-((x - {x})^2 + (z - {z})^2) / 0.05)");
+
+                Self::Expression {
+                    displacement: Expression::parse(&text)
+                        .expect("the preset is a valid expression"),
+                }
+            }
+        }
+    }
+
+    /// Returns the kind of the initial condition.
+    pub fn kind(&self) -> InitialConditionKind {
+        match self {
+            Self::GaussianBump { .. } => InitialConditionKind::GaussianBump,
+            Self::Ring { .. } => InitialConditionKind::Ring,
+            Self::PlaneWave { .. } => InitialConditionKind::PlaneWave,
+            Self::Eigenmode { .. } => InitialConditionKind::Eigenmode,
+            Self::Noise { .. } => InitialConditionKind::Noise,
+            Self::Expression { .. } => InitialConditionKind::Expression,
+        }
+    }
+
+    /// Generates u(t) and u(t - dt) for every cell, stored row by row along the X axis.
+    ///
+    /// Travelling waves use the local wave speed of the `speed_map`, which must match the grid,
+    /// to place u(t - dt) one tick behind u(t) so they actually move in the intended direction.
+    pub fn generate(
+        &self,
+        config: &SimulationConfig,
+        speed_map: &SpeedMap,
+        dt: f32,
+    ) -> Vec<[f32; 2]> {
+        let (width, depth) = config.grid_size();
+        let noise = self.noise_lattice(config);
+
+        (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let (position_x, position_z) = config.cell_position(x, z);
+                let c = speed_map.speed_at(x, z);
+
+                // the distance travelled during a single tick
+                let shift = c * dt;
+
+                match *self {
+                    Self::GaussianBump {
+                        center,
+                        radius,
+                        amplitude,
+                    } => {
+                        let offset_x = (position_x - center[0]) / radius;
+                        let offset_z = (position_z - center[1]) / radius;
+
+                        let u =
+                            amplitude * (-0.5 * (offset_x * offset_x + offset_z * offset_z)).exp();
+
+                        // u(t - dt) = u(t) so the bump starts at rest
+                        [u, u]
+                    }
+
+                    Self::Ring {
+                        center,
+                        radius,
+                        width,
+                        amplitude,
+                    } => {
+                        let distance = (position_x - center[0]).hypot(position_z - center[1]);
+                        let profile = |r: f32| {
+                            let offset = (r - radius) / width;
+                            amplitude * (-0.5 * offset * offset).exp()
+                        };
+
+                        // an outgoing crest was slightly closer to the center a tick ago
+                        [profile(distance), profile(distance + shift)]
+                    }
+
+                    Self::PlaneWave {
+                        center,
+                        direction,
+                        wavelength,
+                        length,
+                        amplitude,
+                    } => {
+                        let along = (position_x - center[0]) * direction.cos()
+                            + (position_z - center[1]) * direction.sin();
+
+                        let profile = |s: f32| {
+                            let envelope = (-0.5 * (s / length) * (s / length)).exp();
+                            amplitude * envelope * (2.0 * PI * s / wavelength).cos()
+                        };
+
+                        // a tick ago, the packet was one tick's travel further back
+                        [profile(along), profile(along + shift)]
+                    }
+
+                    Self::Eigenmode { m, n, amplitude } => {
+                        // fixed edges hold the ghost cells just outside of the grid at zero
+                        let dx = config.grid_spacing();
+                        let length_x = (width + 1) as f32 * dx;
+                        let length_z = (depth + 1) as f32 * dx;
+
+                        let k_x = m as f32 * PI / length_x;
+                        let k_z = n as f32 * PI / length_z;
+
+                        let u = amplitude
+                            * (k_x * (position_x + dx)).sin()
+                            * (k_z * (position_z + dx)).sin();
+
+                        // the mode is at the peak of its oscillation, so it is symmetric in time
+                        let omega = c * k_x.hypot(k_z);
+
+                        [u, u * (omega * dt).cos()]
+                    }
+
+                    Self::Noise { amplitude, .. } => {
+                        let u = amplitude * noise.sample(position_x, position_z);
+
+                        [u, u]
+                    }
+
+                    Self::Expression { ref displacement } => [
+                        displacement.eval(position_x, position_z, 0.0),
+                        displacement.eval(position_x, position_z, -dt),
+                    ],
+                }
+            })
+            .collect()
+    }
+
+    /// Creates the lattice of random values [`InitialCondition::Noise`] interpolates between,
+    /// which is empty for any other initial condition.
+    fn noise_lattice(&self, config: &SimulationConfig) -> NoiseLattice {
+        match *self {
+            Self::Noise { seed, scale, .. } => NoiseLattice::new(config, seed, scale),
+            _ => NoiseLattice::default(),
+        }
+    }
+}
+
+impl InitialConditionKind {
+    /// All kinds, in the order they are presented to the user.
+    pub const ALL: [Self; 6] = [
+        Self::GaussianBump,
+        Self::Ring,
+        Self::PlaneWave,
+        Self::Eigenmode,
+        Self::Noise,
+        Self::Expression,
+    ];
+
+    /// Returns a human readable name of the kind.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GaussianBump => "Gaussian bump",
+            Self::Ring => "Expanding ring",
+            Self::PlaneWave => "Plane wave packet",
+            Self::Eigenmode => "Eigenmode",
+            Self::Noise => "Random noise",
+            Self::Expression => "Expression",
+        }
+    }
+}
+
+/// Uniformly random values in [-1, 1] on a coarse lattice, smoothly interpolated in between.
+#[derive(Debug, Clone, Default)]
+struct NoiseLattice {
+    /// The number of lattice points across the X axis.
+    width: usize,
+    /// The distance between two adjacent lattice points.
+    scale: f32,
+    /// The random value of each lattice point, stored row by row along the X axis.
+    values: Vec<f32>,
+}
+
+impl NoiseLattice {
+    /// Creates a lattice covering the whole domain.
+    fn new(config: &SimulationConfig, seed: u32, scale: f32) -> Self {
+        let scale = scale.max(config.grid_spacing());
+
+        let width = (config.width / scale).ceil() as usize + 2;
+        let depth = (config.depth / scale).ceil() as usize + 2;
+
+        // xorshift, with the seed mixed so nearby seeds give unrelated noise
+        let mut state = seed.wrapping_mul(0x9e37_79b9) | 1;
+
+        let values = (0..width * depth)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+
+                2.0 * (state as f32 / u32::MAX as f32) - 1.0
+            })
+            .collect();
+
+        Self {
+            width,
+            scale,
+            values,
+        }
+    }
+
+    /// Returns the smoothly interpolated noise at the world space position (x, z).
+    fn sample(&self, x: f32, z: f32) -> f32 {
+        let (x, z) = (x / self.scale, z / self.scale);
+        let (cell_x, cell_z) = (x.floor() as usize, z.floor() as usize);
+
+        // smoothstep avoids visible creases along the lattice lines
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let (t_x, t_z) = (smooth(x.fract()), smooth(z.fract()));
+
+        let value = |x: usize, z: usize| self.values[z * self.width + x];
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+        lerp(
+            lerp(value(cell_x, cell_z), value(cell_x + 1, cell_z), t_x),
+            lerp(
+                value(cell_x, cell_z + 1),
+                value(cell_x + 1, cell_z + 1),
+                t_x,
+            ),
+            t_z,
+        )
+    }
+}
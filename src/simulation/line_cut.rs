@@ -0,0 +1,333 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::*;
+
+use crate::{renderer::pipelines::Pipelines, simulation::config::SimulationConfig};
+
+/// The most points a [`LineCut`] is sampled at.
+pub const MAX_LINE_CUT_SAMPLES: u32 = 4096;
+
+/// A straight segment across the water surface along which the displacement u is sampled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineCut {
+    /// The world space position (x, z) the segment starts at.
+    pub start: [f32; 2],
+    /// The world space position (x, z) the segment ends at.
+    pub end: [f32; 2],
+}
+
+/// The displacement u sampled along a [`LineCut`] at a single point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineProfile {
+    /// The segment the profile was sampled along.
+    pub line: LineCut,
+    /// The simulation time the profile was sampled at.
+    pub time: f32,
+    /// The (distance from the start, u) of every sample, in increasing order of distance.
+    pub samples: Vec<[f32; 2]>,
+}
+
+/// The GPU representation of the sampled segment, matching `LineCutParameters` in
+/// `line_cut.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct LineCutUniforms {
+    /// The grid coordinates the segment starts at.
+    pub start: [f32; 2],
+    /// The grid coordinates the segment ends at.
+    pub end: [f32; 2],
+    /// The number of evenly spaced samples taken along the segment.
+    pub sample_count: u32,
+    pub _padding: [u32; 3],
+}
+
+/// Samples the displacement along a [`LineCut`] on the GPU, reading the [`LineProfile`] back
+/// without stalling the frame.
+///
+/// The state is interpolated bilinearly at roughly one sample per cell, written to a storage
+/// buffer and copied into a mappable buffer. Like the
+/// [`StatisticsReduction`](super::statistics::StatisticsReduction), only a single profile is in
+/// flight at a time, so profiles requested in the meantime are skipped.
+pub struct LineCutSampler {
+    /// The segment being sampled, if any.
+    line: Option<LineCut>,
+    /// The extent and resolution of the grid being sampled.
+    config: SimulationConfig,
+
+    /// The uniform buffer holding the [`LineCutUniforms`].
+    uniforms_buffer: Buffer,
+    /// The storage buffer the samples are written to.
+    samples_buffer: Buffer,
+    /// The buffer the samples are copied into to be mapped and read by the CPU.
+    readback_buffer: Buffer,
+
+    /// The bind groups reading the simulation's texture 'a' and 'b' respectively.
+    bind_groups: [BindGroup; 2],
+
+    /// The progress of the profile in flight.
+    readback: Readback,
+}
+
+/// The progress of a profile from the GPU back to the CPU.
+enum Readback {
+    /// No profile is in flight.
+    Idle,
+    /// A profile of `sample_count` samples along `line` at `time` was recorded, but not yet
+    /// submitted.
+    Recorded {
+        line: LineCut,
+        time: f32,
+        sample_count: u32,
+    },
+    /// The readback buffer holding the profile along `line` at `time` is being mapped.
+    Mapping {
+        line: LineCut,
+        time: f32,
+        sample_count: u32,
+        receiver: Receiver<Result<(), BufferAsyncError>>,
+    },
+}
+
+impl LineCut {
+    /// Returns the length of the segment (in world units).
+    pub fn length(&self) -> f32 {
+        (self.end[0] - self.start[0]).hypot(self.end[1] - self.start[1])
+    }
+
+    /// Returns the number of samples taken along the segment, about one per cell crossed.
+    pub fn sample_count(&self, config: &SimulationConfig) -> u32 {
+        let cells = (self.length() / config.grid_spacing()).ceil() as u32;
+
+        (cells + 1).clamp(2, MAX_LINE_CUT_SAMPLES)
+    }
+
+    /// Returns the world space position (x, z) the given fraction of the way along the segment.
+    pub fn point_at(&self, t: f32) -> [f32; 2] {
+        [
+            self.start[0] + t * (self.end[0] - self.start[0]),
+            self.start[1] + t * (self.end[1] - self.start[1]),
+        ]
+    }
+}
+
+impl LineCutSampler {
+    /// The size of a single sample in the samples buffer (in bytes).
+    const SAMPLE_SIZE: BufferAddress = size_of::<f32>() as _;
+
+    /// Creates all resources to sample `line` from either of the simulation's `states`.
+    pub fn new(
+        device: &Device,
+        pipelines: &Pipelines,
+        config: &SimulationConfig,
+        states: [&Texture; 2],
+        line: Option<LineCut>,
+    ) -> Self {
+        let uniforms_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("LineCutSampler::uniforms_buffer"),
+            size: size_of::<LineCutUniforms>() as _,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let samples_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("LineCutSampler::samples_buffer"),
+            size: MAX_LINE_CUT_SAMPLES as BufferAddress * Self::SAMPLE_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("LineCutSampler::readback_buffer"),
+            size: samples_buffer.size(),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_groups = states.map(|state| {
+            let state_view = state.create_view(&TextureViewDescriptor::default());
+
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("LineCutSampler::bind_group"),
+                layout: &pipelines.line_cut_bind_group_layout,
+                entries: &[
+                    // uniforms
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: uniforms_buffer.as_entire_binding(),
+                    },
+                    // wave state
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&state_view),
+                    },
+                    // samples
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: samples_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        Self {
+            line,
+            config: *config,
+            uniforms_buffer,
+            samples_buffer,
+            readback_buffer,
+            bind_groups,
+            readback: Readback::Idle,
+        }
+    }
+
+    /// Returns the segment being sampled, if any.
+    pub fn line(&self) -> Option<LineCut> {
+        self.line
+    }
+
+    /// Replaces the segment being sampled, or stops sampling if `None`.
+    pub fn set_line(&mut self, line: Option<LineCut>) {
+        self.line = line;
+    }
+
+    /// Records sampling the state held by texture 'a' (`state` 0) or 'b' (`state` 1) at `time`,
+    /// unless no segment is set or a previous profile is still in flight.
+    pub fn record(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        pipelines: &Pipelines,
+        state: usize,
+        time: f32,
+    ) {
+        let Some(line) = self.line else {
+            return;
+        };
+
+        if !matches!(self.readback, Readback::Idle) {
+            return;
+        }
+
+        let dx = self.config.grid_spacing();
+        let sample_count = line.sample_count(&self.config);
+
+        let uniforms = LineCutUniforms {
+            start: line.start.map(|position| position / dx),
+            end: line.end.map(|position| position / dx),
+            sample_count,
+            _padding: [0; 3],
+        };
+
+        queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("LineCutSampler::record"),
+            timestamp_writes: None,
+        });
+
+        // the shader has a workgroup size of 64x1x1
+        pass.set_pipeline(&pipelines.line_cut_pipeline);
+        pass.set_bind_group(0, &self.bind_groups[state], &[]);
+        pass.dispatch_workgroups(sample_count.div_ceil(64), 1, 1);
+
+        drop(pass);
+
+        encoder.copy_buffer_to_buffer(
+            &self.samples_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            sample_count as BufferAddress * Self::SAMPLE_SIZE,
+        );
+
+        self.readback = Readback::Recorded {
+            line,
+            time,
+            sample_count,
+        };
+    }
+
+    /// Starts mapping the readback buffer, once the encoder the profile was recorded into has
+    /// been submitted.
+    pub fn begin_readback(&mut self) {
+        let Readback::Recorded {
+            line,
+            time,
+            sample_count,
+        } = self.readback
+        else {
+            return;
+        };
+
+        let size = sample_count as BufferAddress * Self::SAMPLE_SIZE;
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.readback_buffer
+            .slice(..size)
+            .map_async(MapMode::Read, move |result| {
+                // the receiver is gone if the sampler was recreated in the meantime
+                let _ = sender.send(result);
+            });
+
+        self.readback = Readback::Mapping {
+            line,
+            time,
+            sample_count,
+            receiver,
+        };
+    }
+
+    /// Returns the profile in flight if it has been read back, without blocking.
+    pub fn poll(&mut self, device: &Device) -> Option<LineProfile> {
+        let Readback::Mapping { receiver, .. } = &self.readback else {
+            return None;
+        };
+
+        if let Err(error) = device.poll(PollType::Poll) {
+            log::warn!("failed to poll the device for the line cut: {error}");
+        }
+
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(BufferAsyncError),
+        };
+
+        let Readback::Mapping {
+            line,
+            time,
+            sample_count,
+            ..
+        } = std::mem::replace(&mut self.readback, Readback::Idle)
+        else {
+            unreachable!();
+        };
+
+        if let Err(error) = result {
+            log::warn!("failed to read back the line cut: {error}");
+            return None;
+        }
+
+        let size = sample_count as BufferAddress * Self::SAMPLE_SIZE;
+        let spacing = line.length() / (sample_count - 1) as f32;
+
+        let samples = {
+            let data = self.readback_buffer.slice(..size).get_mapped_range();
+
+            bytemuck::cast_slice::<u8, f32>(&data)
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| [i as f32 * spacing, *sample])
+                .collect()
+        };
+
+        self.readback_buffer.unmap();
+
+        Some(LineProfile {
+            line,
+            time,
+            samples,
+        })
+    }
+}
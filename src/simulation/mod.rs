@@ -0,0 +1,1362 @@
+pub mod boundary;
+pub mod config;
+pub mod cpu;
+pub mod export;
+pub mod expression;
+pub mod initial;
+pub mod line_cut;
+pub mod obstacles;
+pub mod probes;
+pub mod snapshot;
+pub mod solver;
+pub mod sources;
+pub mod spectrum;
+pub mod speed_map;
+pub mod statistics;
+pub mod timestep;
+
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::*;
+
+use crate::{
+    renderer::pipelines::Pipelines,
+    simulation::{
+        boundary::{BoundaryConditions, PmlSettings},
+        config::SimulationConfig,
+        initial::{InitialCondition, InitialConditionKind},
+        line_cut::{LineCut, LineCutSampler, LineProfile},
+        obstacles::ObstacleMask,
+        probes::{Probe, ProbeHistory, ProbeRecorder},
+        snapshot::Snapshot,
+        sources::{GpuSource, MAX_SOURCES, Source, expression_signal_wgsl},
+        speed_map::SpeedMap,
+        statistics::{StatisticsReduction, StatisticsUniforms, WaveStatistics},
+    },
+};
+
+/// The most [`Impulse`]s that can be applied during a single tick.
+pub const MAX_IMPULSES: usize = 8;
+
+/// The side length of the square workgroups the simulation and its statistics are computed in.
+pub const WORKGROUP_SIZE: u32 = 16;
+
+/// The most ticks that can be run during a single call to [`WaveSimulation::step`].
+pub const MAX_SUBSTEPS: u32 = 64;
+
+/// The largest Courant number c·dt/dx the leapfrog scheme is stable for on a square 2D grid.
+pub const MAX_COURANT_NUMBER: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Manages all GPU state to numerically solve the wave equation.
+///
+/// The wave state is represented by two storage textures in the [`TextureFormat::Rg32Float`] format,
+/// with the red channel representing u(x, t) and the green channel representing u(x, t-1).
+///
+/// The wave speed c(x, z) of each cell is held in a [`TextureFormat::R32Float`] texture mirroring
+/// the CPU side [`SpeedMap`], and solid cells are marked in a [`TextureFormat::R8Uint`] texture
+/// mirroring the CPU side [`ObstacleMask`].
+///
+/// Every step, the enabled [`Source`]s are uploaded to a storage buffer and injected as a forcing
+/// term.
+///
+/// Each wave texture is paired with an auxiliary texture holding the (x, z) components of the
+/// perfectly matched layer's auxiliary field, which is zero outside of the layer.
+#[allow(unused)]
+pub struct WaveSimulation {
+    /// The physical parameters of the simulated medium.
+    pub parameters: WaveParameters,
+    /// How waves behave when reaching each edge of the domain.
+    pub boundaries: BoundaryConditions,
+    /// The perfectly matched layer lining any edges with a [`Boundary::Pml`](boundary::Boundary::Pml)
+    /// condition.
+    pub pml: PmlSettings,
+    /// The emitters injecting waves into the domain, of which at most [`MAX_SOURCES`] are used.
+    pub sources: Vec<Source>,
+
+    /// The simulation time elapsed since the initial state.
+    time: f32,
+    /// The impulses waiting to be applied during the next ticks.
+    pending_impulses: Vec<Impulse>,
+    /// The extent and resolution of the simulated domain.
+    config: SimulationConfig,
+    /// The state the wave starts out from, and is restored to on reset.
+    initial_condition: InitialCondition,
+    /// The wave speed of every cell, as last uploaded to `speed_texture`.
+    speed_map: SpeedMap,
+    /// The solid cells of the domain, as last uploaded to `obstacle_texture`.
+    obstacles: ObstacleMask,
+
+    /// Which texture is currently being read / written to.
+    /// - if `active` is even, `a` is the "read" texture and `b` is the "write" texture,
+    /// -  if `active` is odd, `a` is the "write" texture and `b` is the "read" texture.
+    active: usize,
+
+    /// Storage texture 'a' of the simulation.
+    texture_a: Texture,
+    /// Storage texture 'b' of the simulation.
+    texture_b: Texture,
+
+    /// The auxiliary field read and written alongside `texture_a`.
+    auxiliary_a: Texture,
+    /// The auxiliary field read and written alongside `texture_b`.
+    auxiliary_b: Texture,
+
+    /// The bind group holding `texture_a` as the "read" texture and `texture_b` as the "write"
+    /// texture, alongside their auxiliary fields.
+    a_read_b_write_bind_group: BindGroup,
+    /// The bind group holding `texture_a` as the "write" texture and `texture_b` as the "read"
+    /// texture, alongside their auxiliary fields.
+    b_read_a_write_bind_group: BindGroup,
+
+    /// The texture holding the wave speed of every cell.
+    speed_texture: Texture,
+    /// The texture marking which cells are solid obstacles.
+    obstacle_texture: Texture,
+
+    /// The uniform buffer holding the [`SimulationUniforms`] of the tick being run.
+    parameters_buffer: Buffer,
+    /// The buffer holding the [`SimulationUniforms`] of up to [`MAX_SUBSTEPS`] consecutive ticks,
+    /// copied into the `parameters_buffer` before each of them.
+    substep_uniforms_buffer: Buffer,
+    /// The storage buffer holding up to [`MAX_SOURCES`] [`GpuSource`]s.
+    sources_buffer: Buffer,
+    /// The bind group holding the `parameters_buffer` in slot 0, the `speed_texture` in slot 1, the
+    /// `obstacle_texture` in slot 2 and the `sources_buffer` in slot 3.
+    parameters_bind_group: BindGroup,
+    /// The simulation pipeline compiled with the drive expressions of the sources, alongside the
+    /// revision of the simulation shader and the generated WGSL it was compiled from, if any
+    /// source is driven by an expression.
+    expression_pipeline: Option<(u32, String, ComputePipeline)>,
+    /// Why the drive expressions of the sources could not be compiled, while the previous
+    /// `expression_pipeline` keeps running.
+    expression_error: Option<String>,
+    /// The revision of the simulation shader and the generated WGSL that failed to compile, so
+    /// that it isn't compiled again every frame.
+    failed_expression_signals: Option<(u32, String)>,
+
+    /// Measures the statistics of the current state in the background.
+    statistics: StatisticsReduction,
+    /// Records the displacement at every probe after each tick.
+    probe_recorder: ProbeRecorder,
+    /// Samples the current state along the line cut in the background.
+    line_cut: LineCutSampler,
+}
+
+/// The parameters of the numerical integration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaveParameters {
+    /// The requested duration of a single simulation tick (dt).
+    pub dt: f32,
+    /// Whether ticks of `dt` exceeding the stability limit are split into several smaller ticks,
+    /// rather than the simulation refusing to run.
+    pub auto_subdivide: bool,
+}
+
+/// The GPU representation of the simulation's parameters, matching `SimulationParameters` in
+/// `simulation.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct SimulationUniforms {
+    /// The duration of a single simulation tick (dt).
+    pub dt: f32,
+    /// The distance between two adjacent cells of the grid (dx).
+    pub dx: f32,
+    /// The thickness of the perfectly matched layer (in cells).
+    pub pml_thickness: f32,
+    /// The damping coefficient at the outermost cell of the perfectly matched layer.
+    pub pml_max_damping: f32,
+    /// The [`Boundary::gpu_id`](boundary::Boundary::gpu_id) of each edge, in the order x_min,
+    /// x_max, z_min, z_max.
+    pub boundaries: [u32; 4],
+    /// The order of the polynomial damping profile of the perfectly matched layer.
+    pub pml_order: f32,
+    /// The simulation time at the start of the tick.
+    pub time: f32,
+    /// The number of sources in the sources buffer.
+    pub source_count: u32,
+    /// The number of `impulses` to apply during the tick.
+    pub impulse_count: u32,
+    /// The [`Impulse`]s to apply during the tick, each packed as (x, z, radius, amplitude).
+    pub impulses: [[f32; 4]; MAX_IMPULSES],
+}
+
+/// A gaussian bump added on top of the wave at rest, like poking the surface of water.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Impulse {
+    /// The world space position (x, z) of the center of the bump.
+    pub position: [f32; 2],
+    /// The standard deviation of the bump (in world units).
+    pub radius: f32,
+    /// The height of the bump at its center.
+    pub amplitude: f32,
+}
+
+/// A [`Snapshot`] whose grid textures are being read back from the GPU, started by
+/// [`WaveSimulation::begin_snapshot`].
+pub struct PendingSnapshot {
+    /// Everything of the snapshot but the texels of the grid textures.
+    snapshot: Snapshot,
+    /// The copy of the grid textures being mapped.
+    readback: GridReadback,
+    /// Receives the outcome of mapping the `readback`.
+    receiver: Receiver<Result<(), BufferAsyncError>>,
+}
+
+/// Grid textures of the simulation copied into a single buffer, to be read back to the CPU.
+struct GridReadback {
+    /// The buffer holding the texels of every texture one after another.
+    buffer: Buffer,
+    /// The number of textures copied into the `buffer`.
+    textures: usize,
+    /// The size (in bytes) of a row of texels.
+    row_size: u32,
+    /// The size (in bytes) of a row of texels in the `buffer`, padded to the alignment of texture
+    /// copies.
+    padded_row_size: u32,
+}
+
+impl WaveParameters {
+    /// Creates a stable timestep for the given grid, where no wave travels faster than
+    /// `max_wave_speed`.
+    pub fn for_config(config: &SimulationConfig, max_wave_speed: f32) -> Self {
+        Self {
+            dt: Self::stable_dt(config, max_wave_speed),
+            auto_subdivide: true,
+        }
+    }
+
+    /// Returns a comfortably stable timestep for the given grid, where no wave travels faster than
+    /// `max_wave_speed`.
+    pub fn stable_dt(config: &SimulationConfig, max_wave_speed: f32) -> f32 {
+        // half of the largest stable timestep for the 2D leapfrog scheme
+        0.5 * MAX_COURANT_NUMBER * config.grid_spacing() / max_wave_speed
+    }
+
+    /// Returns the Courant number c·dt/dx of the requested `dt` on the given grid, where no wave
+    /// travels faster than `max_wave_speed`.
+    ///
+    /// The leapfrog scheme is only stable for Courant numbers up to [`MAX_COURANT_NUMBER`].
+    pub fn courant_number(&self, config: &SimulationConfig, max_wave_speed: f32) -> f32 {
+        max_wave_speed * self.dt / config.grid_spacing()
+    }
+
+    /// Returns how many ticks each step of the requested `dt` is split into to stay stable, which
+    /// is 1 unless `auto_subdivide` is needed.
+    pub fn subdivisions(&self, config: &SimulationConfig, max_wave_speed: f32) -> u32 {
+        if !self.auto_subdivide {
+            return 1;
+        }
+
+        let ratio = self.courant_number(config, max_wave_speed) / MAX_COURANT_NUMBER;
+
+        (ratio.ceil() as u32).clamp(1, MAX_SUBSTEPS)
+    }
+
+    /// Returns the duration of a single tick that is actually run, after any subdivision.
+    pub fn tick_dt(&self, config: &SimulationConfig, max_wave_speed: f32) -> f32 {
+        self.dt / self.subdivisions(config, max_wave_speed) as f32
+    }
+
+    /// Returns whether ticks of [`WaveParameters::tick_dt`] are stable.
+    pub fn tick_is_stable(&self, config: &SimulationConfig, max_wave_speed: f32) -> bool {
+        let subdivisions = self.subdivisions(config, max_wave_speed) as f32;
+
+        self.courant_number(config, max_wave_speed) / subdivisions <= MAX_COURANT_NUMBER
+    }
+}
+
+impl WaveSimulation {
+    /// Creates all resources to run the [`WaveSimulation`].
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        pipelines: &Pipelines,
+        config: SimulationConfig,
+    ) -> Self {
+        let texture_a = Self::create_compute_texture(device, &config, "a");
+        let texture_b = Self::create_compute_texture(device, &config, "b");
+
+        let auxiliary_a = Self::create_compute_texture(device, &config, "auxiliary_a");
+        let auxiliary_b = Self::create_compute_texture(device, &config, "auxiliary_b");
+
+        let a_read_b_write_bind_group = Self::create_read_write_bind_group(
+            device,
+            pipelines,
+            (&texture_a, &auxiliary_a),
+            (&texture_b, &auxiliary_b),
+            "a_read_b_write",
+        );
+
+        let b_read_a_write_bind_group = Self::create_read_write_bind_group(
+            device,
+            pipelines,
+            (&texture_b, &auxiliary_b),
+            (&texture_a, &auxiliary_a),
+            "b_read_a_write",
+        );
+
+        let parameters_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("WaveSimulation::parameters_buffer"),
+            size: size_of::<SimulationUniforms>() as _,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let substep_uniforms_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("WaveSimulation::substep_uniforms_buffer"),
+            size: (MAX_SUBSTEPS as usize * size_of::<SimulationUniforms>()) as _,
+            usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let sources_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("WaveSimulation::sources_buffer"),
+            size: (MAX_SOURCES * size_of::<GpuSource>()) as _,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let speed_map = SpeedMap::uniform(&config, 1.0);
+        let speed_texture =
+            Self::create_medium_texture(device, &config, TextureFormat::R32Float, "speed");
+
+        let obstacles = ObstacleMask::empty(&config);
+        let obstacle_texture =
+            Self::create_medium_texture(device, &config, TextureFormat::R8Uint, "obstacle");
+
+        let parameters_bind_group = Self::create_parameters_bind_group(
+            device,
+            pipelines,
+            &parameters_buffer,
+            &sources_buffer,
+            &speed_texture,
+            &obstacle_texture,
+        );
+
+        let statistics = StatisticsReduction::new(
+            device,
+            pipelines,
+            &config,
+            [&texture_a, &texture_b],
+            &speed_texture,
+            &obstacle_texture,
+        );
+
+        let probe_recorder = ProbeRecorder::new(
+            device,
+            queue,
+            pipelines,
+            &config,
+            [&texture_a, &texture_b],
+            Vec::new(),
+        );
+
+        let line_cut =
+            LineCutSampler::new(device, pipelines, &config, [&texture_a, &texture_b], None);
+
+        let simulation = Self {
+            parameters: WaveParameters::for_config(&config, speed_map.max_speed()),
+            boundaries: BoundaryConditions::default(),
+            pml: PmlSettings::default(),
+            sources: Vec::new(),
+            time: 0.0,
+            pending_impulses: Vec::new(),
+            initial_condition: InitialCondition::preset(
+                InitialConditionKind::GaussianBump,
+                &config,
+            ),
+            config,
+            speed_map,
+            obstacles,
+            active: 0,
+            texture_a,
+            texture_b,
+            auxiliary_a,
+            auxiliary_b,
+            a_read_b_write_bind_group,
+            b_read_a_write_bind_group,
+            speed_texture,
+            obstacle_texture,
+            parameters_buffer,
+            substep_uniforms_buffer,
+            sources_buffer,
+            parameters_bind_group,
+            expression_pipeline: None,
+            expression_error: None,
+            failed_expression_signals: None,
+            statistics,
+            probe_recorder,
+            line_cut,
+        };
+
+        simulation.write_initial_state(queue);
+        simulation.write_speed_map(queue);
+        simulation.write_obstacles(queue);
+
+        simulation
+    }
+
+    /// Returns the extent and resolution of the simulated domain.
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    /// Returns the simulation time elapsed since the initial state.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Returns the wave speed of every cell.
+    pub fn speed_map(&self) -> &SpeedMap {
+        &self.speed_map
+    }
+
+    /// Uploads a new [`SpeedMap`], resampling it onto the simulation grid if needed.
+    ///
+    /// The timestep is left as it is, and ticks are subdivided to stay stable for the fastest cell
+    /// of the new map (see [`WaveSimulation::subdivisions`]).
+    pub fn set_speed_map(&mut self, queue: &Queue, speed_map: SpeedMap) {
+        self.speed_map = if speed_map.size() == self.config.grid_size() {
+            speed_map
+        } else {
+            speed_map.resample(&self.config)
+        };
+
+        self.write_speed_map(queue);
+    }
+
+    /// Returns the solid obstacles inside the domain.
+    pub fn obstacles(&self) -> &ObstacleMask {
+        &self.obstacles
+    }
+
+    /// Uploads a new [`ObstacleMask`], resampling it onto the simulation grid if needed.
+    pub fn set_obstacles(&mut self, queue: &Queue, obstacles: ObstacleMask) {
+        self.obstacles = if obstacles.size() == self.config.grid_size() {
+            obstacles
+        } else {
+            obstacles.resample(&self.config)
+        };
+
+        self.write_obstacles(queue);
+    }
+
+    /// Recreates the simulation textures and bind groups to match the new [`SimulationConfig`],
+    /// resetting the simulation to its initial state.
+    ///
+    /// The speed map and obstacles are resampled onto the new grid. The timestep is kept and its
+    /// ticks subdivided to stay stable on the new grid, unless `auto_subdivide` is off, in which
+    /// case it is replaced by the [`WaveParameters::stable_dt`] of the new grid.
+    pub fn reconfigure(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        pipelines: &Pipelines,
+        config: SimulationConfig,
+    ) {
+        self.config = config;
+        self.speed_map = self.speed_map.resample(&config);
+        self.obstacles = self.obstacles.resample(&config);
+
+        // a finer grid would otherwise halt the simulation, with nothing to subdivide its ticks
+        if !self.parameters.auto_subdivide {
+            self.parameters.dt = WaveParameters::stable_dt(&config, self.speed_map.max_speed());
+        }
+
+        self.texture_a = Self::create_compute_texture(device, &config, "a");
+        self.texture_b = Self::create_compute_texture(device, &config, "b");
+
+        self.auxiliary_a = Self::create_compute_texture(device, &config, "auxiliary_a");
+        self.auxiliary_b = Self::create_compute_texture(device, &config, "auxiliary_b");
+
+        self.a_read_b_write_bind_group = Self::create_read_write_bind_group(
+            device,
+            pipelines,
+            (&self.texture_a, &self.auxiliary_a),
+            (&self.texture_b, &self.auxiliary_b),
+            "a_read_b_write",
+        );
+
+        self.b_read_a_write_bind_group = Self::create_read_write_bind_group(
+            device,
+            pipelines,
+            (&self.texture_b, &self.auxiliary_b),
+            (&self.texture_a, &self.auxiliary_a),
+            "b_read_a_write",
+        );
+
+        self.speed_texture =
+            Self::create_medium_texture(device, &config, TextureFormat::R32Float, "speed");
+        self.obstacle_texture =
+            Self::create_medium_texture(device, &config, TextureFormat::R8Uint, "obstacle");
+
+        self.parameters_bind_group = Self::create_parameters_bind_group(
+            device,
+            pipelines,
+            &self.parameters_buffer,
+            &self.sources_buffer,
+            &self.speed_texture,
+            &self.obstacle_texture,
+        );
+
+        self.statistics = StatisticsReduction::new(
+            device,
+            pipelines,
+            &config,
+            [&self.texture_a, &self.texture_b],
+            &self.speed_texture,
+            &self.obstacle_texture,
+        );
+
+        self.probe_recorder = ProbeRecorder::new(
+            device,
+            queue,
+            pipelines,
+            &config,
+            [&self.texture_a, &self.texture_b],
+            self.probe_recorder.probes().to_vec(),
+        );
+
+        self.line_cut = LineCutSampler::new(
+            device,
+            pipelines,
+            &config,
+            [&self.texture_a, &self.texture_b],
+            self.line_cut.line(),
+        );
+
+        self.reset(queue);
+        self.write_speed_map(queue);
+        self.write_obstacles(queue);
+    }
+
+    /// Returns the state the wave starts out from.
+    pub fn initial_condition(&self) -> &InitialCondition {
+        &self.initial_condition
+    }
+
+    /// Replaces the state the wave starts out from, resetting the simulation to it.
+    pub fn set_initial_condition(&mut self, queue: &Queue, initial_condition: InitialCondition) {
+        self.initial_condition = initial_condition;
+        self.reset(queue);
+    }
+
+    /// Restores the initial state of the wave, rewinding the simulation time to zero.
+    ///
+    /// The medium, obstacles and sources are kept as they are.
+    pub fn reset(&mut self, queue: &Queue) {
+        self.active = 0;
+        self.time = 0.0;
+        self.pending_impulses.clear();
+        self.probe_recorder.clear();
+
+        self.write_initial_state(queue);
+
+        // the auxiliary field of the layer starts out at rest as well
+        let zeros = vec![[0.0f32; 2]; self.config.cell_count()];
+        Self::write_grid_texture(queue, &self.auxiliary_a, bytemuck::cast_slice(&zeros));
+    }
+
+    /// Returns the Courant number c·dt/dx of the requested `dt`, using the fastest wave speed of
+    /// the medium.
+    ///
+    /// The simulation is only stable for Courant numbers up to [`MAX_COURANT_NUMBER`].
+    pub fn courant_number(&self) -> f32 {
+        self.parameters
+            .courant_number(&self.config, self.speed_map.max_speed())
+    }
+
+    /// Returns how many ticks each step of the requested `dt` is split into to keep the
+    /// simulation stable.
+    pub fn subdivisions(&self) -> u32 {
+        self.parameters
+            .subdivisions(&self.config, self.speed_map.max_speed())
+    }
+
+    /// Returns the duration of a single tick that is actually run, after any subdivision.
+    pub fn tick_dt(&self) -> f32 {
+        self.parameters
+            .tick_dt(&self.config, self.speed_map.max_speed())
+    }
+
+    /// Returns whether ticks of [`WaveSimulation::tick_dt`] are stable, or the simulation refuses
+    /// to run.
+    pub fn tick_is_stable(&self) -> bool {
+        self.parameters
+            .tick_is_stable(&self.config, self.speed_map.max_speed())
+    }
+
+    /// Returns the [`BindGroup`] holding the simulation parameters, wave speed map and obstacle
+    /// mask.
+    pub fn get_parameters(&self) -> &BindGroup {
+        &self.parameters_bind_group
+    }
+
+    /// Returns the currently "active" (read) texture's view in a [`BindGroup`] in slot 0.
+    pub fn get_active_texture(&self) -> &BindGroup {
+        if self.active.is_multiple_of(2) {
+            &self.a_read_b_write_bind_group
+        } else {
+            &self.b_read_a_write_bind_group
+        }
+    }
+
+    /// Returns the texture currently holding u(t) and u(t - dt).
+    pub fn get_current_state(&self) -> &Texture {
+        if self.active.is_multiple_of(2) {
+            &self.texture_a
+        } else {
+            &self.texture_b
+        }
+    }
+
+    /// Reads u(t) and u(t - dt) of every cell back from the GPU, stored row by row along the X
+    /// axis.
+    ///
+    /// This blocks until all previously submitted work has completed, so it is meant for tests
+    /// and offline runs rather than every frame.
+    pub fn read_state(&self, device: &Device, queue: &Queue) -> anyhow::Result<Vec<[f32; 2]>> {
+        let [state] = Self::read_grid_textures(device, queue, [self.get_current_state()])?;
+
+        Ok(state)
+    }
+
+    /// Copies the full state of the simulation back from the GPU into a [`Snapshot`], including
+    /// both wave textures and their auxiliary fields.
+    ///
+    /// Like [`WaveSimulation::read_state`], this blocks until all previously submitted work has
+    /// completed.
+    pub fn snapshot(&self, device: &Device, queue: &Queue) -> anyhow::Result<Snapshot> {
+        let [state_a, state_b, auxiliary_a, auxiliary_b] =
+            Self::read_grid_textures(device, queue, self.grid_textures())?;
+
+        Ok(Snapshot {
+            states: [state_a, state_b],
+            auxiliary: [auxiliary_a, auxiliary_b],
+            ..self.snapshot_settings()
+        })
+    }
+
+    /// Starts copying the full state of the simulation back from the GPU into a [`Snapshot`]
+    /// without blocking, which is completed across frames by polling the [`PendingSnapshot`].
+    pub fn begin_snapshot(&self, device: &Device, queue: &Queue) -> PendingSnapshot {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+        let readback = GridReadback::record(device, &mut encoder, self.grid_textures());
+        queue.submit([encoder.finish()]);
+
+        PendingSnapshot::new(self.snapshot_settings(), readback)
+    }
+
+    /// Returns the wave textures 'a' and 'b' followed by their auxiliary textures, in the order
+    /// their texels are stored in a [`Snapshot`].
+    fn grid_textures(&self) -> [&Texture; 4] {
+        [
+            &self.texture_a,
+            &self.texture_b,
+            &self.auxiliary_a,
+            &self.auxiliary_b,
+        ]
+    }
+
+    /// Captures everything of a [`Snapshot`] but the texels of the grid textures, which are left
+    /// empty.
+    fn snapshot_settings(&self) -> Snapshot {
+        Snapshot {
+            config: self.config,
+            parameters: self.parameters,
+            boundaries: self.boundaries,
+            pml: self.pml,
+            sources: self.sources.clone(),
+            initial_condition: self.initial_condition.clone(),
+            speed_map: self.speed_map.clone(),
+            obstacles: self.obstacles.clone(),
+            time: self.time,
+            active: (self.active % 2) as u32,
+            states: [Vec::new(), Vec::new()],
+            auxiliary: [Vec::new(), Vec::new()],
+        }
+    }
+
+    /// Restores the full state of the simulation from a [`Snapshot`], recreating all resources
+    /// first if it was taken on a different grid.
+    ///
+    /// Pending impulses are dropped and the probe history is cleared, as they belong to the run
+    /// being replaced.
+    pub fn restore(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        pipelines: &Pipelines,
+        snapshot: Snapshot,
+    ) {
+        if snapshot.config != self.config {
+            self.reconfigure(device, queue, pipelines, snapshot.config);
+        }
+
+        self.parameters = snapshot.parameters;
+        self.boundaries = snapshot.boundaries;
+        self.pml = snapshot.pml;
+        self.sources = snapshot.sources;
+        self.initial_condition = snapshot.initial_condition;
+        self.speed_map = snapshot.speed_map;
+        self.obstacles = snapshot.obstacles;
+
+        self.time = snapshot.time;
+        self.active = snapshot.active as usize;
+        self.pending_impulses.clear();
+        self.probe_recorder.clear();
+
+        self.write_speed_map(queue);
+        self.write_obstacles(queue);
+
+        let textures = [
+            &self.texture_a,
+            &self.texture_b,
+            &self.auxiliary_a,
+            &self.auxiliary_b,
+        ];
+
+        for (texture, texels) in textures
+            .into_iter()
+            .zip(snapshot.states.iter().chain(&snapshot.auxiliary))
+        {
+            Self::write_grid_texture(queue, texture, bytemuck::cast_slice(texels));
+        }
+    }
+
+    /// Reads the texels of grid textures back from the GPU, blocking until all previously
+    /// submitted work has completed.
+    fn read_grid_textures<const N: usize>(
+        device: &Device,
+        queue: &Queue,
+        textures: [&Texture; N],
+    ) -> anyhow::Result<[Vec<[f32; 2]>; N]> {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+        let readback = GridReadback::record(device, &mut encoder, textures);
+        queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        readback
+            .buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| sender.send(result).unwrap());
+
+        device.poll(PollType::wait_indefinitely())?;
+        receiver.recv()??;
+
+        Ok(readback
+            .texels()
+            .try_into()
+            .expect("a readback holds the texels of every texture it was recorded for"))
+    }
+
+    /// Records a measurement of the [`WaveStatistics`] of the current state, unless the previous
+    /// measurement is still being read back.
+    ///
+    /// Once the encoder has been submitted, [`WaveSimulation::begin_statistics_readback`] must be
+    /// called to start reading the measurement back.
+    pub fn measure_statistics(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        pipelines: &Pipelines,
+    ) {
+        let uniforms = StatisticsUniforms {
+            dt: self.tick_dt(),
+            dx: self.config.grid_spacing(),
+            _padding: [0.0; 2],
+            boundaries: self.boundaries.gpu_ids(),
+        };
+
+        self.statistics.record(
+            queue,
+            encoder,
+            pipelines,
+            self.active % 2,
+            uniforms,
+            self.time,
+        );
+    }
+
+    /// Starts reading back the measurement recorded by [`WaveSimulation::measure_statistics`],
+    /// once its encoder has been submitted.
+    pub fn begin_statistics_readback(&mut self) {
+        self.statistics.begin_readback();
+    }
+
+    /// Returns the latest measurement of the [`WaveStatistics`] once it has been read back,
+    /// without blocking.
+    pub fn poll_statistics(&mut self, device: &Device) -> Option<WaveStatistics> {
+        self.statistics.poll(device)
+    }
+
+    /// Returns the points whose displacement is recorded every tick.
+    pub fn probes(&self) -> &[Probe] {
+        self.probe_recorder.probes()
+    }
+
+    /// Replaces the points whose displacement is recorded every tick (up to
+    /// [`MAX_PROBES`](probes::MAX_PROBES)), clearing the recorded history.
+    pub fn set_probes(&mut self, queue: &Queue, probes: Vec<Probe>) {
+        self.probe_recorder.set_probes(queue, &self.config, probes);
+    }
+
+    /// Returns the displacement recorded at every probe since they were placed, or since the
+    /// last reset.
+    pub fn probe_history(&self) -> &ProbeHistory {
+        self.probe_recorder.history()
+    }
+
+    /// Starts reading back the probe samples recorded during [`WaveSimulation::step`], once its
+    /// encoder has been submitted.
+    pub fn begin_probe_readback(&mut self) {
+        self.probe_recorder.begin_readback();
+    }
+
+    /// Appends the probe samples to the [`WaveSimulation::probe_history`] once they have been
+    /// read back, without blocking.
+    pub fn poll_probes(&mut self, device: &Device) {
+        self.probe_recorder.poll(device);
+    }
+
+    /// Returns the segment the displacement is sampled along, if any.
+    pub fn line_cut(&self) -> Option<LineCut> {
+        self.line_cut.line()
+    }
+
+    /// Replaces the segment the displacement is sampled along, or stops sampling if `None`.
+    pub fn set_line_cut(&mut self, line: Option<LineCut>) {
+        self.line_cut.set_line(line);
+    }
+
+    /// Records sampling the current state along the line cut, unless none is set or the previous
+    /// profile is still being read back.
+    ///
+    /// Once the encoder has been submitted, [`WaveSimulation::begin_line_cut_readback`] must be
+    /// called to start reading the profile back.
+    pub fn measure_line_cut(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        pipelines: &Pipelines,
+    ) {
+        self.line_cut
+            .record(queue, encoder, pipelines, self.active % 2, self.time);
+    }
+
+    /// Starts reading back the profile recorded by [`WaveSimulation::measure_line_cut`], once its
+    /// encoder has been submitted.
+    pub fn begin_line_cut_readback(&mut self) {
+        self.line_cut.begin_readback();
+    }
+
+    /// Returns the latest [`LineProfile`] once it has been read back, without blocking.
+    pub fn poll_line_cut(&mut self, device: &Device) -> Option<LineProfile> {
+        self.line_cut.poll(device)
+    }
+
+    /// Queues an [`Impulse`] to be added to the wave during the next tick.
+    pub fn poke(&mut self, impulse: Impulse) {
+        self.pending_impulses.push(impulse);
+    }
+
+    /// Returns why the drive expressions of the sources could not be compiled for the GPU during
+    /// the last step, if they couldn't.
+    pub fn expression_error(&self) -> Option<&str> {
+        self.expression_error.as_deref()
+    }
+
+    /// Excecutes the simulation compute pipeline `substeps` times, advancing the simulation by as
+    /// many "ticks" of `dt` each (up to [`MAX_SUBSTEPS`]).
+    ///
+    /// Pending [`Impulse`]s are applied during the first of the ticks, and the displacement at
+    /// every probe is recorded after each of them.
+    pub fn step(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        pipelines: &Pipelines,
+        substeps: u32,
+    ) {
+        let substeps = substeps.min(MAX_SUBSTEPS);
+
+        // an unstable tick would blow up the whole wave within a few frames
+        if substeps == 0 || !self.tick_is_stable() {
+            return;
+        }
+
+        let active_sources = self
+            .sources
+            .iter()
+            .filter(|source| source.enabled)
+            .take(MAX_SOURCES)
+            .collect::<Vec<_>>();
+
+        let expression_signals = expression_signal_wgsl(active_sources.iter().copied());
+        let sources = active_sources
+            .into_iter()
+            .map(Source::to_gpu)
+            .collect::<Vec<_>>();
+
+        self.update_expression_pipeline(device, pipelines, expression_signals);
+
+        // impulses beyond the per-tick limit are left for the following ticks
+        let impulse_count = self.pending_impulses.len().min(MAX_IMPULSES);
+        let impulses = self
+            .pending_impulses
+            .drain(..impulse_count)
+            .collect::<Vec<_>>();
+
+        // buffer writes only take effect once the encoder is submitted, so the uniforms of every
+        // tick are uploaded at once and copied into place in between the ticks
+        let uniforms = (0..substeps)
+            .map(|substep| {
+                let time = self.time + substep as f32 * self.tick_dt();
+                let impulses: &[Impulse] = if substep == 0 { &impulses } else { &[] };
+
+                self.uniforms(time, sources.len(), impulses)
+            })
+            .collect::<Vec<_>>();
+
+        queue.write_buffer(
+            &self.substep_uniforms_buffer,
+            0,
+            bytemuck::cast_slice(&uniforms),
+        );
+
+        if !sources.is_empty() {
+            queue.write_buffer(&self.sources_buffer, 0, bytemuck::cast_slice(&sources));
+        }
+
+        self.probe_recorder.prepare(queue, substeps);
+
+        for substep in 0..substeps {
+            let size = size_of::<SimulationUniforms>() as BufferAddress;
+
+            encoder.copy_buffer_to_buffer(
+                &self.substep_uniforms_buffer,
+                substep as BufferAddress * size,
+                &self.parameters_buffer,
+                0,
+                size,
+            );
+
+            self.tick(encoder, pipelines);
+
+            self.probe_recorder.record_tick(
+                encoder,
+                pipelines,
+                substep,
+                self.active % 2,
+                self.time,
+            );
+        }
+
+        self.probe_recorder.record_readback(encoder);
+    }
+
+    /// Recompiles the `expression_pipeline` whenever the generated body of `expression_signal` or
+    /// the simulation shader it is injected into changes, dropping it once no source is driven by
+    /// an expression anymore.
+    ///
+    /// If the expressions cannot be compiled, the previous pipeline is kept and the error is
+    /// reported by [`WaveSimulation::expression_error`].
+    fn update_expression_pipeline(
+        &mut self,
+        device: &Device,
+        pipelines: &Pipelines,
+        expression_signals: anyhow::Result<Option<String>>,
+    ) {
+        let expression_signals = match expression_signals {
+            Ok(Some(expression_signals)) => expression_signals,
+            Ok(None) => {
+                self.expression_pipeline = None;
+                self.expression_error = None;
+                return;
+            }
+            Err(error) => {
+                self.expression_error = Some(format!("{error:#}"));
+                return;
+            }
+        };
+
+        let revision = pipelines.simulation_revision();
+
+        if let Some((compiled_revision, compiled, _)) = &self.expression_pipeline
+            && *compiled_revision == revision
+            && *compiled == expression_signals
+        {
+            self.expression_error = None;
+            return;
+        }
+
+        if let Some((failed_revision, failed)) = &self.failed_expression_signals
+            && *failed_revision == revision
+            && *failed == expression_signals
+        {
+            return;
+        }
+
+        match pipelines.create_simulation_pipeline(device, &expression_signals) {
+            Ok(pipeline) => {
+                self.expression_pipeline = Some((revision, expression_signals, pipeline));
+                self.expression_error = None;
+                self.failed_expression_signals = None;
+            }
+            Err(error) => {
+                log::warn!("Failed to compile the drive expressions: {error:#}");
+
+                self.expression_error = Some(format!("{error:#}"));
+                self.failed_expression_signals = Some((revision, expression_signals));
+            }
+        }
+    }
+
+    /// Records a single tick of the simulation compute pipeline, with the uniforms already in
+    /// place.
+    fn tick(&mut self, encoder: &mut CommandEncoder, pipelines: &Pipelines) {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("WaveSimulation::tick"),
+            timestamp_writes: None,
+        });
+
+        let active_bind_group = self.get_active_texture();
+
+        let x = self.texture_a.width().div_ceil(WORKGROUP_SIZE);
+        let y = self.texture_a.height().div_ceil(WORKGROUP_SIZE);
+
+        let pipeline = match &self.expression_pipeline {
+            Some((_, _, pipeline)) => pipeline,
+            None => &pipelines.simulation_pipeline,
+        };
+
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, active_bind_group, &[]);
+        pass.set_bind_group(1, &self.parameters_bind_group, &[]);
+        pass.dispatch_workgroups(x, y, 1);
+
+        drop(pass);
+
+        self.active += 1;
+        self.time += self.tick_dt();
+    }
+
+    /// Collects the uniforms describing the tick of the simulation starting at `time`.
+    fn uniforms(&self, time: f32, source_count: usize, impulses: &[Impulse]) -> SimulationUniforms {
+        let dx = self.config.grid_spacing();
+
+        let mut packed_impulses = [[0.0; 4]; MAX_IMPULSES];
+
+        for (packed, impulse) in packed_impulses.iter_mut().zip(impulses) {
+            let [x, z] = impulse.position;
+            *packed = [x, z, impulse.radius, impulse.amplitude];
+        }
+
+        SimulationUniforms {
+            dt: self.tick_dt(),
+            dx,
+            pml_thickness: self.pml.thickness as f32,
+            pml_max_damping: self.pml.max_damping(self.speed_map.max_speed(), dx),
+            boundaries: self.boundaries.gpu_ids(),
+            pml_order: self.pml.order,
+            time,
+            source_count: source_count as u32,
+            impulse_count: impulses.len() as u32,
+            impulses: packed_impulses,
+        }
+    }
+
+    /// Writes the `initial_condition` into both wave textures.
+    fn write_initial_state(&self, queue: &Queue) {
+        let texels = self
+            .initial_condition
+            .generate(&self.config, &self.speed_map, self.tick_dt());
+
+        for texture in [&self.texture_a, &self.texture_b] {
+            Self::write_grid_texture(queue, texture, bytemuck::cast_slice(&texels));
+        }
+    }
+
+    /// Uploads the CPU side `speed_map` into the `speed_texture`.
+    fn write_speed_map(&self, queue: &Queue) {
+        Self::write_grid_texture(
+            queue,
+            &self.speed_texture,
+            bytemuck::cast_slice(self.speed_map.values()),
+        );
+    }
+
+    /// Uploads the CPU side `obstacles` into the `obstacle_texture`.
+    fn write_obstacles(&self, queue: &Queue) {
+        Self::write_grid_texture(queue, &self.obstacle_texture, self.obstacles.cells());
+    }
+
+    /// Overwrites the whole of a texture covering the simulation grid with tightly packed rows of
+    /// texels.
+    fn write_grid_texture(queue: &Queue, texture: &Texture, data: &[u8]) {
+        let size = texture.size();
+        let texel_size = texture.format().block_copy_size(None).unwrap();
+
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            data,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width * texel_size),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+    }
+
+    /// Creates a storage [`Texture`] appropriate for use in the simulation.
+    fn create_compute_texture(device: &Device, config: &SimulationConfig, label: &str) -> Texture {
+        let (width, height) = config.grid_size();
+
+        device.create_texture(&TextureDescriptor {
+            label: Some(&format!("WaveSimulation::texture_{label}")),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rg32Float,
+            usage: TextureUsages::STORAGE_BINDING
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    /// Creates a read only [`Texture`] holding a property of every cell of the medium.
+    fn create_medium_texture(
+        device: &Device,
+        config: &SimulationConfig,
+        format: TextureFormat,
+        label: &str,
+    ) -> Texture {
+        let (width, height) = config.grid_size();
+
+        device.create_texture(&TextureDescriptor {
+            label: Some(&format!("WaveSimulation::{label}_texture")),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        })
+    }
+
+    /// Creates a [`BindGroup`] holding the uniform `buffer`, the `sources_buffer`, the
+    /// `speed_texture` and the `obstacle_texture`, corresponding to
+    /// [`Pipelines::simulation_parameters_bind_group_layout`].
+    fn create_parameters_bind_group(
+        device: &Device,
+        pipelines: &Pipelines,
+        buffer: &Buffer,
+        sources_buffer: &Buffer,
+        speed_texture: &Texture,
+        obstacle_texture: &Texture,
+    ) -> BindGroup {
+        let speed_view = speed_texture.create_view(&TextureViewDescriptor::default());
+        let obstacle_view = obstacle_texture.create_view(&TextureViewDescriptor::default());
+
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("WaveSimulation::parameters_bind_group"),
+            layout: &pipelines.simulation_parameters_bind_group_layout,
+            entries: &[
+                // uniforms
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                // wave speed map
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&speed_view),
+                },
+                // obstacle mask
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(&obstacle_view),
+                },
+                // sources
+                BindGroupEntry {
+                    binding: 3,
+                    resource: sources_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Creates a [`BindGroup`] holding the `left` textures in the read position and the `right`
+    /// textures in the write position, corresponding to [`Pipelines::texture_read_write_bind_group_layout`].
+    ///
+    /// Each side is a pair of (wave texture, auxiliary texture).
+    fn create_read_write_bind_group(
+        device: &Device,
+        pipelines: &Pipelines,
+        left: (&Texture, &Texture),
+        right: (&Texture, &Texture),
+        label: &str,
+    ) -> BindGroup {
+        let left_view = left.0.create_view(&TextureViewDescriptor::default());
+        let right_view = right.0.create_view(&TextureViewDescriptor::default());
+
+        let left_auxiliary_view = left.1.create_view(&TextureViewDescriptor::default());
+        let right_auxiliary_view = right.1.create_view(&TextureViewDescriptor::default());
+
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some(&format!("WaveSimulation::{label}_bind_group")),
+            layout: &pipelines.texture_read_write_bind_group_layout,
+            entries: &[
+                // read texture
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&left_view),
+                },
+                // write texture
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&right_view),
+                },
+                // auxiliary read texture
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(&left_auxiliary_view),
+                },
+                // auxiliary write texture
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(&right_auxiliary_view),
+                },
+            ],
+        })
+    }
+}
+
+impl PendingSnapshot {
+    /// Starts mapping the `readback` of the grid textures of `snapshot`, once the encoder it was
+    /// recorded into has been submitted.
+    fn new(snapshot: Snapshot, readback: GridReadback) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        readback
+            .buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                // the receiver is gone if the snapshot was dropped in the meantime
+                let _ = sender.send(result);
+            });
+
+        Self {
+            snapshot,
+            readback,
+            receiver,
+        }
+    }
+
+    /// Returns the snapshot once its grid textures have been read back, without blocking.
+    pub fn poll(&mut self, device: &Device) -> Option<anyhow::Result<Snapshot>> {
+        if let Err(error) = device.poll(PollType::Poll) {
+            log::warn!("failed to poll the device for a snapshot: {error}");
+        }
+
+        let result = match self.receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(BufferAsyncError),
+        };
+
+        Some(result.map_err(Into::into).map(|()| {
+            let mut texels = self.readback.texels().into_iter();
+            let mut next = || texels.next().unwrap_or_default();
+
+            Snapshot {
+                states: [next(), next()],
+                auxiliary: [next(), next()],
+                ..self.snapshot.clone()
+            }
+        }))
+    }
+}
+
+impl GridReadback {
+    /// Records copying `textures`, which all cover the simulation grid, into a new readback
+    /// buffer.
+    fn record<const N: usize>(
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        textures: [&Texture; N],
+    ) -> Self {
+        let size = textures[0].size();
+
+        let texel_size = textures[0].format().block_copy_size(None).unwrap();
+        let row_size = size.width * texel_size;
+        let padded_row_size = row_size.next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT);
+        let texture_size = (padded_row_size * size.height) as BufferAddress;
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("GridReadback::buffer"),
+            size: texture_size * N as BufferAddress,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        for (index, texture) in textures.into_iter().enumerate() {
+            encoder.copy_texture_to_buffer(
+                texture.as_image_copy(),
+                TexelCopyBufferInfo {
+                    buffer: &buffer,
+                    layout: TexelCopyBufferLayout {
+                        offset: index as BufferAddress * texture_size,
+                        bytes_per_row: Some(padded_row_size),
+                        rows_per_image: Some(size.height),
+                    },
+                },
+                size,
+            );
+        }
+
+        Self {
+            buffer,
+            textures: N,
+            row_size,
+            padded_row_size,
+        }
+    }
+
+    /// Returns the texels of every texture stored row by row along the X axis, once the `buffer`
+    /// has been mapped.
+    fn texels(&self) -> Vec<Vec<[f32; 2]>> {
+        let data = self.buffer.slice(..).get_mapped_range();
+
+        data.chunks_exact(data.len() / self.textures)
+            .map(|texture| {
+                texture
+                    .chunks_exact(self.padded_row_size as usize)
+                    .flat_map(|row| {
+                        bytemuck::cast_slice::<u8, [f32; 2]>(&row[..self.row_size as usize])
+                    })
+                    .copied()
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::config::SimulationConfig;
+
+/// How a solid cell of an [`ObstacleMask`] interacts with the wave.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Wall {
+    /// The wave is clamped to zero inside the wall, reflecting it with an inverted phase.
+    #[default]
+    Clamped,
+    /// The wall acts as a rigid mirror with zero slope, reflecting the wave with the same phase.
+    Reflecting,
+}
+
+/// Marks which cells of the simulation grid are solid obstacles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObstacleMask {
+    /// The number of cells across the X axis.
+    width: u32,
+    /// The number of cells across the Z axis.
+    depth: u32,
+    /// The [`ObstacleMask::gpu_id`] of each cell, stored row by row along the X axis.
+    cells: Vec<u8>,
+}
+
+/// Classic diffraction and reflection setups.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObstaclePreset {
+    /// No obstacles at all.
+    #[default]
+    None,
+    /// A wall across the domain with a single opening in its middle.
+    SingleSlit,
+    /// A wall across the domain with two narrow openings, as in Young's experiment.
+    DoubleSlit,
+    /// A wall across the domain with many equally spaced narrow openings.
+    Grating,
+    /// Two walls meeting at a right angle, sending waves back where they came from.
+    CornerReflector,
+}
+
+impl Wall {
+    /// All wall kinds, in the order they are presented to the user.
+    pub const ALL: [Self; 2] = [Self::Clamped, Self::Reflecting];
+
+    /// Returns a human readable name of the wall kind.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Clamped => "Clamped",
+            Self::Reflecting => "Reflecting",
+        }
+    }
+}
+
+impl ObstacleMask {
+    /// The identifier of an open cell used by `simulation.wgsl`.
+    pub const OPEN: u8 = 0;
+
+    /// Creates an [`ObstacleMask`] without any obstacles.
+    pub fn empty(config: &SimulationConfig) -> Self {
+        Self::from_fn(config, |_, _| None)
+    }
+
+    /// Creates an [`ObstacleMask`] by evaluating `wall` at the world space position (x, z) of
+    /// every cell, with [`None`] marking an open cell.
+    pub fn from_fn(config: &SimulationConfig, wall: impl Fn(f32, f32) -> Option<Wall>) -> Self {
+        let (width, depth) = config.grid_size();
+
+        let cells = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let (x, z) = config.cell_position(x, z);
+                Self::gpu_id(wall(x, z))
+            })
+            .collect();
+
+        Self {
+            width,
+            depth,
+            cells,
+        }
+    }
+
+    /// Creates an [`ObstacleMask`] from the state of every cell, stored row by row along the X
+    /// axis.
+    pub fn from_cells(config: &SimulationConfig, cells: &[Option<Wall>]) -> anyhow::Result<Self> {
+        let (width, depth) = config.grid_size();
+
+        ensure!(
+            cells.len() == config.cell_count(),
+            "expected {} cells for a {width}x{depth} grid, got {}",
+            config.cell_count(),
+            cells.len()
+        );
+
+        Ok(Self {
+            width,
+            depth,
+            cells: cells.iter().copied().map(Self::gpu_id).collect(),
+        })
+    }
+
+    /// Generates the given [`ObstaclePreset`] out of walls of the given kind.
+    ///
+    /// Walls running across the domain are placed at a third of its width, perpendicular to the
+    /// X axis.
+    pub fn from_preset(config: &SimulationConfig, preset: ObstaclePreset, wall: Wall) -> Self {
+        let (width, depth) = (config.width, config.depth);
+
+        // walls are two cells thick, so waves can't leak through them diagonally
+        let half_thickness = config.grid_spacing();
+        let wall_x = width / 3.0;
+        let center_z = depth / 2.0;
+
+        // builds a wall across the domain, open wherever `is_open` returns true for the offset
+        // from the center of the wall
+        let barrier = |is_open: &dyn Fn(f32) -> bool| {
+            Self::from_fn(config, |x, z| {
+                let in_wall = (x - wall_x).abs() < half_thickness && !is_open(z - center_z);
+                in_wall.then_some(wall)
+            })
+        };
+
+        match preset {
+            ObstaclePreset::None => Self::empty(config),
+
+            ObstaclePreset::SingleSlit => {
+                let slit_width = 0.06 * depth;
+
+                barrier(&|offset| offset.abs() < slit_width / 2.0)
+            }
+
+            ObstaclePreset::DoubleSlit => {
+                let slit_width = 0.03 * depth;
+                let separation = 0.15 * depth;
+
+                barrier(&|offset| (offset.abs() - separation / 2.0).abs() < slit_width / 2.0)
+            }
+
+            ObstaclePreset::Grating => {
+                let slit_width = 0.015 * depth;
+                let period = 0.05 * depth;
+
+                barrier(&|offset| {
+                    let phase = (offset / period).rem_euclid(1.0) * period;
+                    phase < slit_width
+                })
+            }
+
+            ObstaclePreset::CornerReflector => {
+                let (vertex_x, vertex_z) = (0.75 * width, center_z);
+                let arm_length = 0.3 * width.min(depth);
+
+                // the arms leave the vertex at 45 degrees towards -X, opening towards the source
+                Self::from_fn(config, |x, z| {
+                    let along = vertex_x - x;
+                    let across = (z - vertex_z).abs();
+
+                    let distance_to_arm = (along - across).abs() / std::f32::consts::SQRT_2;
+                    let distance_along_arm = (along + across) / std::f32::consts::SQRT_2;
+
+                    let in_wall = distance_to_arm < half_thickness
+                        && (0.0..arm_length).contains(&distance_along_arm);
+
+                    in_wall.then_some(wall)
+                })
+            }
+        }
+    }
+
+    /// Resamples the mask onto a new grid by taking the nearest cell of the current mask.
+    pub fn resample(&self, config: &SimulationConfig) -> Self {
+        let (width, depth) = config.grid_size();
+
+        let cells = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let source_x = (x as u64 * self.width as u64 / width as u64) as u32;
+                let source_z = (z as u64 * self.depth as u64 / depth as u64) as u32;
+
+                self.cells[(source_z * self.width + source_x) as usize]
+            })
+            .collect();
+
+        Self {
+            width,
+            depth,
+            cells,
+        }
+    }
+
+    /// Returns the wall occupying the cell at the given grid coordinates, if any.
+    pub fn wall_at(&self, x: u32, z: u32) -> Option<Wall> {
+        match self.cells[(z * self.width + x) as usize] {
+            1 => Some(Wall::Clamped),
+            2 => Some(Wall::Reflecting),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of cells across the X and Z axes respectively.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.depth)
+    }
+
+    /// Returns the identifier of every cell used by `simulation.wgsl`, stored row by row along
+    /// the X axis.
+    pub fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    /// Returns the identifier of a cell used by `simulation.wgsl`.
+    fn gpu_id(wall: Option<Wall>) -> u8 {
+        match wall {
+            None => Self::OPEN,
+            Some(Wall::Clamped) => 1,
+            Some(Wall::Reflecting) => 2,
+        }
+    }
+}
+
+impl ObstaclePreset {
+    /// All presets, in the order they are presented to the user.
+    pub const ALL: [Self; 5] = [
+        Self::None,
+        Self::SingleSlit,
+        Self::DoubleSlit,
+        Self::Grating,
+        Self::CornerReflector,
+    ];
+
+    /// Returns a human readable name of the preset.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::SingleSlit => "Single slit",
+            Self::DoubleSlit => "Double slit",
+            Self::Grating => "Diffraction grating",
+            Self::CornerReflector => "Corner reflector",
+        }
+    }
+}
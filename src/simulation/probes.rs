@@ -0,0 +1,524 @@
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    sync::mpsc::{Receiver, TryRecvError},
+};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::*;
+
+use crate::{
+    renderer::pipelines::Pipelines,
+    simulation::{MAX_SUBSTEPS, config::SimulationConfig, spectrum},
+};
+
+/// The most probes that can be recorded at once.
+pub const MAX_PROBES: usize = 16;
+
+/// The number of ticks the GPU ring buffer holds samples of until they are read back.
+pub const PROBE_RING_LENGTH: u32 = 1024;
+
+/// The most ticks of samples kept in a [`ProbeHistory`].
+pub const PROBE_HISTORY_LENGTH: usize = 16384;
+
+/// A point of the grid whose displacement u(t) is recorded every tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Probe {
+    /// The world space position (x, z) of the probe, which is snapped to the nearest cell.
+    pub position: [f32; 2],
+}
+
+/// The recorded displacement u(t) of every probe over the latest ticks.
+#[derive(Debug, Clone, Default)]
+pub struct ProbeHistory {
+    /// The simulation time of every recorded tick, oldest first.
+    times: VecDeque<f32>,
+    /// The recorded samples of each probe, aligned with `times`.
+    samples: Vec<VecDeque<f32>>,
+}
+
+/// The GPU representation of a single recording, matching `ProbeParameters` in `probes.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct ProbeUniforms {
+    /// The slot of the ring buffer the samples of the tick are written to.
+    pub slot: u32,
+    /// The number of probes being recorded.
+    pub probe_count: u32,
+    pub _padding: [u32; 2],
+}
+
+/// Records the displacement at every [`Probe`] after each tick into a ring buffer on the GPU,
+/// reading it back into a [`ProbeHistory`] without stalling the frame.
+///
+/// The ring buffer holds [`PROBE_RING_LENGTH`] ticks, so as long as a readback completes within
+/// that many ticks, no samples are lost.
+pub struct ProbeRecorder {
+    /// The probes being recorded.
+    probes: Vec<Probe>,
+    /// The samples read back so far.
+    history: ProbeHistory,
+    /// Incremented whenever the history is cleared, to discard samples of readbacks started
+    /// before.
+    generation: u32,
+
+    /// The number of ticks recorded into the ring buffer since it was last cleared.
+    recorded: u64,
+    /// The number of those ticks whose samples have been copied out of the ring buffer.
+    copied: u64,
+    /// The simulation time of every recorded tick not yet copied out of the ring buffer.
+    pending_times: VecDeque<f32>,
+
+    /// The uniform buffer holding the [`ProbeUniforms`] of the tick being recorded.
+    parameters_buffer: Buffer,
+    /// The buffer holding the [`ProbeUniforms`] of up to [`MAX_SUBSTEPS`] consecutive ticks,
+    /// copied into the `parameters_buffer` before each of them.
+    substep_uniforms_buffer: Buffer,
+    /// The storage buffer holding the grid coordinates of up to [`MAX_PROBES`] probes.
+    probes_buffer: Buffer,
+    /// The storage buffer holding the samples of [`PROBE_RING_LENGTH`] ticks.
+    ring_buffer: Buffer,
+    /// The buffer samples are copied into to be mapped and read by the CPU.
+    readback_buffer: Buffer,
+
+    /// The bind groups reading the simulation's texture 'a' and 'b' respectively.
+    bind_groups: [BindGroup; 2],
+
+    /// The progress of the readback in flight.
+    readback: Readback,
+}
+
+/// The progress of a readback of samples from the GPU to the CPU.
+enum Readback {
+    /// No readback is in flight.
+    Idle,
+    /// The samples of the ticks at `times` were copied, but not yet submitted.
+    Recorded { times: Vec<f32>, generation: u32 },
+    /// The readback buffer holding the samples of the ticks at `times` is being mapped.
+    Mapping {
+        times: Vec<f32>,
+        generation: u32,
+        receiver: Receiver<Result<(), BufferAsyncError>>,
+    },
+}
+
+impl Probe {
+    /// Returns the grid coordinates of the cell nearest to the probe.
+    pub fn cell(&self, config: &SimulationConfig) -> [u32; 2] {
+        let (width, depth) = config.grid_size();
+        let dx = config.grid_spacing();
+
+        let snap =
+            |position: f32, cells: u32| ((position / dx).round().max(0.0) as u32).min(cells - 1);
+
+        [snap(self.position[0], width), snap(self.position[1], depth)]
+    }
+}
+
+impl ProbeHistory {
+    /// Returns the number of recorded ticks.
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    /// Returns whether no ticks have been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Returns the simulation time of every recorded tick, oldest first.
+    pub fn times(&self) -> &VecDeque<f32> {
+        &self.times
+    }
+
+    /// Returns the recorded samples of the given probe, oldest first.
+    pub fn samples(&self, probe: usize) -> &VecDeque<f32> {
+        &self.samples[probe]
+    }
+
+    /// Returns the latest recorded sample of the given probe.
+    pub fn latest(&self, probe: usize) -> Option<f32> {
+        self.samples.get(probe)?.back().copied()
+    }
+
+    /// Returns the amplitude spectrum of the latest `length` samples (at most) of the given
+    /// probe, as (frequency, amplitude) pairs.
+    pub fn spectrum(&self, probe: usize, length: usize) -> Vec<[f32; 2]> {
+        let start = self.len().saturating_sub(length);
+        let samples = self.samples[probe]
+            .range(start..)
+            .copied()
+            .collect::<Vec<_>>();
+
+        let (Some(first), Some(last)) = (self.times.get(start), self.times.back()) else {
+            return Vec::new();
+        };
+
+        // the ticks are assumed to be evenly spaced
+        let dt = (last - first) / (samples.len().max(2) - 1) as f32;
+
+        spectrum::amplitude_spectrum(&samples, dt)
+    }
+
+    /// Formats the history as CSV, with a column for the time followed by one for every probe.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time");
+
+        for probe in 0..self.samples.len() {
+            write!(csv, ",probe_{}", probe + 1).unwrap();
+        }
+
+        csv.push('\n');
+
+        for (tick, time) in self.times.iter().enumerate() {
+            write!(csv, "{time}").unwrap();
+
+            for samples in &self.samples {
+                write!(csv, ",{}", samples[tick]).unwrap();
+            }
+
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// Forgets all samples, preparing the history for `probe_count` probes.
+    fn clear(&mut self, probe_count: usize) {
+        self.times.clear();
+        self.samples = vec![VecDeque::new(); probe_count];
+    }
+
+    /// Appends the samples of every probe at a single tick, dropping the oldest tick if full.
+    fn push(&mut self, time: f32, samples: &[f32]) {
+        if self.len() == PROBE_HISTORY_LENGTH {
+            self.times.pop_front();
+            self.samples.iter_mut().for_each(|samples| {
+                samples.pop_front();
+            });
+        }
+
+        self.times.push_back(time);
+
+        for (history, sample) in self.samples.iter_mut().zip(samples) {
+            history.push_back(*sample);
+        }
+    }
+}
+
+impl ProbeRecorder {
+    /// The size of the samples of a single tick in the ring buffer (in bytes).
+    const TICK_SIZE: BufferAddress = (MAX_PROBES * size_of::<f32>()) as _;
+
+    /// Creates all resources to record `probes` from either of the simulation's `states`.
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        pipelines: &Pipelines,
+        config: &SimulationConfig,
+        states: [&Texture; 2],
+        probes: Vec<Probe>,
+    ) -> Self {
+        let parameters_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("ProbeRecorder::parameters_buffer"),
+            size: size_of::<ProbeUniforms>() as _,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let substep_uniforms_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("ProbeRecorder::substep_uniforms_buffer"),
+            size: (MAX_SUBSTEPS as usize * size_of::<ProbeUniforms>()) as _,
+            usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let probes_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("ProbeRecorder::probes_buffer"),
+            size: (MAX_PROBES * size_of::<[u32; 2]>()) as _,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let ring_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("ProbeRecorder::ring_buffer"),
+            size: PROBE_RING_LENGTH as BufferAddress * Self::TICK_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("ProbeRecorder::readback_buffer"),
+            size: ring_buffer.size(),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_groups = states.map(|state| {
+            let state_view = state.create_view(&TextureViewDescriptor::default());
+
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("ProbeRecorder::bind_group"),
+                layout: &pipelines.probes_bind_group_layout,
+                entries: &[
+                    // uniforms
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: parameters_buffer.as_entire_binding(),
+                    },
+                    // wave state
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&state_view),
+                    },
+                    // probe cells
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: probes_buffer.as_entire_binding(),
+                    },
+                    // ring buffer
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: ring_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let mut recorder = Self {
+            probes: Vec::new(),
+            history: ProbeHistory::default(),
+            generation: 0,
+            recorded: 0,
+            copied: 0,
+            pending_times: VecDeque::new(),
+            parameters_buffer,
+            substep_uniforms_buffer,
+            probes_buffer,
+            ring_buffer,
+            readback_buffer,
+            bind_groups,
+            readback: Readback::Idle,
+        };
+
+        recorder.set_probes(queue, config, probes);
+        recorder
+    }
+
+    /// Returns the probes being recorded.
+    pub fn probes(&self) -> &[Probe] {
+        &self.probes
+    }
+
+    /// Replaces the probes being recorded (up to [`MAX_PROBES`]), clearing the history.
+    pub fn set_probes(&mut self, queue: &Queue, config: &SimulationConfig, mut probes: Vec<Probe>) {
+        probes.truncate(MAX_PROBES);
+
+        let cells = probes
+            .iter()
+            .map(|probe| probe.cell(config))
+            .collect::<Vec<_>>();
+
+        if !cells.is_empty() {
+            queue.write_buffer(&self.probes_buffer, 0, bytemuck::cast_slice(&cells));
+        }
+
+        self.probes = probes;
+        self.clear();
+    }
+
+    /// Returns the samples read back so far.
+    pub fn history(&self) -> &ProbeHistory {
+        &self.history
+    }
+
+    /// Forgets all samples, including those still in flight.
+    pub fn clear(&mut self) {
+        self.history.clear(self.probes.len());
+        self.generation = self.generation.wrapping_add(1);
+
+        self.recorded = 0;
+        self.copied = 0;
+        self.pending_times.clear();
+    }
+
+    /// Uploads the uniforms of the next `substeps` ticks to be recorded.
+    pub fn prepare(&self, queue: &Queue, substeps: u32) {
+        if self.probes.is_empty() {
+            return;
+        }
+
+        let uniforms = (0..substeps as u64)
+            .map(|substep| ProbeUniforms {
+                slot: ((self.recorded + substep) % PROBE_RING_LENGTH as u64) as u32,
+                probe_count: self.probes.len() as u32,
+                _padding: [0; 2],
+            })
+            .collect::<Vec<_>>();
+
+        queue.write_buffer(
+            &self.substep_uniforms_buffer,
+            0,
+            bytemuck::cast_slice(&uniforms),
+        );
+    }
+
+    /// Records the samples of every probe from the state held by texture 'a' (`state` 0) or 'b'
+    /// (`state` 1), as the `substep`th of the ticks prepared by [`ProbeRecorder::prepare`], which
+    /// ended at `time`.
+    pub fn record_tick(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        pipelines: &Pipelines,
+        substep: u32,
+        state: usize,
+        time: f32,
+    ) {
+        if self.probes.is_empty() {
+            return;
+        }
+
+        let size = size_of::<ProbeUniforms>() as BufferAddress;
+
+        encoder.copy_buffer_to_buffer(
+            &self.substep_uniforms_buffer,
+            substep as BufferAddress * size,
+            &self.parameters_buffer,
+            0,
+            size,
+        );
+
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("ProbeRecorder::record_tick"),
+            timestamp_writes: None,
+        });
+
+        // the shader has a workgroup size of `MAX_PROBES`
+        pass.set_pipeline(&pipelines.probes_pipeline);
+        pass.set_bind_group(0, &self.bind_groups[state], &[]);
+        pass.dispatch_workgroups(1, 1, 1);
+
+        drop(pass);
+
+        self.recorded += 1;
+        self.pending_times.push_back(time);
+    }
+
+    /// Copies the samples recorded since the last readback out of the ring buffer, unless a
+    /// previous readback is still in flight.
+    pub fn record_readback(&mut self, encoder: &mut CommandEncoder) {
+        if !matches!(self.readback, Readback::Idle) || self.recorded == self.copied {
+            return;
+        }
+
+        // the oldest samples have already been overwritten if the readbacks fell behind
+        let overwritten = (self.recorded - self.copied).saturating_sub(PROBE_RING_LENGTH as u64);
+
+        if overwritten > 0 {
+            log::warn!("dropped the probe samples of {overwritten} ticks");
+
+            self.copied += overwritten;
+            self.pending_times.drain(..overwritten as usize);
+        }
+
+        let ticks = self.recorded - self.copied;
+        let start = self.copied % PROBE_RING_LENGTH as u64;
+
+        // the ticks may wrap around the end of the ring buffer
+        let before_wrap = ticks.min(PROBE_RING_LENGTH as u64 - start);
+
+        encoder.copy_buffer_to_buffer(
+            &self.ring_buffer,
+            start * Self::TICK_SIZE,
+            &self.readback_buffer,
+            0,
+            before_wrap * Self::TICK_SIZE,
+        );
+
+        if ticks > before_wrap {
+            encoder.copy_buffer_to_buffer(
+                &self.ring_buffer,
+                0,
+                &self.readback_buffer,
+                before_wrap * Self::TICK_SIZE,
+                (ticks - before_wrap) * Self::TICK_SIZE,
+            );
+        }
+
+        self.copied = self.recorded;
+        self.readback = Readback::Recorded {
+            times: self.pending_times.drain(..).collect(),
+            generation: self.generation,
+        };
+    }
+
+    /// Starts mapping the readback buffer, once the encoder the readback was recorded into has
+    /// been submitted.
+    pub fn begin_readback(&mut self) {
+        let Readback::Recorded { times, generation } =
+            std::mem::replace(&mut self.readback, Readback::Idle)
+        else {
+            return;
+        };
+
+        let size = times.len() as BufferAddress * Self::TICK_SIZE;
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.readback_buffer
+            .slice(..size)
+            .map_async(MapMode::Read, move |result| {
+                // the receiver is gone if the recorder was recreated in the meantime
+                let _ = sender.send(result);
+            });
+
+        self.readback = Readback::Mapping {
+            times,
+            generation,
+            receiver,
+        };
+    }
+
+    /// Appends the samples in flight to the history if they have been read back, without
+    /// blocking.
+    pub fn poll(&mut self, device: &Device) {
+        let Readback::Mapping { receiver, .. } = &self.readback else {
+            return;
+        };
+
+        if let Err(error) = device.poll(PollType::Poll) {
+            log::warn!("failed to poll the device for probe samples: {error}");
+        }
+
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(BufferAsyncError),
+        };
+
+        let Readback::Mapping {
+            times, generation, ..
+        } = std::mem::replace(&mut self.readback, Readback::Idle)
+        else {
+            unreachable!();
+        };
+
+        if let Err(error) = result {
+            log::warn!("failed to read back the probe samples: {error}");
+            return;
+        }
+
+        let size = times.len() as BufferAddress * Self::TICK_SIZE;
+
+        // samples of probes that have since been replaced are dropped
+        if generation == self.generation {
+            let data = self.readback_buffer.slice(..size).get_mapped_range();
+            let samples = bytemuck::cast_slice::<u8, [f32; MAX_PROBES]>(&data);
+
+            for (time, samples) in times.into_iter().zip(samples) {
+                self.history.push(time, &samples[..self.probes.len()]);
+            }
+        }
+
+        self.readback_buffer.unmap();
+    }
+}
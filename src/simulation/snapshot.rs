@@ -0,0 +1,462 @@
+use anyhow::{Context, bail, ensure};
+
+use crate::simulation::{
+    WaveParameters,
+    boundary::{Boundary, BoundaryConditions, Edge, PmlSettings},
+    config::SimulationConfig,
+    expression::Expression,
+    initial::{InitialCondition, InitialConditionKind},
+    obstacles::{ObstacleMask, Wall},
+    sources::{Source, SourceShape, Waveform},
+    speed_map::SpeedMap,
+};
+
+/// The bytes every snapshot file starts with.
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"WAVESNAP";
+
+/// The version of the snapshot format written by [`Snapshot::to_bytes`].
+///
+/// This must be bumped whenever the layout changes, as older files are rejected rather than
+/// misread.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// The full state of a [`WaveSimulation`](super::WaveSimulation) at a single point in time,
+/// from which a run can be restored exactly.
+///
+/// Snapshots are stored in a little endian binary format starting with [`SNAPSHOT_MAGIC`] and
+/// [`SNAPSHOT_VERSION`], followed by every field in declaration order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// The extent and resolution of the simulated domain.
+    pub config: SimulationConfig,
+    /// The parameters of the numerical integration.
+    pub parameters: WaveParameters,
+    /// How waves behave when reaching each edge of the domain.
+    pub boundaries: BoundaryConditions,
+    /// The perfectly matched layer lining any [`Boundary::Pml`] edges.
+    pub pml: PmlSettings,
+    /// The emitters injecting waves into the domain.
+    pub sources: Vec<Source>,
+    /// The state the wave started out from, and is restored to on reset.
+    pub initial_condition: InitialCondition,
+    /// The wave speed of every cell.
+    pub speed_map: SpeedMap,
+    /// The solid obstacles inside the domain.
+    pub obstacles: ObstacleMask,
+
+    /// The simulation time elapsed since the initial state.
+    pub time: f32,
+    /// Which of the two wave textures is read during the next tick, 0 for 'a' and 1 for 'b'.
+    pub active: u32,
+    /// The (u(t), u(t - dt)) of every cell held by texture 'a' and 'b' respectively, stored row by
+    /// row along the X axis.
+    pub states: [Vec<[f32; 2]>; 2],
+    /// The auxiliary field of the perfectly matched layer read and written alongside texture 'a'
+    /// and 'b' respectively, stored row by row along the X axis.
+    pub auxiliary: [Vec<[f32; 2]>; 2],
+}
+
+/// Appends values to a snapshot in its little endian binary format.
+#[derive(Debug, Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+/// Reads values back from a snapshot in its little endian binary format.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Snapshot {
+    /// Encodes the snapshot into the versioned binary snapshot format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+
+        writer.bytes.extend_from_slice(&SNAPSHOT_MAGIC);
+        writer.u32(SNAPSHOT_VERSION);
+
+        let config = &self.config;
+        writer.f32s(&[config.width, config.depth, config.cells_per_unit]);
+
+        writer.f32(self.parameters.dt);
+        writer.bool(self.parameters.auto_subdivide);
+
+        for edge in Edge::ALL {
+            writer.u32(self.boundaries.get(edge).gpu_id());
+        }
+
+        writer.u32(self.pml.thickness);
+        writer.f32s(&[self.pml.order, self.pml.reflection]);
+
+        writer.u32(self.sources.len() as u32);
+
+        for source in &self.sources {
+            writer.source(source);
+        }
+
+        writer.initial_condition(&self.initial_condition);
+
+        writer.f32s(self.speed_map.values());
+        writer.bytes.extend_from_slice(self.obstacles.cells());
+
+        writer.f32(self.time);
+        writer.u32(self.active);
+
+        for field in self.states.iter().chain(&self.auxiliary) {
+            writer.f32s(bytemuck::cast_slice(field));
+        }
+
+        writer.bytes
+    }
+
+    /// Decodes a snapshot from the versioned binary snapshot format, validating it against the
+    /// grid it describes.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = Reader { bytes };
+
+        ensure!(
+            reader.take(SNAPSHOT_MAGIC.len())? == SNAPSHOT_MAGIC,
+            "not a wave simulation snapshot"
+        );
+
+        let version = reader.u32()?;
+
+        ensure!(
+            version == SNAPSHOT_VERSION,
+            "unsupported snapshot version {version}, expected {SNAPSHOT_VERSION}"
+        );
+
+        let [width, depth, cells_per_unit] = reader.f32_array()?;
+        let config = SimulationConfig {
+            width,
+            depth,
+            cells_per_unit,
+        };
+
+        ensure!(
+            [width, depth, cells_per_unit]
+                .iter()
+                .all(|value| value.is_finite() && *value > 0.0),
+            "invalid simulation domain {config:?}"
+        );
+
+        let parameters = WaveParameters {
+            dt: reader.f32()?,
+            auto_subdivide: reader.bool()?,
+        };
+
+        let mut boundaries = BoundaryConditions::default();
+
+        for edge in Edge::ALL {
+            let id = reader.u32()?;
+            let boundary = Boundary::ALL
+                .into_iter()
+                .find(|boundary| boundary.gpu_id() == id)
+                .with_context(|| format!("unknown boundary {id} on the {} edge", edge.name()))?;
+
+            // set directly, as `BoundaryConditions::set` would also switch the opposite edge
+            match edge {
+                Edge::XMin => boundaries.x_min = boundary,
+                Edge::XMax => boundaries.x_max = boundary,
+                Edge::ZMin => boundaries.z_min = boundary,
+                Edge::ZMax => boundaries.z_max = boundary,
+            }
+        }
+
+        let pml = PmlSettings {
+            thickness: reader.u32()?,
+            order: reader.f32()?,
+            reflection: reader.f32()?,
+        };
+
+        let source_count = reader.u32()?;
+        let sources = (0..source_count)
+            .map(|i| {
+                reader
+                    .source()
+                    .with_context(|| format!("invalid source {i}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let initial_condition = reader.initial_condition()?;
+
+        let cell_count = config.cell_count();
+
+        let speed_map = SpeedMap::from_values(&config, reader.f32s(cell_count)?)
+            .context("invalid speed map")?;
+
+        let walls = reader
+            .take(cell_count)?
+            .iter()
+            .map(|id| match id {
+                0 => Ok(None),
+                1 => Ok(Some(Wall::Clamped)),
+                2 => Ok(Some(Wall::Reflecting)),
+                _ => bail!("unknown obstacle {id}"),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let obstacles = ObstacleMask::from_cells(&config, &walls)?;
+
+        let time = reader.f32()?;
+        let active = reader.u32()?;
+
+        ensure!(active < 2, "invalid active texture {active}");
+
+        let mut field = || -> anyhow::Result<Vec<[f32; 2]>> {
+            Ok(bytemuck::cast_slice(&reader.f32s(2 * cell_count)?).to_vec())
+        };
+
+        let states = [field()?, field()?];
+        let auxiliary = [field()?, field()?];
+
+        ensure!(
+            reader.bytes.is_empty(),
+            "{} unexpected bytes at the end of the snapshot",
+            reader.bytes.len()
+        );
+
+        Ok(Self {
+            config,
+            parameters,
+            boundaries,
+            pml,
+            sources,
+            initial_condition,
+            speed_map,
+            obstacles,
+            time,
+            active,
+            states,
+            auxiliary,
+        })
+    }
+}
+
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.bytes.push(value as u8);
+    }
+
+    fn f32s(&mut self, values: &[f32]) {
+        values.iter().for_each(|value| self.f32(*value));
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn source(&mut self, source: &Source) {
+        self.u32(source.shape.gpu_id());
+
+        match source.shape {
+            SourceShape::Point { position } => self.f32s(&position),
+            SourceShape::Line { start, end } => self.f32s(&[start[0], start[1], end[0], end[1]]),
+            SourceShape::Ring { center, radius } => self.f32s(&[center[0], center[1], radius]),
+        }
+
+        self.u32(source.waveform.gpu_id());
+
+        if let Waveform::Expression(drive) = &source.waveform {
+            self.string(drive.text());
+        }
+
+        self.f32s(&[
+            source.frequency,
+            source.amplitude,
+            source.phase,
+            source.start_time,
+            source.end_time,
+        ]);
+        self.bool(source.enabled);
+    }
+
+    fn initial_condition(&mut self, condition: &InitialCondition) {
+        let kind = InitialConditionKind::ALL
+            .iter()
+            .position(|kind| *kind == condition.kind())
+            .unwrap();
+
+        self.u32(kind as u32);
+
+        match *condition {
+            InitialCondition::GaussianBump {
+                center,
+                radius,
+                amplitude,
+            } => self.f32s(&[center[0], center[1], radius, amplitude]),
+            InitialCondition::Ring {
+                center,
+                radius,
+                width,
+                amplitude,
+            } => self.f32s(&[center[0], center[1], radius, width, amplitude]),
+            InitialCondition::PlaneWave {
+                center,
+                direction,
+                wavelength,
+                length,
+                amplitude,
+            } => self.f32s(&[
+                center[0], center[1], direction, wavelength, length, amplitude,
+            ]),
+            InitialCondition::Eigenmode { m, n, amplitude } => {
+                self.u32(m);
+                self.u32(n);
+                self.f32(amplitude);
+            }
+            InitialCondition::Noise {
+                seed,
+                scale,
+                amplitude,
+            } => {
+                self.u32(seed);
+                self.f32s(&[scale, amplitude]);
+            }
+            InitialCondition::Expression { ref displacement } => self.string(displacement.text()),
+        }
+    }
+}
+
+impl Reader<'_> {
+    /// Consumes the next `length` bytes.
+    fn take(&mut self, length: usize) -> anyhow::Result<&[u8]> {
+        ensure!(self.bytes.len() >= length, "the snapshot is truncated");
+
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> anyhow::Result<f32> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn bool(&mut self) -> anyhow::Result<bool> {
+        match self.take(1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => bail!("invalid boolean {value}"),
+        }
+    }
+
+    fn f32s(&mut self, count: usize) -> anyhow::Result<Vec<f32>> {
+        let bytes = self.take(count.checked_mul(4).context("the snapshot is truncated")?)?;
+
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect())
+    }
+
+    fn f32_array<const N: usize>(&mut self) -> anyhow::Result<[f32; N]> {
+        Ok(self.f32s(N)?.try_into().unwrap())
+    }
+
+    fn string(&mut self) -> anyhow::Result<String> {
+        let length = self.u32()? as usize;
+
+        Ok(String::from_utf8(self.take(length)?.to_vec())?)
+    }
+
+    fn expression(&mut self) -> anyhow::Result<Expression> {
+        let text = self.string()?;
+
+        Expression::parse(&text).with_context(|| format!("invalid expression `{text}`"))
+    }
+
+    fn source(&mut self) -> anyhow::Result<Source> {
+        let shape = match self.u32()? {
+            0 => SourceShape::Point {
+                position: self.f32_array()?,
+            },
+            1 => SourceShape::Line {
+                start: self.f32_array()?,
+                end: self.f32_array()?,
+            },
+            2 => SourceShape::Ring {
+                center: self.f32_array()?,
+                radius: self.f32()?,
+            },
+            id => bail!("unknown source shape {id}"),
+        };
+
+        let id = self.u32()?;
+        let waveform = match Waveform::ALL
+            .into_iter()
+            .find(|waveform| waveform.gpu_id() == id)
+        {
+            Some(waveform) => waveform,
+            None if id == Waveform::EXPRESSION_ID => Waveform::Expression(self.expression()?),
+            None => bail!("unknown waveform {id}"),
+        };
+
+        let [frequency, amplitude, phase, start_time, end_time] = self.f32_array()?;
+
+        Ok(Source {
+            shape,
+            waveform,
+            frequency,
+            amplitude,
+            phase,
+            start_time,
+            end_time,
+            enabled: self.bool()?,
+        })
+    }
+
+    fn initial_condition(&mut self) -> anyhow::Result<InitialCondition> {
+        let index = self.u32()?;
+        let kind = InitialConditionKind::ALL
+            .get(index as usize)
+            .with_context(|| format!("unknown initial condition {index}"))?;
+
+        Ok(match kind {
+            InitialConditionKind::GaussianBump => InitialCondition::GaussianBump {
+                center: self.f32_array()?,
+                radius: self.f32()?,
+                amplitude: self.f32()?,
+            },
+            InitialConditionKind::Ring => InitialCondition::Ring {
+                center: self.f32_array()?,
+                radius: self.f32()?,
+                width: self.f32()?,
+                amplitude: self.f32()?,
+            },
+            InitialConditionKind::PlaneWave => InitialCondition::PlaneWave {
+                center: self.f32_array()?,
+                direction: self.f32()?,
+                wavelength: self.f32()?,
+                length: self.f32()?,
+                amplitude: self.f32()?,
+            },
+            InitialConditionKind::Eigenmode => InitialCondition::Eigenmode {
+                m: self.u32()?,
+                n: self.u32()?,
+                amplitude: self.f32()?,
+            },
+            InitialConditionKind::Noise => InitialCondition::Noise {
+                seed: self.u32()?,
+                scale: self.f32()?,
+                amplitude: self.f32()?,
+            },
+            InitialConditionKind::Expression => InitialCondition::Expression {
+                displacement: self.expression()?,
+            },
+        })
+    }
+}
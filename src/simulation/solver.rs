@@ -0,0 +1,269 @@
+use anyhow::Context;
+use wgpu::{CommandEncoderDescriptor, Device, PollType, Queue};
+
+use crate::{
+    renderer::pipelines::Pipelines,
+    simulation::{
+        Impulse, MAX_SUBSTEPS, WaveParameters, WaveSimulation,
+        boundary::{BoundaryConditions, PmlSettings},
+        config::SimulationConfig,
+        cpu::CpuSimulation,
+        initial::InitialCondition,
+        obstacles::ObstacleMask,
+        sources::Source,
+        speed_map::SpeedMap,
+        statistics::WaveStatistics,
+    },
+};
+
+/// A backend numerically solving the wave equation, allowing the same scenario to be run on
+/// either the CPU or the GPU.
+pub trait WaveSolver {
+    /// Returns the extent and resolution of the simulated domain.
+    fn config(&self) -> &SimulationConfig;
+
+    /// Returns the simulation time elapsed since the initial state.
+    fn time(&self) -> f32;
+
+    /// Returns the duration of a single tick that is actually run, after any subdivision.
+    fn tick_dt(&self) -> f32;
+
+    /// Returns the physical parameters of the simulated medium.
+    fn parameters_mut(&mut self) -> &mut WaveParameters;
+
+    /// Returns how waves behave when reaching each edge of the domain.
+    fn boundaries_mut(&mut self) -> &mut BoundaryConditions;
+
+    /// Returns the perfectly matched layer lining any edges with a
+    /// [`Boundary::Pml`](super::boundary::Boundary::Pml) condition.
+    fn pml_mut(&mut self) -> &mut PmlSettings;
+
+    /// Returns the emitters injecting waves into the domain.
+    fn sources_mut(&mut self) -> &mut Vec<Source>;
+
+    /// Replaces the wave speed of every cell, resampling the map onto the grid if needed.
+    fn set_speed_map(&mut self, speed_map: SpeedMap);
+
+    /// Replaces the solid obstacles inside the domain, resampling the mask onto the grid if needed.
+    fn set_obstacles(&mut self, obstacles: ObstacleMask);
+
+    /// Replaces the state the wave starts out from, resetting the simulation to it.
+    fn set_initial_condition(&mut self, initial_condition: InitialCondition);
+
+    /// Queues an [`Impulse`] to be added to the wave during the next tick.
+    fn poke(&mut self, impulse: Impulse);
+
+    /// Restores the initial state of the wave, rewinding the simulation time to zero.
+    fn reset(&mut self);
+
+    /// Advances the simulation by `substeps` ticks.
+    fn step(&mut self, substeps: u32);
+
+    /// Returns u(t) and u(t - dt) of every cell, stored row by row along the X axis.
+    fn state(&mut self) -> anyhow::Result<Vec<[f32; 2]>>;
+
+    /// Measures the [`WaveStatistics`] of the current state.
+    fn statistics(&mut self) -> anyhow::Result<WaveStatistics>;
+}
+
+/// Runs a [`WaveSimulation`] through the [`WaveSolver`] interface, submitting the work of every
+/// step to the GPU right away.
+pub struct GpuSolver<'a> {
+    /// The simulation being run.
+    pub simulation: &'a mut WaveSimulation,
+    /// The device the simulation was created on.
+    device: &'a Device,
+    /// The queue to submit the work of every step to.
+    queue: &'a Queue,
+    /// The pipelines the simulation was created with.
+    pipelines: &'a Pipelines,
+}
+
+impl<'a> GpuSolver<'a> {
+    /// Creates a new [`GpuSolver`] running `simulation` on the given device.
+    pub fn new(
+        simulation: &'a mut WaveSimulation,
+        device: &'a Device,
+        queue: &'a Queue,
+        pipelines: &'a Pipelines,
+    ) -> Self {
+        Self {
+            simulation,
+            device,
+            queue,
+            pipelines,
+        }
+    }
+}
+
+impl WaveSolver for GpuSolver<'_> {
+    fn config(&self) -> &SimulationConfig {
+        self.simulation.config()
+    }
+
+    fn time(&self) -> f32 {
+        self.simulation.time()
+    }
+
+    fn tick_dt(&self) -> f32 {
+        self.simulation.tick_dt()
+    }
+
+    fn parameters_mut(&mut self) -> &mut WaveParameters {
+        &mut self.simulation.parameters
+    }
+
+    fn boundaries_mut(&mut self) -> &mut BoundaryConditions {
+        &mut self.simulation.boundaries
+    }
+
+    fn pml_mut(&mut self) -> &mut PmlSettings {
+        &mut self.simulation.pml
+    }
+
+    fn sources_mut(&mut self) -> &mut Vec<Source> {
+        &mut self.simulation.sources
+    }
+
+    fn set_speed_map(&mut self, speed_map: SpeedMap) {
+        self.simulation.set_speed_map(self.queue, speed_map);
+    }
+
+    fn set_obstacles(&mut self, obstacles: ObstacleMask) {
+        self.simulation.set_obstacles(self.queue, obstacles);
+    }
+
+    fn set_initial_condition(&mut self, initial_condition: InitialCondition) {
+        self.simulation
+            .set_initial_condition(self.queue, initial_condition);
+    }
+
+    fn poke(&mut self, impulse: Impulse) {
+        self.simulation.poke(impulse);
+    }
+
+    fn reset(&mut self) {
+        self.simulation.reset(self.queue);
+    }
+
+    fn step(&mut self, mut substeps: u32) {
+        // the uniforms of at most `MAX_SUBSTEPS` ticks can be staged per submission
+        while substeps > 0 {
+            let batch = substeps.min(MAX_SUBSTEPS);
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&CommandEncoderDescriptor::default());
+
+            self.simulation
+                .step(self.device, self.queue, &mut encoder, self.pipelines, batch);
+            self.queue.submit([encoder.finish()]);
+
+            self.simulation.begin_probe_readback();
+            self.simulation.poll_probes(self.device);
+
+            substeps -= batch;
+        }
+    }
+
+    fn state(&mut self) -> anyhow::Result<Vec<[f32; 2]>> {
+        self.simulation.read_state(self.device, self.queue)
+    }
+
+    fn statistics(&mut self) -> anyhow::Result<WaveStatistics> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor::default());
+
+        self.simulation
+            .measure_statistics(self.queue, &mut encoder, self.pipelines);
+        self.queue.submit([encoder.finish()]);
+        self.simulation.begin_statistics_readback();
+
+        self.device.poll(PollType::wait_indefinitely())?;
+
+        self.simulation
+            .poll_statistics(self.device)
+            .context("failed to read back the simulation statistics")
+    }
+}
+
+impl WaveSolver for CpuSimulation {
+    fn config(&self) -> &SimulationConfig {
+        CpuSimulation::config(self)
+    }
+
+    fn time(&self) -> f32 {
+        CpuSimulation::time(self)
+    }
+
+    fn tick_dt(&self) -> f32 {
+        CpuSimulation::tick_dt(self)
+    }
+
+    fn parameters_mut(&mut self) -> &mut WaveParameters {
+        &mut self.parameters
+    }
+
+    fn boundaries_mut(&mut self) -> &mut BoundaryConditions {
+        &mut self.boundaries
+    }
+
+    fn pml_mut(&mut self) -> &mut PmlSettings {
+        &mut self.pml
+    }
+
+    fn sources_mut(&mut self) -> &mut Vec<Source> {
+        &mut self.sources
+    }
+
+    fn set_speed_map(&mut self, speed_map: SpeedMap) {
+        CpuSimulation::set_speed_map(self, speed_map);
+    }
+
+    fn set_obstacles(&mut self, obstacles: ObstacleMask) {
+        CpuSimulation::set_obstacles(self, obstacles);
+    }
+
+    fn set_initial_condition(&mut self, initial_condition: InitialCondition) {
+        CpuSimulation::set_initial_condition(self, initial_condition);
+    }
+
+    fn poke(&mut self, impulse: Impulse) {
+        CpuSimulation::poke(self, impulse);
+    }
+
+    fn reset(&mut self) {
+        CpuSimulation::reset(self);
+    }
+
+    fn step(&mut self, mut substeps: u32) {
+        // batched like the ticks of a `GpuSolver`, for impulses to be applied at the same ticks
+        while substeps > 0 {
+            let batch = substeps.min(MAX_SUBSTEPS);
+
+            CpuSimulation::step(self, batch);
+
+            substeps -= batch;
+        }
+    }
+
+    fn state(&mut self) -> anyhow::Result<Vec<[f32; 2]>> {
+        Ok(CpuSimulation::state(self).to_vec())
+    }
+
+    fn statistics(&mut self) -> anyhow::Result<WaveStatistics> {
+        Ok(CpuSimulation::statistics(self))
+    }
+}
+
+/// Returns the largest absolute difference of u(t) between two states of the same grid, such as
+/// those of a [`GpuSolver`] and a [`CpuSimulation`] run through the same scenario.
+pub fn max_difference(a: &[[f32; 2]], b: &[[f32; 2]]) -> f32 {
+    assert_eq!(a.len(), b.len(), "states must be of the same grid");
+
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a[0] - b[0]).abs())
+        .fold(0.0, f32::max)
+}
@@ -0,0 +1,312 @@
+use std::f32::consts::PI;
+
+use anyhow::Context;
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::expression::Expression;
+
+/// The most sources that can be active in a single simulation.
+pub const MAX_SOURCES: usize = 64;
+
+/// An emitter continuously injecting waves into the simulation as a forcing term.
+///
+/// In scenario files, the `phase` and `start_time` default to zero, the `end_time` to infinity and
+/// `enabled` to true.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Source {
+    /// The region over which the source injects waves.
+    pub shape: SourceShape,
+    /// How the strength of the source varies over time.
+    pub waveform: Waveform,
+
+    /// The (central) frequency of the waveform (in Hz).
+    pub frequency: f32,
+    /// The strength of the source, roughly matching the displacement it causes next to it.
+    pub amplitude: f32,
+    /// The phase offset of a [`Waveform::Sinusoid`] (in radians).
+    #[serde(default)]
+    pub phase: f32,
+
+    /// The simulation time the source turns on at.
+    #[serde(default)]
+    pub start_time: f32,
+    /// The simulation time the source turns off at, which may be infinite.
+    #[serde(default = "never")]
+    pub end_time: f32,
+    /// Whether the source is currently emitting at all.
+    #[serde(default = "always")]
+    pub enabled: bool,
+}
+
+/// The region over which a [`Source`] injects waves, in world space (x, z) coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SourceShape {
+    /// A single point, emitting circular waves.
+    Point {
+        /// The position of the point.
+        position: [f32; 2],
+    },
+    /// A line segment, emitting (nearly) plane waves.
+    Line {
+        /// The first end of the segment.
+        start: [f32; 2],
+        /// The second end of the segment.
+        end: [f32; 2],
+    },
+    /// A circle, emitting waves both inwards and outwards.
+    Ring {
+        /// The center of the circle.
+        center: [f32; 2],
+        /// The radius of the circle.
+        radius: f32,
+    },
+}
+
+/// How the strength of a [`Source`] varies over time.
+///
+/// In scenario files, the built-in waveforms are written by name and an expression as
+/// `{ expression = "..." }`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Waveform {
+    /// A continuous sine wave.
+    Sinusoid,
+    /// A single smooth bump, peaking one period after the source turns on.
+    GaussianPulse,
+    /// The second derivative of a gaussian (the "mexican hat" wavelet), peaking one period after
+    /// the source turns on.
+    Ricker,
+    /// A drive d(x, z, t) typed in by the user, of the world space position and the time since
+    /// the source turned on, which is compiled into `simulation.wgsl`.
+    ///
+    /// Like every other waveform, it is scaled by the amplitude and the square of the angular
+    /// frequency.
+    Expression(Expression),
+}
+
+/// The GPU representation of a [`Source`], matching `Source` in `simulation.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct GpuSource {
+    /// The point, line start or ring center of the shape.
+    pub origin: [f32; 2],
+    /// The line end of the shape, or the radius of a ring in the first component.
+    pub extent: [f32; 2],
+    /// The [`SourceShape::gpu_id`] of the shape.
+    pub shape: u32,
+    /// The [`Waveform::gpu_id`] of the waveform.
+    pub waveform: u32,
+    /// The (central) frequency of the waveform (in Hz).
+    pub frequency: f32,
+    /// The strength of the source.
+    pub amplitude: f32,
+    /// The phase offset of the waveform (in radians).
+    pub phase: f32,
+    /// The simulation time the source turns on at.
+    pub start_time: f32,
+    /// The simulation time the source turns off at.
+    pub end_time: f32,
+    /// Pads the struct to a multiple of 8 bytes.
+    pub _padding: f32,
+}
+
+impl Source {
+    /// Creates an always-on sinusoidal point source.
+    pub fn point(position: [f32; 2], frequency: f32, amplitude: f32) -> Self {
+        Self {
+            shape: SourceShape::Point { position },
+            waveform: Waveform::Sinusoid,
+            frequency,
+            amplitude,
+            phase: 0.0,
+            start_time: 0.0,
+            end_time: f32::INFINITY,
+            enabled: true,
+        }
+    }
+
+    /// Returns the strength of the source at the given simulation time, before any spatial
+    /// falloff is applied.
+    ///
+    /// Only a [`Waveform::Expression`] depends on the world space position (x, z).
+    ///
+    /// This mirrors `source_signal` in `simulation.wgsl`.
+    pub fn signal(&self, time: f32, x: f32, z: f32) -> f32 {
+        if !self.enabled || time < self.start_time || time >= self.end_time {
+            return 0.0;
+        }
+
+        let local_time = time - self.start_time;
+        let omega = 2.0 * PI * self.frequency;
+
+        // pulses are delayed by one period so they start out (nearly) at zero
+        let arg = PI * self.frequency * (local_time - 1.0 / self.frequency);
+
+        let signal = match &self.waveform {
+            Waveform::Sinusoid => (omega * local_time + self.phase).sin(),
+            Waveform::GaussianPulse => (-arg * arg).exp(),
+            Waveform::Ricker => (1.0 - 2.0 * arg * arg) * (-arg * arg).exp(),
+            Waveform::Expression(drive) => drive.eval(x, z, local_time),
+        };
+
+        // the response of the medium to a forcing term falls off with the square of its
+        // frequency, so this keeps the amplitude comparable across frequencies
+        self.amplitude * omega * omega * signal
+    }
+
+    /// Returns the GPU representation of the source.
+    pub fn to_gpu(&self) -> GpuSource {
+        let (origin, extent) = match self.shape {
+            SourceShape::Point { position } => (position, [0.0; 2]),
+            SourceShape::Line { start, end } => (start, end),
+            SourceShape::Ring { center, radius } => (center, [radius, 0.0]),
+        };
+
+        GpuSource {
+            origin,
+            extent,
+            shape: self.shape.gpu_id(),
+            waveform: self.waveform.gpu_id(),
+            frequency: self.frequency,
+            amplitude: if self.enabled { self.amplitude } else { 0.0 },
+            phase: self.phase,
+            start_time: self.start_time,
+            end_time: self.end_time.min(f32::MAX),
+            _padding: 0.0,
+        }
+    }
+}
+
+impl SourceShape {
+    /// Returns the distance from the world space position (x, z) to the shape.
+    ///
+    /// This mirrors `source_distance` in `simulation.wgsl`.
+    pub fn distance(&self, x: f32, z: f32) -> f32 {
+        match *self {
+            SourceShape::Point { position } => (x - position[0]).hypot(z - position[1]),
+            SourceShape::Line { start, end } => {
+                let (dx, dz) = (end[0] - start[0], end[1] - start[1]);
+                let length_squared = dx * dx + dz * dz;
+
+                let t = if length_squared > 0.0 {
+                    (((x - start[0]) * dx + (z - start[1]) * dz) / length_squared).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+
+                (x - start[0] - t * dx).hypot(z - start[1] - t * dz)
+            }
+            SourceShape::Ring { center, radius } => {
+                ((x - center[0]).hypot(z - center[1]) - radius).abs()
+            }
+        }
+    }
+
+    /// Returns the identifier of the shape used by `simulation.wgsl`.
+    pub fn gpu_id(&self) -> u32 {
+        match self {
+            SourceShape::Point { .. } => 0,
+            SourceShape::Line { .. } => 1,
+            SourceShape::Ring { .. } => 2,
+        }
+    }
+
+    /// Returns a human readable name of the kind of shape.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SourceShape::Point { .. } => "Point",
+            SourceShape::Line { .. } => "Line",
+            SourceShape::Ring { .. } => "Ring",
+        }
+    }
+}
+
+impl Waveform {
+    /// All built-in waveforms, in the order they are presented to the user.
+    pub const ALL: [Self; 3] = [Self::Sinusoid, Self::GaussianPulse, Self::Ricker];
+
+    /// The identifier of a [`Waveform::Expression`] used by `simulation.wgsl`.
+    pub const EXPRESSION_ID: u32 = 3;
+
+    /// The drive a [`Waveform::Expression`] starts out with when picked in the UI.
+    pub const DEFAULT_EXPRESSION: &str = "sin(2 * pi * t)";
+
+    /// Returns the identifier of the waveform used by `simulation.wgsl`.
+    pub fn gpu_id(&self) -> u32 {
+        match self {
+            Self::Sinusoid => 0,
+            Self::GaussianPulse => 1,
+            Self::Ricker => 2,
+            Self::Expression(_) => Self::EXPRESSION_ID,
+        }
+    }
+
+    /// Returns a human readable name of the waveform.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sinusoid => "Sinusoid",
+            Self::GaussianPulse => "Gaussian pulse",
+            Self::Ricker => "Ricker wavelet",
+            Self::Expression(_) => "Expression",
+        }
+    }
+}
+
+/// Generates the body of `expression_signal` in `simulation.wgsl`, switching over the index of
+/// each of the `sources` as uploaded to the GPU to evaluate its [`Waveform::Expression`].
+///
+/// Returns [`None`] if none of the sources is driven by an expression, in which case the shader
+/// can be used as it is, and fails if one of the expressions cannot be compiled for the GPU (see
+/// [`Expression::to_wgsl`](super::expression::Expression::to_wgsl)).
+pub fn expression_signal_wgsl<'a>(
+    sources: impl IntoIterator<Item = &'a Source>,
+) -> anyhow::Result<Option<String>> {
+    let cases = sources
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, source)| match &source.waveform {
+            Waveform::Expression(drive) => Some(
+                drive
+                    .to_wgsl()
+                    .with_context(|| format!("invalid drive of source {}", index + 1))
+                    .map(|drive| {
+                        format!(
+                            "        case {index}u: {{\n            return {drive};\n        }}\n"
+                        )
+                    }),
+            ),
+            _ => None,
+        })
+        .collect::<anyhow::Result<String>>()?;
+
+    if cases.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(format!(
+        "switch index {{\n{cases}        default: {{}}\n    }}"
+    )))
+}
+
+/// Returns the spatial falloff of a source at `distance` from its shape on a grid with the given
+/// spacing, spreading it over a couple of cells to avoid grid artifacts.
+///
+/// This mirrors `source_falloff` in `simulation.wgsl`.
+pub fn source_falloff(distance: f32, dx: f32) -> f32 {
+    let width = dx;
+
+    (-0.5 * (distance / width) * (distance / width)).exp()
+}
+
+/// The default `end_time` of a [`Source`] read from a scenario file, which never turns off.
+fn never() -> f32 {
+    f32::INFINITY
+}
+
+/// The default `enabled` of a [`Source`] read from a scenario file.
+fn always() -> bool {
+    true
+}
@@ -0,0 +1,118 @@
+use std::f32::consts::PI;
+
+/// Computes the one-sided amplitude spectrum of `samples` taken every `dt` seconds, as
+/// (frequency, amplitude) pairs from 0 Hz up to the Nyquist frequency.
+///
+/// The mean is removed and a Hann window applied before transforming, zero padding the samples to
+/// the next power of two. Amplitudes are scaled so a pure sinusoid peaks at its own amplitude.
+pub fn amplitude_spectrum(samples: &[f32], dt: f32) -> Vec<[f32; 2]> {
+    if samples.len() < 2 || dt <= 0.0 {
+        return Vec::new();
+    }
+
+    let length = samples.len();
+    let size = length.next_power_of_two();
+
+    let mean = samples.iter().sum::<f32>() / length as f32;
+    let window = |i: usize| 0.5 - 0.5 * (2.0 * PI * i as f32 / (length - 1) as f32).cos();
+    let window_sum = (0..length).map(window).sum::<f32>();
+
+    let mut buffer = samples
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| [(sample - mean) * window(i), 0.0])
+        .chain(std::iter::repeat([0.0; 2]))
+        .take(size)
+        .collect::<Vec<_>>();
+
+    fft(&mut buffer);
+
+    let frequency_step = 1.0 / (size as f32 * dt);
+
+    buffer[..=size / 2]
+        .iter()
+        .enumerate()
+        .map(|(k, [re, im])| [k as f32 * frequency_step, 2.0 * re.hypot(*im) / window_sum])
+        .collect()
+}
+
+/// Returns the frequency of the highest peak of an [`amplitude_spectrum`], ignoring the constant
+/// component, refined in between bins by fitting a parabola through the peak and its neighbours.
+pub fn peak_frequency(spectrum: &[[f32; 2]]) -> Option<f32> {
+    let (peak, _) = spectrum
+        .iter()
+        .enumerate()
+        .skip(1)
+        .max_by(|(_, a), (_, b)| a[1].total_cmp(&b[1]))?;
+
+    let [frequency, amplitude] = spectrum[peak];
+
+    let (Some([_, left]), Some([_, right])) = (spectrum.get(peak - 1), spectrum.get(peak + 1))
+    else {
+        return Some(frequency);
+    };
+
+    let curvature = left - 2.0 * amplitude + right;
+
+    if curvature >= 0.0 {
+        return Some(frequency);
+    }
+
+    let offset = 0.5 * (left - right) / curvature;
+    let frequency_step = spectrum[1][0] - spectrum[0][0];
+
+    Some(frequency + offset * frequency_step)
+}
+
+/// Computes the discrete Fourier transform of `buffer` in place, with the iterative radix-2
+/// Cooley-Tukey algorithm.
+///
+/// Complex numbers are stored as (real, imaginary) pairs, and the length of the buffer must be a
+/// power of two.
+pub fn fft(buffer: &mut [[f32; 2]]) {
+    let size = buffer.len();
+
+    assert!(
+        size.is_power_of_two(),
+        "the length of the buffer must be a power of two"
+    );
+
+    if size == 1 {
+        return;
+    }
+
+    // reorder the buffer so every butterfly combines two adjacent halves
+    let bits = size.trailing_zeros();
+
+    for i in 0..size {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+
+        if i < j {
+            buffer.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+
+    while length <= size {
+        let half = length / 2;
+
+        for j in 0..half {
+            let angle = -2.0 * PI * j as f32 / length as f32;
+            let [w_re, w_im] = [angle.cos(), angle.sin()];
+
+            for start in (0..size).step_by(length) {
+                let [a_re, a_im] = buffer[start + j];
+                let [b_re, b_im] = buffer[start + j + half];
+
+                let t_re = w_re * b_re - w_im * b_im;
+                let t_im = w_re * b_im + w_im * b_re;
+
+                buffer[start + j] = [a_re + t_re, a_im + t_im];
+                buffer[start + j + half] = [a_re - t_re, a_im - t_im];
+            }
+        }
+
+        length *= 2;
+    }
+}
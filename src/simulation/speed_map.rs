@@ -0,0 +1,366 @@
+use anyhow::{Context, bail, ensure};
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::{
+    config::SimulationConfig,
+    expression::{Expression, Variable},
+};
+
+/// The wave speed c(x, z) of every cell in the simulation grid, allowing for heterogeneous media.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedMap {
+    /// The number of cells across the X axis.
+    width: u32,
+    /// The number of cells across the Z axis.
+    depth: u32,
+    /// The wave speed of each cell, stored row by row along the X axis.
+    values: Vec<f32>,
+}
+
+/// Procedurally generated media modelling common optical and acoustic setups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeedMapPreset {
+    /// The same wave speed everywhere.
+    Uniform,
+    /// A circular region of slower medium in the middle of the domain, focusing plane waves.
+    Lens,
+    /// A slow channel running across the X axis, guiding waves by total internal reflection.
+    Waveguide,
+    /// A slanted step into a slower medium, like a shelf of shallow water, refracting waves.
+    Shelf,
+    /// A medium getting gradually faster away from the center line, continuously bending waves
+    /// back towards it.
+    GradientIndex,
+}
+
+impl SpeedMap {
+    /// Creates a [`SpeedMap`] with the same wave speed in every cell.
+    pub fn uniform(config: &SimulationConfig, speed: f32) -> Self {
+        Self::from_fn(config, |_, _| speed)
+    }
+
+    /// Creates a [`SpeedMap`] by evaluating `speed` at the world space position (x, z) of every
+    /// cell.
+    pub fn from_fn(config: &SimulationConfig, speed: impl Fn(f32, f32) -> f32) -> Self {
+        let (width, depth) = config.grid_size();
+
+        let values = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let (x, z) = config.cell_position(x, z);
+                speed(x, z)
+            })
+            .collect();
+
+        Self {
+            width,
+            depth,
+            values,
+        }
+    }
+
+    /// Creates a [`SpeedMap`] from the speed of every cell, stored row by row along the X axis.
+    pub fn from_values(config: &SimulationConfig, values: Vec<f32>) -> anyhow::Result<Self> {
+        let (width, depth) = config.grid_size();
+
+        ensure!(
+            values.len() == config.cell_count(),
+            "expected {} wave speeds for a {width}x{depth} grid, got {}",
+            config.cell_count(),
+            values.len()
+        );
+
+        if let Some(index) = values.iter().position(|c| !(c.is_finite() && *c > 0.0)) {
+            bail!(
+                "wave speed at cell ({}, {}) must be positive, got {}",
+                index as u32 % width,
+                index as u32 / width,
+                values[index]
+            );
+        }
+
+        Ok(Self {
+            width,
+            depth,
+            values,
+        })
+    }
+
+    /// Creates a [`SpeedMap`] from a greyscale image in the binary (P5) or plain (P2) PGM format,
+    /// stretched over the whole domain.
+    ///
+    /// Black pixels map to `min_speed` and white pixels to `max_speed`, with the first row of the
+    /// image lying at z = 0.
+    pub fn from_pgm(
+        config: &SimulationConfig,
+        bytes: &[u8],
+        min_speed: f32,
+        max_speed: f32,
+    ) -> anyhow::Result<Self> {
+        let image = GreyscaleImage::parse_pgm(bytes).context("failed to parse PGM image")?;
+        let (width, depth) = config.grid_size();
+
+        let values = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let u = (x as f32 + 0.5) / width as f32;
+                let v = (z as f32 + 0.5) / depth as f32;
+
+                min_speed + (max_speed - min_speed) * image.sample(u, v)
+            })
+            .collect();
+
+        Self::from_values(config, values)
+    }
+
+    /// Creates a [`SpeedMap`] by evaluating the expression c(x, z) at the world space position of
+    /// every cell.
+    ///
+    /// Fails if the expression depends on the time t, or isn't positive in every cell.
+    pub fn from_expression(
+        config: &SimulationConfig,
+        expression: &Expression,
+    ) -> anyhow::Result<Self> {
+        expression.ensure_variables(&[Variable::X, Variable::Z])?;
+
+        let (width, depth) = config.grid_size();
+
+        let values = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let (x, z) = config.cell_position(x, z);
+                expression.eval(x, z, 0.0)
+            })
+            .collect();
+
+        Self::from_values(config, values)
+    }
+
+    /// Generates the given [`SpeedMapPreset`], with `speed` being the speed of the surrounding
+    /// medium.
+    pub fn from_preset(config: &SimulationConfig, preset: SpeedMapPreset, speed: f32) -> Self {
+        let (center_x, center_z) = (config.width / 2.0, config.depth / 2.0);
+
+        match preset {
+            SpeedMapPreset::Uniform => Self::uniform(config, speed),
+
+            SpeedMapPreset::Lens => {
+                let radius = 0.2 * config.width.min(config.depth);
+
+                Self::from_fn(config, |x, z| {
+                    let distance = (x - center_x).hypot(z - center_z);
+
+                    if distance < radius {
+                        speed / 1.5
+                    } else {
+                        speed
+                    }
+                })
+            }
+
+            SpeedMapPreset::Waveguide => {
+                let half_width = 0.05 * config.depth;
+
+                Self::from_fn(config, |_, z| {
+                    if (z - center_z).abs() < half_width {
+                        speed * 0.6
+                    } else {
+                        speed
+                    }
+                })
+            }
+
+            SpeedMapPreset::Shelf => Self::from_fn(config, |x, z| {
+                if x > center_x + 0.3 * (z - center_z) {
+                    speed * 0.5
+                } else {
+                    speed
+                }
+            }),
+
+            SpeedMapPreset::GradientIndex => Self::from_fn(config, |_, z| {
+                let offset = (z - center_z) / center_z;
+
+                speed * (0.5 + 0.5 * offset * offset)
+            }),
+        }
+    }
+
+    /// Resamples the map onto a new grid by taking the nearest cell of the current map.
+    pub fn resample(&self, config: &SimulationConfig) -> Self {
+        let (width, depth) = config.grid_size();
+
+        let values = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let source_x = (x as u64 * self.width as u64 / width as u64) as u32;
+                let source_z = (z as u64 * self.depth as u64 / depth as u64) as u32;
+
+                self.speed_at(source_x, source_z)
+            })
+            .collect();
+
+        Self {
+            width,
+            depth,
+            values,
+        }
+    }
+
+    /// Returns the wave speed of the cell at the given grid coordinates.
+    pub fn speed_at(&self, x: u32, z: u32) -> f32 {
+        self.values[(z * self.width + x) as usize]
+    }
+
+    /// Returns the fastest wave speed of any cell, which limits the stable timestep.
+    pub fn max_speed(&self) -> f32 {
+        self.values.iter().copied().fold(0.0, f32::max)
+    }
+
+    /// Returns the number of cells across the X and Z axes respectively.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.depth)
+    }
+
+    /// Returns the wave speed of every cell, stored row by row along the X axis.
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+}
+
+impl SpeedMapPreset {
+    /// All presets, in the order they are presented to the user.
+    pub const ALL: [Self; 5] = [
+        Self::Uniform,
+        Self::Lens,
+        Self::Waveguide,
+        Self::Shelf,
+        Self::GradientIndex,
+    ];
+
+    /// Returns a human readable name of the preset.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Uniform => "Uniform",
+            Self::Lens => "Lens",
+            Self::Waveguide => "Waveguide",
+            Self::Shelf => "Shelf",
+            Self::GradientIndex => "Gradient index",
+        }
+    }
+}
+
+/// A greyscale image with pixel values normalized to [0, 1].
+struct GreyscaleImage {
+    /// The number of pixels in each row.
+    width: usize,
+    /// The number of rows.
+    height: usize,
+    /// The value of every pixel, stored row by row.
+    pixels: Vec<f32>,
+}
+
+impl GreyscaleImage {
+    /// Parses a binary (P5) or plain (P2) PGM image.
+    fn parse_pgm(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut cursor = 0;
+
+        let magic = next_header_token(bytes, &mut cursor).context("missing magic number")?;
+        let width = next_header_number(bytes, &mut cursor, "width")?;
+        let height = next_header_number(bytes, &mut cursor, "height")?;
+        let max_value = next_header_number(bytes, &mut cursor, "maximum value")?;
+
+        ensure!(width > 0 && height > 0, "image must not be empty");
+        ensure!(
+            (1..=u16::MAX as usize).contains(&max_value),
+            "invalid maximum value {max_value}"
+        );
+
+        let count = width * height;
+
+        let raw = match magic {
+            "P5" => {
+                // a single whitespace character separates the header from the raster
+                let raster = &bytes[(cursor + 1).min(bytes.len())..];
+
+                if max_value < 256 {
+                    ensure!(raster.len() >= count, "image data is truncated");
+
+                    raster[..count]
+                        .iter()
+                        .map(|&value| value as usize)
+                        .collect()
+                } else {
+                    ensure!(raster.len() >= count * 2, "image data is truncated");
+
+                    raster
+                        .chunks_exact(2)
+                        .take(count)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as usize)
+                        .collect()
+                }
+            }
+            "P2" => (0..count)
+                .map(|_| next_header_number(bytes, &mut cursor, "pixel value"))
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            _ => bail!("unsupported image format {magic:?}, expected a P2 or P5 PGM image"),
+        };
+
+        Ok(Self {
+            width,
+            height,
+            pixels: raw
+                .into_iter()
+                .map(|value| value.min(max_value) as f32 / max_value as f32)
+                .collect(),
+        })
+    }
+
+    /// Returns the value of the pixel nearest to the normalized coordinates (u, v).
+    fn sample(&self, u: f32, v: f32) -> f32 {
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// Returns the next whitespace separated token of a PGM header, skipping over comments.
+fn next_header_token<'a>(bytes: &'a [u8], cursor: &mut usize) -> Option<&'a str> {
+    loop {
+        while bytes.get(*cursor).is_some_and(u8::is_ascii_whitespace) {
+            *cursor += 1;
+        }
+
+        if bytes.get(*cursor) != Some(&b'#') {
+            break;
+        }
+
+        while bytes.get(*cursor).is_some_and(|&byte| byte != b'\n') {
+            *cursor += 1;
+        }
+    }
+
+    let start = *cursor;
+
+    while bytes
+        .get(*cursor)
+        .is_some_and(|byte| !byte.is_ascii_whitespace())
+    {
+        *cursor += 1;
+    }
+
+    (start != *cursor)
+        .then(|| std::str::from_utf8(&bytes[start..*cursor]).ok())
+        .flatten()
+}
+
+/// Parses the next token of a PGM header as a number.
+fn next_header_number(bytes: &[u8], cursor: &mut usize, name: &str) -> anyhow::Result<usize> {
+    let token = next_header_token(bytes, cursor).with_context(|| format!("missing {name}"))?;
+
+    token
+        .parse()
+        .with_context(|| format!("invalid {name} {token:?}"))
+}
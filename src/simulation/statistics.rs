@@ -0,0 +1,342 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::*;
+
+use crate::{
+    renderer::pipelines::Pipelines,
+    simulation::{WORKGROUP_SIZE, config::SimulationConfig},
+};
+
+/// Aggregate measures of the whole wave at a single point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WaveStatistics {
+    /// The simulation time the statistics were measured at.
+    pub time: f32,
+    /// The discrete kinetic energy ½·Σ ((u(t) - u(t - dt)) / dt)²·dx².
+    pub kinetic_energy: f32,
+    /// The discrete potential energy ½·Σ c²·∇u(t)·∇u(t - dt)·dx².
+    pub potential_energy: f32,
+    /// The smallest displacement u(t) of any cell.
+    pub min: f32,
+    /// The largest displacement u(t) of any cell.
+    pub max: f32,
+    /// The mean displacement u(t) over all cells.
+    pub mean: f32,
+    /// The root mean square displacement u(t) over all cells.
+    pub rms: f32,
+}
+
+/// The statistics of a subset of the cells, matching `Partial` in `statistics.wgsl`.
+///
+/// The GPU reduces the cells of each workgroup into one of these, which are then combined on the
+/// CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct StatisticsPartial {
+    /// The kinetic energy of the cells.
+    pub kinetic_energy: f32,
+    /// The potential energy of the cells.
+    pub potential_energy: f32,
+    /// The smallest u(t) of the cells.
+    pub min: f32,
+    /// The largest u(t) of the cells.
+    pub max: f32,
+    /// The sum of u(t) over the cells.
+    pub sum: f32,
+    /// The sum of u(t)² over the cells.
+    pub sum_of_squares: f32,
+}
+
+/// The GPU representation of the reduction's parameters, matching `StatisticsParameters` in
+/// `statistics.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct StatisticsUniforms {
+    /// The duration of the tick separating u(t) and u(t - dt).
+    pub dt: f32,
+    /// The distance between two adjacent cells of the grid (dx).
+    pub dx: f32,
+    pub _padding: [f32; 2],
+    /// The [`Boundary::gpu_id`](super::boundary::Boundary::gpu_id) of each edge, in the order
+    /// x_min, x_max, z_min, z_max.
+    pub boundaries: [u32; 4],
+}
+
+/// Measures the [`WaveStatistics`] of the simulation on the GPU, reading them back without
+/// stalling the frame.
+///
+/// A measurement is reduced to one [`StatisticsPartial`] per workgroup on the GPU, copied into a
+/// mappable buffer and combined on the CPU once the mapping has completed a few frames later.
+/// Only a single measurement is in flight at a time, so measurements requested in the meantime
+/// are skipped.
+pub struct StatisticsReduction {
+    /// The number of workgroups dispatched across the X and Z axes.
+    workgroups: (u32, u32),
+    /// The number of cells of the grid.
+    cell_count: usize,
+
+    /// The uniform buffer holding the [`StatisticsUniforms`].
+    uniforms_buffer: Buffer,
+    /// The storage buffer the [`StatisticsPartial`] of every workgroup is written to.
+    partials_buffer: Buffer,
+    /// The buffer the partials are copied into to be mapped and read by the CPU.
+    readback_buffer: Buffer,
+
+    /// The bind groups reading the simulation's texture 'a' and 'b' respectively.
+    bind_groups: [BindGroup; 2],
+
+    /// The progress of the measurement in flight.
+    readback: Readback,
+}
+
+/// The progress of a measurement from the GPU back to the CPU.
+enum Readback {
+    /// No measurement is in flight.
+    Idle,
+    /// A measurement of the state at `time` was recorded, but not yet submitted.
+    Recorded { time: f32 },
+    /// The readback buffer holding the measurement of the state at `time` is being mapped.
+    Mapping {
+        time: f32,
+        receiver: Receiver<Result<(), BufferAsyncError>>,
+    },
+}
+
+impl WaveStatistics {
+    /// Returns the total discrete energy of the wave.
+    ///
+    /// In a uniform medium without sources, impulses or absorbing edges, this is conserved
+    /// exactly by the leapfrog scheme, so any drift points at the integrator or the boundaries.
+    pub fn total_energy(&self) -> f32 {
+        self.kinetic_energy + self.potential_energy
+    }
+}
+
+impl StatisticsPartial {
+    /// The statistics of no cells at all.
+    pub const EMPTY: Self = Self {
+        kinetic_energy: 0.0,
+        potential_energy: 0.0,
+        min: f32::MAX,
+        max: f32::MIN,
+        sum: 0.0,
+        sum_of_squares: 0.0,
+    };
+
+    /// Merges the statistics of two disjoint sets of cells.
+    pub fn combine(self, other: Self) -> Self {
+        Self {
+            kinetic_energy: self.kinetic_energy + other.kinetic_energy,
+            potential_energy: self.potential_energy + other.potential_energy,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            sum: self.sum + other.sum,
+            sum_of_squares: self.sum_of_squares + other.sum_of_squares,
+        }
+    }
+
+    /// Turns the statistics of all `cell_count` cells of the grid into [`WaveStatistics`]
+    /// measured at `time`.
+    pub fn finish(self, cell_count: usize, time: f32) -> WaveStatistics {
+        let cell_count = cell_count as f32;
+
+        WaveStatistics {
+            time,
+            kinetic_energy: self.kinetic_energy,
+            potential_energy: self.potential_energy,
+            min: self.min,
+            max: self.max,
+            mean: self.sum / cell_count,
+            rms: (self.sum_of_squares / cell_count).sqrt(),
+        }
+    }
+}
+
+impl StatisticsReduction {
+    /// Creates all resources to measure the statistics of either of the simulation's `states`,
+    /// given the wave speed of every cell and the obstacle mask.
+    pub fn new(
+        device: &Device,
+        pipelines: &Pipelines,
+        config: &SimulationConfig,
+        states: [&Texture; 2],
+        speed_texture: &Texture,
+        obstacle_texture: &Texture,
+    ) -> Self {
+        let (width, depth) = config.grid_size();
+
+        let workgroups = (
+            width.div_ceil(WORKGROUP_SIZE),
+            depth.div_ceil(WORKGROUP_SIZE),
+        );
+        let partials_size =
+            (workgroups.0 * workgroups.1) as BufferAddress * size_of::<StatisticsPartial>() as u64;
+
+        let uniforms_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("StatisticsReduction::uniforms_buffer"),
+            size: size_of::<StatisticsUniforms>() as _,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let partials_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("StatisticsReduction::partials_buffer"),
+            size: partials_size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("StatisticsReduction::readback_buffer"),
+            size: partials_size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let speed_view = speed_texture.create_view(&TextureViewDescriptor::default());
+        let obstacle_view = obstacle_texture.create_view(&TextureViewDescriptor::default());
+
+        let bind_groups = states.map(|state| {
+            let state_view = state.create_view(&TextureViewDescriptor::default());
+
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("StatisticsReduction::bind_group"),
+                layout: &pipelines.statistics_bind_group_layout,
+                entries: &[
+                    // uniforms
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: uniforms_buffer.as_entire_binding(),
+                    },
+                    // wave state
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&state_view),
+                    },
+                    // wave speed map
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: BindingResource::TextureView(&speed_view),
+                    },
+                    // obstacle mask
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: BindingResource::TextureView(&obstacle_view),
+                    },
+                    // workgroup partials
+                    BindGroupEntry {
+                        binding: 4,
+                        resource: partials_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        Self {
+            workgroups,
+            cell_count: config.cell_count(),
+            uniforms_buffer,
+            partials_buffer,
+            readback_buffer,
+            bind_groups,
+            readback: Readback::Idle,
+        }
+    }
+
+    /// Records the reduction of the state held by texture 'a' (`state` 0) or 'b' (`state` 1),
+    /// unless a previous measurement is still in flight.
+    pub fn record(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        pipelines: &Pipelines,
+        state: usize,
+        uniforms: StatisticsUniforms,
+        time: f32,
+    ) {
+        if !matches!(self.readback, Readback::Idle) {
+            return;
+        }
+
+        queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("StatisticsReduction::record"),
+            timestamp_writes: None,
+        });
+
+        pass.set_pipeline(&pipelines.statistics_pipeline);
+        pass.set_bind_group(0, &self.bind_groups[state], &[]);
+        pass.dispatch_workgroups(self.workgroups.0, self.workgroups.1, 1);
+
+        drop(pass);
+
+        encoder.copy_buffer_to_buffer(
+            &self.partials_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            self.partials_buffer.size(),
+        );
+
+        self.readback = Readback::Recorded { time };
+    }
+
+    /// Starts mapping the readback buffer, once the encoder the measurement was recorded into
+    /// has been submitted.
+    pub fn begin_readback(&mut self) {
+        let Readback::Recorded { time } = self.readback else {
+            return;
+        };
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.readback_buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                // the receiver is gone if the reduction was recreated in the meantime
+                let _ = sender.send(result);
+            });
+
+        self.readback = Readback::Mapping { time, receiver };
+    }
+
+    /// Returns the measurement in flight if it has been read back, without blocking.
+    pub fn poll(&mut self, device: &Device) -> Option<WaveStatistics> {
+        let Readback::Mapping { time, receiver } = &self.readback else {
+            return None;
+        };
+
+        if let Err(error) = device.poll(PollType::Poll) {
+            log::warn!("failed to poll the device for statistics: {error}");
+        }
+
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(BufferAsyncError),
+        };
+
+        let time = *time;
+        self.readback = Readback::Idle;
+
+        if let Err(error) = result {
+            log::warn!("failed to read back the simulation statistics: {error}");
+            return None;
+        }
+
+        let statistics = {
+            let data = self.readback_buffer.slice(..).get_mapped_range();
+
+            bytemuck::cast_slice::<u8, StatisticsPartial>(&data)
+                .iter()
+                .copied()
+                .fold(StatisticsPartial::EMPTY, StatisticsPartial::combine)
+                .finish(self.cell_count, time)
+        };
+
+        self.readback_buffer.unmap();
+
+        Some(statistics)
+    }
+}
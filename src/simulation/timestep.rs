@@ -0,0 +1,126 @@
+use crate::simulation::MAX_SUBSTEPS;
+
+/// Decouples the simulation from the frame rate by converting the wall time of each frame into a
+/// whole number of ticks of a fixed physical timestep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedTimestep {
+    /// The most ticks run during a single frame (up to [`MAX_SUBSTEPS`]), after which the
+    /// simulation falls behind wall time instead of taking ever longer frames to catch up.
+    pub max_substeps: u32,
+    /// How many seconds of simulation time pass per second of wall time, allowing for slow motion
+    /// and fast forwarding.
+    pub speed: f32,
+    /// Whether the simulation only advances by explicitly requested ticks.
+    pub paused: bool,
+
+    /// The (scaled) wall time not yet covered by a simulation tick (in seconds).
+    accumulator: f32,
+    /// The ticks requested through [`FixedTimestep::step`] but not run yet.
+    pending_steps: u32,
+    /// The number of ticks run during the last frame.
+    last_substeps: u32,
+
+    /// The wall time accumulated since the ratio was last measured (in seconds).
+    wall_elapsed: f32,
+    /// The simulation time accumulated since the ratio was last measured.
+    simulation_elapsed: f32,
+    /// An estimate of how much simulation time passes per second of wall time, over the last
+    /// second.
+    time_ratio: f32,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+impl FixedTimestep {
+    /// The slowest allowed `speed`.
+    pub const MIN_SPEED: f32 = 1.0 / 16.0;
+    /// The fastest allowed `speed`.
+    pub const MAX_SPEED: f32 = 16.0;
+
+    /// Creates a new [`FixedTimestep`] running at most `max_substeps` ticks per frame.
+    pub fn new(max_substeps: u32) -> Self {
+        Self {
+            max_substeps,
+            speed: 1.0,
+            paused: false,
+            accumulator: 0.0,
+            pending_steps: 0,
+            last_substeps: 0,
+            wall_elapsed: 0.0,
+            simulation_elapsed: 0.0,
+            time_ratio: 0.0,
+        }
+    }
+
+    /// Accounts for a frame lasting `frame_time` seconds, returning how many ticks of `dt` the
+    /// simulation should advance by to keep up with it.
+    ///
+    /// While paused, only the ticks requested through [`FixedTimestep::step`] are run.
+    pub fn advance(&mut self, frame_time: f32, dt: f32) -> u32 {
+        let max_substeps = self.max_substeps.clamp(1, MAX_SUBSTEPS);
+
+        let substeps = if self.paused {
+            // large step requests are spread over several frames
+            let substeps = self.pending_steps.min(max_substeps);
+            self.pending_steps -= substeps;
+
+            substeps
+        } else {
+            self.pending_steps = 0;
+            self.accumulator += frame_time * self.speed;
+
+            let substeps = (self.accumulator / dt) as u32;
+
+            if substeps > max_substeps {
+                // drop the backlog rather than trying to catch up with it, which would only slow
+                // down the following frames even further
+                self.accumulator = 0.0;
+                max_substeps
+            } else {
+                self.accumulator -= substeps as f32 * dt;
+                substeps
+            }
+        };
+
+        self.last_substeps = substeps;
+
+        self.wall_elapsed += frame_time;
+        self.simulation_elapsed += substeps as f32 * dt;
+
+        if self.wall_elapsed > 1.0 {
+            self.time_ratio = self.simulation_elapsed / self.wall_elapsed;
+
+            self.wall_elapsed = 0.0;
+            self.simulation_elapsed = 0.0;
+        }
+
+        substeps
+    }
+
+    /// Pauses the simulation, then requests it to advance by `count` more ticks.
+    pub fn step(&mut self, count: u32) {
+        self.paused = true;
+        self.pending_steps += count;
+    }
+
+    /// Discards any wall time not yet covered by a simulation tick, and any requested ticks.
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+        self.pending_steps = 0;
+    }
+
+    /// Returns the number of ticks run during the last frame.
+    pub fn last_substeps(&self) -> u32 {
+        self.last_substeps
+    }
+
+    /// Returns an estimate of how much simulation time passes per second of wall time, where 1
+    /// means the simulation runs in real time.
+    pub fn time_ratio(&self) -> f32 {
+        self.time_ratio
+    }
+}
@@ -16,6 +16,12 @@ pub struct FrameTimer {
     pub fps: f32,
 }
 
+impl Default for FrameTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FrameTimer {
     /// Creates a new [`FrameTimer`].
     pub fn new() -> Self {
@@ -0,0 +1,100 @@
+//! Checks the command line of the headless runner, and that a run on the CPU fallback writes all
+//! requested outputs and can be continued from its final snapshot.
+
+use gpu_template::{
+    batch::{self, BatchOptions},
+    simulation::{
+        config::SimulationConfig, cpu::CpuSimulation, export::ExportFormat, snapshot::Snapshot,
+    },
+};
+
+/// Parses a command line given as a single string.
+fn parse(args: &str) -> anyhow::Result<BatchOptions> {
+    BatchOptions::parse(args.split_whitespace().map(String::from))
+}
+
+#[test]
+fn parses_the_command_line() {
+    let options = parse(
+        "--cells-per-unit 20 --ticks 50 --export out/wave --format vti --energy-density \
+         --interval 5 --metrics-interval 2 --cpu",
+    )
+    .unwrap();
+
+    assert_eq!(options.config.cells_per_unit, 20.0);
+    assert_eq!(options.ticks, Some(50));
+    assert_eq!(options.export, Some("out/wave".into()));
+    assert_eq!(options.format, ExportFormat::Vti);
+    assert!(options.fields.energy_density && !options.fields.velocity);
+    assert_eq!(options.interval, Some(5));
+    assert_eq!(options.metrics_interval, 2);
+    assert!(options.force_cpu);
+
+    assert_eq!(parse("").unwrap(), BatchOptions::default());
+}
+
+#[test]
+fn rejects_invalid_command_lines() {
+    assert!(parse("--bogus").is_err());
+    assert!(parse("--ticks").is_err());
+    assert!(parse("--ticks many").is_err());
+    assert!(parse("--format png").is_err());
+    assert!(parse("--interval 5").is_err());
+    assert!(parse("--export out --interval 0").is_err());
+    assert!(parse("--cells-per-unit 0").is_err());
+}
+
+#[test]
+fn cpu_snapshot_continues_identically() {
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 1.0,
+        cells_per_unit: 20.0,
+    };
+
+    let mut original = CpuSimulation::new(config);
+    original.step(13);
+
+    let mut restored = CpuSimulation::new(SimulationConfig {
+        cells_per_unit: 10.0,
+        ..config
+    });
+    restored.restore(Snapshot::from_bytes(&original.snapshot().to_bytes()).unwrap());
+
+    original.step(9);
+    restored.step(9);
+
+    assert_eq!(restored.time(), original.time());
+    assert_eq!(restored.state(), original.state());
+}
+
+#[test]
+fn writes_every_output_of_a_cpu_run() {
+    let directory = std::env::temp_dir().join(format!("wave-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let path = |name: &str| directory.join(name).to_string_lossy().into_owned();
+
+    let options = parse(&format!(
+        "--cpu --cells-per-unit 10 --ticks 12 --export {} --interval 5 --metrics {} \
+         --metrics-interval 4 --save-snapshot {}",
+        path("wave"),
+        path("metrics.csv"),
+        path("end.wavesnap"),
+    ))
+    .unwrap();
+
+    batch::run(&options).unwrap();
+
+    // frames at ticks 0, 5 and 10, and statistics at ticks 0, 4, 8 and 12
+    assert!(directory.join("wave_00002_u.npy").exists());
+    assert!(!directory.join("wave_00003_u.npy").exists());
+
+    let metrics = std::fs::read_to_string(directory.join("metrics.csv")).unwrap();
+    assert_eq!(metrics.lines().count(), 1 + 4);
+
+    let snapshot = Snapshot::from_bytes(&std::fs::read(directory.join("end.wavesnap")).unwrap());
+    assert_eq!(snapshot.unwrap().config.cells_per_unit, 10.0);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,139 @@
+//! Checks the layout of exported NumPy arrays and VTK image data, and the fields derived from a
+//! known state.
+
+use gpu_template::simulation::{
+    config::SimulationConfig,
+    export::{ExportFields, ExportFormat, ExportSeries, FieldExport},
+    speed_map::{SpeedMap, SpeedMapPreset},
+};
+
+/// Exports a linear ramp u = x moving at a constant velocity on a small grid.
+fn ramp_export(fields: ExportFields) -> FieldExport {
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 0.5,
+        cells_per_unit: 10.0,
+    };
+
+    let (width, _) = config.grid_size();
+    let dx = config.grid_spacing();
+
+    let state = (0..config.cell_count())
+        .map(|i| {
+            let u = (i % width as usize) as f32 * dx;
+            [u, u - 0.01]
+        })
+        .collect::<Vec<_>>();
+
+    let speed_map = SpeedMap::from_preset(&config, SpeedMapPreset::Uniform, 2.0);
+
+    FieldExport::from_state(&config, &state, &speed_map, 0.01, 0.5, fields)
+}
+
+#[test]
+fn npy_header_is_aligned_and_describes_the_grid() {
+    let export = ramp_export(ExportFields::default());
+    let (width, depth) = export.config.grid_size();
+
+    let bytes = export.to_npy(&export.fields[0].1);
+    let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let header = std::str::from_utf8(&bytes[10..10 + header_length]).unwrap();
+
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    assert_eq!((10 + header_length) % 64, 0);
+    assert!(header.ends_with('\n'));
+    assert!(header.contains(&format!("'shape': ({depth}, {width})")));
+    assert_eq!(
+        bytes.len(),
+        10 + header_length + 4 * (width * depth) as usize
+    );
+}
+
+#[test]
+fn derives_velocity_and_energy_density() {
+    let export = ramp_export(ExportFields {
+        velocity: true,
+        energy_density: true,
+    });
+
+    let names = export
+        .fields
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["u", "velocity", "energy_density"]);
+
+    // v = 1 and |∇u| = 1 everywhere, so the energy density is ½·1 + ½·2²·1
+    for velocity in &export.fields[1].1 {
+        assert!((velocity - 1.0).abs() < 1e-3, "velocity {velocity}");
+    }
+
+    for energy in &export.fields[2].1 {
+        assert!((energy - 2.5).abs() < 1e-2, "energy density {energy}");
+    }
+}
+
+#[test]
+fn vti_spans_the_grid_with_its_spacing() {
+    let export = ramp_export(ExportFields::default());
+    let (width, depth) = export.config.grid_size();
+
+    let vti = export.to_vti();
+
+    assert!(vti.contains(&format!(
+        r#"WholeExtent="0 {} 0 0 0 {}""#,
+        width - 1,
+        depth - 1
+    )));
+    assert!(vti.contains(r#"Spacing="0.1 0.1 0.1""#));
+    assert!(vti.contains(r#"Name="TIME""#));
+}
+
+#[test]
+fn series_writes_numbered_frames_every_interval() {
+    let directory = std::env::temp_dir().join(format!("wave-export-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let stem = directory.join("ramp");
+    let export = ramp_export(ExportFields::default());
+
+    let mut series = ExportSeries::new(stem.clone(), ExportFormat::Vti, ExportFields::default(), 5);
+
+    for _ in 0..3 {
+        assert!(series.is_due());
+        series.write_frame(&export).unwrap();
+
+        assert_eq!(series.ticks_until_next(), 5);
+        series.advance(4);
+        assert!(!series.is_due());
+        series.advance(1);
+    }
+
+    assert_eq!(series.frame_count(), 3);
+    assert!(directory.join("ramp_00002.vti").exists());
+
+    let collection = std::fs::read_to_string(directory.join("ramp.pvd")).unwrap();
+    assert_eq!(collection.matches("<DataSet").count(), 3);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn series_collection_escapes_file_names() {
+    let directory = std::env::temp_dir().join(format!("wave-escape-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let stem = directory.join(r#"a&b<"c'"#);
+    let mut series = ExportSeries::new(stem, ExportFormat::Vti, ExportFields::default(), 1);
+    series
+        .write_frame(&ramp_export(ExportFields::default()))
+        .unwrap();
+
+    let collection = std::fs::read_to_string(directory.join(r#"a&b<"c'.pvd"#)).unwrap();
+    assert!(
+        collection.contains(r#"file="a&amp;b&lt;&quot;c&apos;_00000.vti""#),
+        "{collection}"
+    );
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,289 @@
+//! Checks that expressions parse, type check and evaluate like their WGSL translation would, that
+//! mistakes are reported with their column, that drives the GPU cannot compile are rejected, and
+//! that expressions drive the simulation exactly like the built-in setups they spell out.
+
+use gpu_template::{
+    renderer::{
+        gpu_context::GpuContext,
+        pipelines::Pipelines,
+        shaders::{Preprocessor, ShaderFile, Shaders},
+    },
+    simulation::{
+        WaveSimulation,
+        config::SimulationConfig,
+        cpu::CpuSimulation,
+        expression::{Expression, Variable},
+        initial::InitialCondition,
+        snapshot::Snapshot,
+        sources::{Source, Waveform, expression_signal_wgsl},
+        speed_map::SpeedMap,
+    },
+};
+use wgpu::naga;
+
+/// Parses and evaluates `text` at the given position and time.
+fn eval(text: &str, x: f32, z: f32, t: f32) -> f32 {
+    Expression::parse(text).unwrap().eval(x, z, t)
+}
+
+/// Returns the error of parsing `text`, with its whole chain of causes.
+fn error_of(text: &str) -> String {
+    format!("{:#}", Expression::parse(text).unwrap_err())
+}
+
+#[test]
+fn evaluates_with_the_usual_precedence() {
+    assert_eq!(eval("1 + 2 * 3 - 4 / 2", 0.0, 0.0, 0.0), 5.0);
+    assert_eq!(eval("-2^2", 0.0, 0.0, 0.0), -4.0);
+    assert_eq!(eval("2^3^2", 0.0, 0.0, 0.0), 512.0);
+    assert_eq!(eval("2^-1", 0.0, 0.0, 0.0), 0.5);
+    assert_eq!(eval("-7 % 3", 0.0, 0.0, 0.0), -1.0);
+    assert_eq!(eval("(x - 1)^2 + z * t", 3.0, 2.0, 0.5), 5.0);
+    assert_eq!(eval("1.5e1 + .5", 0.0, 0.0, 0.0), 15.5);
+
+    let gaussian = "exp(-((x - 2.5)^2 + (z - 2.5)^2) / 0.05) * sin(10 * t)";
+    let expected = (-(0.1f32 * 0.1 + 0.2 * 0.2) / 0.05).exp() * (10.0f32 * 0.3).sin();
+    assert!((eval(gaussian, 2.6, 2.3, 0.3) - expected).abs() < 1e-6);
+}
+
+#[test]
+fn mirrors_wgsl_builtins() {
+    assert_eq!(eval("sign(0) + sign(-3)", 0.0, 0.0, 0.0), -1.0);
+    assert_eq!(eval("fract(-1.25)", 0.0, 0.0, 0.0), 0.75);
+    assert_eq!(eval("step(1, x)", 1.0, 0.0, 0.0), 1.0);
+    assert_eq!(eval("step(1, x)", 0.5, 0.0, 0.0), 0.0);
+    assert_eq!(eval("smoothstep(0, 2, x)", 1.0, 0.0, 0.0), 0.5);
+    assert_eq!(eval("clamp(x, 0, 1)", 3.0, 0.0, 0.0), 1.0);
+    assert!((eval("ln(e) + log(1)", 0.0, 0.0, 0.0) - 1.0).abs() < 1e-6);
+    assert_eq!(
+        eval("atan2(1, 0)", 0.0, 0.0, 0.0),
+        std::f32::consts::FRAC_PI_2
+    );
+
+    let branch = "if(x > 1 && !(z < 0) || t == 5, 1, 2)";
+    assert_eq!(eval(branch, 2.0, 1.0, 0.0), 1.0);
+    assert_eq!(eval(branch, 2.0, -1.0, 0.0), 2.0);
+    assert_eq!(eval(branch, 0.0, -1.0, 5.0), 1.0);
+}
+
+#[test]
+fn reports_mistakes_with_their_column() {
+    assert!(error_of("sin(x").contains("expected `,` or `)` at column 6"));
+    assert!(error_of("(x + 1").contains("expected `)` at column 7"));
+    assert!(error_of("1 + y").contains("unknown variable `y` at column 5"));
+    assert!(error_of("2 * foo(x)").contains("unknown function `foo` at column 5"));
+    assert!(error_of("x $ 2").contains("`$` at column 3"));
+    assert!(error_of("x = 2").contains("did you mean `==`"));
+    assert!(error_of("max(x)").contains("takes 2 arguments, got 1"));
+    assert!(error_of("1 < x < 2").contains("cannot be chained"));
+    assert!(error_of("2 (z)").contains("unexpected `(` at column 3"));
+
+    // booleans and numbers don't mix
+    assert!(error_of("x > 1").contains("must evaluate to a number"));
+    assert!(error_of("1 + (x > 2)").contains("`+` expects a number"));
+    assert!(error_of("if(x, 1, 2)").contains("`if` expects a boolean"));
+    assert!(error_of("!x").contains("`!` expects a boolean"));
+}
+
+#[test]
+fn checks_the_variables_of_each_use() {
+    let expression = Expression::parse("x + t").unwrap();
+
+    assert!(expression.uses(Variable::T) && !expression.uses(Variable::Z));
+    assert!(
+        expression
+            .ensure_variables(&[Variable::X, Variable::Z])
+            .is_err()
+    );
+
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 1.0,
+        cells_per_unit: 10.0,
+    };
+
+    assert!(SpeedMap::from_expression(&config, &expression).is_err());
+    assert!(SpeedMap::from_expression(&config, &Expression::parse("x - 0.5").unwrap()).is_err());
+
+    let speed_map =
+        SpeedMap::from_expression(&config, &Expression::parse("1 + x").unwrap()).unwrap();
+    let (x, _) = config.cell_position(3, 0);
+    assert_eq!(speed_map.speed_at(3, 0), 1.0 + x);
+}
+
+#[test]
+fn injected_drives_compile_into_the_simulation_shader() {
+    let drive = |text: &str| Source {
+        waveform: Waveform::Expression(Expression::parse(text).unwrap()),
+        ..Source::point([0.5, 0.5], 2.0, 0.1)
+    };
+
+    let sources = [
+        drive("exp(-((x - 2.5)^2 + (z - 2.5)^2) / 0.05) * sin(10 * t)"),
+        Source::point([0.5, 0.5], 2.0, 0.1),
+        drive("if(t < 1 || x >= z, pow(abs(x), 1.5), -1) + smoothstep(0, 1, fract(t)) % 2"),
+    ];
+
+    assert!(expression_signal_wgsl(&sources[1..2]).unwrap().is_none());
+
+    let signals = expression_signal_wgsl(&sources).unwrap().unwrap();
+    assert!(signals.contains("case 0u") && signals.contains("case 2u"));
+    assert!(!signals.contains("case 1u"));
+
+    let simulation = Shaders::preprocessor()
+        .process("simulation.wgsl", ShaderFile::Simulation.embedded_source())
+        .unwrap();
+    let source = Shaders::inject_expression_signals(&simulation.source, Some(&signals));
+    let module = naga::front::wgsl::parse_str(&source).unwrap();
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .unwrap();
+}
+
+#[test]
+fn rejects_drives_folding_into_non_finite_constants() {
+    for text in [
+        "1/0",
+        "exp(1000)",
+        "log(0) * t",
+        "sqrt(-1) + x",
+        "if(t > 1, 0 / 0, t)",
+    ] {
+        let error = format!(
+            "{:#}",
+            Expression::parse(text).unwrap().to_wgsl().unwrap_err()
+        );
+        assert!(error.contains("without any variable"), "{text}: {error}");
+    }
+
+    for text in ["1/x", "exp(1000 * t)", "log(t)", "sqrt(-x)"] {
+        assert!(Expression::parse(text).unwrap().to_wgsl().is_ok(), "{text}");
+    }
+
+    let sources = [
+        Source::point([0.5, 0.5], 2.0, 0.1),
+        Source {
+            waveform: Waveform::Expression(Expression::parse("1/0").unwrap()),
+            ..Source::point([0.5, 0.5], 2.0, 0.1)
+        },
+    ];
+
+    let error = format!("{:#}", expression_signal_wgsl(&sources).unwrap_err());
+    assert!(error.starts_with("invalid drive of source 2"), "{error}");
+
+    // a constant slipping past the check is still caught by validating the shader
+    let simulation = Shaders::preprocessor()
+        .process("simulation.wgsl", ShaderFile::Simulation.embedded_source())
+        .unwrap();
+    let source = Shaders::inject_expression_signals(&simulation.source, Some("return 1.0 / 0.0;"));
+
+    assert!(
+        Preprocessor::new()
+            .process("simulation.wgsl", &source)
+            .unwrap()
+            .validate()
+            .is_err()
+    );
+}
+
+#[test]
+fn failing_drives_keep_the_previous_pipeline() {
+    let Ok((device, queue)) = pollster::block_on(GpuContext::new_headless()) else {
+        eprintln!("skipping: no GPU able to run the simulation");
+        return;
+    };
+
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 1.0,
+        cells_per_unit: 16.0,
+    };
+
+    let shaders = Shaders::new(&device);
+    let pipelines = Pipelines::new(&device, &shaders);
+    let mut simulation = WaveSimulation::new(&device, &queue, &pipelines, config);
+
+    assert!(
+        pipelines
+            .create_simulation_pipeline(&device, "return 1.0 / 0.0;")
+            .is_err()
+    );
+
+    let step = |simulation: &mut WaveSimulation, text: &str| {
+        simulation.sources = vec![Source {
+            waveform: Waveform::Expression(Expression::parse(text).unwrap()),
+            ..Source::point([0.5, 0.5], 2.0, 0.1)
+        }];
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        simulation.step(&device, &queue, &mut encoder, &pipelines, 1);
+        queue.submit([encoder.finish()]);
+    };
+
+    step(&mut simulation, "sin(t)");
+    assert_eq!(simulation.expression_error(), None);
+
+    step(&mut simulation, "1/0");
+    assert!(simulation.expression_error().is_some());
+    assert!(simulation.read_state(&device, &queue).is_ok());
+
+    step(&mut simulation, "sin(t)");
+    assert_eq!(simulation.expression_error(), None);
+}
+
+#[test]
+fn expression_drive_matches_the_builtin_sinusoid() {
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 1.0,
+        cells_per_unit: 32.0,
+    };
+
+    let sinusoid = Source::point([0.4, 0.6], 2.0, 0.05);
+    let drive = Source {
+        waveform: Waveform::Expression(Expression::parse("sin(2 * pi * 2 * t)").unwrap()),
+        ..sinusoid.clone()
+    };
+
+    let mut builtin = CpuSimulation::new(config);
+    let mut expression = CpuSimulation::new(config);
+
+    builtin.sources.push(sinusoid);
+    expression.sources.push(drive);
+
+    builtin.step(25);
+    expression.step(25);
+
+    assert_eq!(builtin.state(), expression.state());
+}
+
+#[test]
+fn expression_initial_condition_sets_the_velocity_and_survives_snapshots() {
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 0.5,
+        cells_per_unit: 20.0,
+    };
+
+    let mut simulation = CpuSimulation::new(config);
+    simulation.set_initial_condition(InitialCondition::Expression {
+        displacement: Expression::parse("0.1 * cos(3 * x) * sin(5 * z - t)").unwrap(),
+    });
+
+    let dt = simulation.tick_dt();
+    let (x, z) = config.cell_position(4, 7);
+    let [u, u_previous] = simulation.state()[7 * 20 + 4];
+
+    assert_eq!(u, 0.1 * (3.0 * x).cos() * (5.0 * z).sin());
+    assert_eq!(u_previous, 0.1 * (3.0 * x).cos() * (5.0 * z + dt).sin());
+
+    let snapshot = simulation.snapshot();
+    assert_eq!(
+        Snapshot::from_bytes(&snapshot.to_bytes()).unwrap(),
+        snapshot
+    );
+}
@@ -0,0 +1,175 @@
+//! Checks that scenario files round trip through TOML, that invalid files are rejected with an
+//! error naming the offending field, and that the bundled scenarios load.
+
+use std::path::Path;
+
+use gpu_template::{
+    scenario::{CameraPose, Medium, Obstacles, Scenario, TimeStep},
+    simulation::{
+        boundary::{Boundary, BoundaryConditions, PmlSettings},
+        config::SimulationConfig,
+        cpu::CpuSimulation,
+        expression::Expression,
+        initial::InitialCondition,
+        obstacles::{ObstaclePreset, Wall},
+        sources::{Source, SourceShape, Waveform},
+        speed_map::SpeedMapPreset,
+    },
+};
+
+/// Builds a scenario setting every field.
+fn sample_scenario() -> Scenario {
+    Scenario {
+        ticks: Some(500),
+        domain: SimulationConfig {
+            width: 2.0,
+            depth: 1.0,
+            cells_per_unit: 40.0,
+        },
+        time: TimeStep {
+            dt: Some(0.005),
+            auto_subdivide: false,
+        },
+        boundaries: BoundaryConditions {
+            x_min: Boundary::Pml,
+            x_max: Boundary::Absorbing,
+            z_min: Boundary::Periodic,
+            z_max: Boundary::Periodic,
+        },
+        pml: PmlSettings {
+            thickness: 12,
+            order: 2.0,
+            reflection: 1e-3,
+        },
+        medium: Medium {
+            preset: Some(SpeedMapPreset::Lens),
+            image: None,
+            expression: None,
+            speed: 0.8,
+        },
+        obstacles: Obstacles {
+            preset: ObstaclePreset::SingleSlit,
+            wall: Wall::Reflecting,
+        },
+        initial: Some(InitialCondition::PlaneWave {
+            center: [0.5, 0.5],
+            direction: 0.3,
+            wavelength: 0.1,
+            length: 0.2,
+            amplitude: 0.4,
+        }),
+        sources: vec![
+            Source::point([1.5, 0.5], 3.0, 0.05),
+            Source {
+                shape: SourceShape::Ring {
+                    center: [1.0, 0.5],
+                    radius: 0.2,
+                },
+                waveform: Waveform::Ricker,
+                start_time: 0.5,
+                end_time: 2.0,
+                enabled: false,
+                ..Source::point([0.0; 2], 2.0, 0.1)
+            },
+            Source {
+                waveform: Waveform::Expression(
+                    Expression::parse("sin(10 * t) * if(x > 1, 1, 0)").unwrap(),
+                ),
+                ..Source::point([0.5, 0.5], 1.0, 0.02)
+            },
+        ],
+        camera: Some(CameraPose {
+            position: [1.0, 2.0, 3.0],
+            yaw: 10.0,
+            pitch: -45.0,
+            fov: 50.0,
+        }),
+    }
+}
+
+/// Returns the error of parsing `toml` as a scenario, with its whole chain of causes.
+fn error_of(toml: &str) -> String {
+    format!("{:#}", Scenario::from_toml(toml).unwrap_err())
+}
+
+#[test]
+fn round_trips_through_toml() {
+    let scenario = sample_scenario();
+    let toml = scenario.to_toml().unwrap();
+
+    assert_eq!(Scenario::from_toml(&toml).unwrap(), scenario);
+}
+
+#[test]
+fn fills_in_defaults() {
+    let scenario = Scenario::from_toml(
+        r#"
+        [domain]
+        width = 1.0
+        depth = 1.0
+        cells_per_unit = 20.0
+
+        [[sources]]
+        shape = { kind = "point", position = [0.5, 0.5] }
+        waveform = "gaussian_pulse"
+        frequency = 2.0
+        amplitude = 0.1
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(scenario.ticks, None);
+    assert_eq!(scenario.time, TimeStep::default());
+    assert_eq!(scenario.boundaries, BoundaryConditions::default());
+    assert_eq!(scenario.medium, Medium::default());
+    assert_eq!(scenario.sources[0].end_time, f32::INFINITY);
+    assert!(scenario.sources[0].enabled);
+
+    // the default setup matches a new simulation exactly
+    let snapshot = scenario.to_snapshot().unwrap();
+    let simulation = CpuSimulation::new(scenario.domain);
+
+    assert_eq!(snapshot.parameters, simulation.parameters);
+    assert_eq!(snapshot.states[0], simulation.state());
+}
+
+#[test]
+fn names_the_offending_field() {
+    let domain = "[domain]\nwidth = 1.0\ndepth = 1.0\ncells_per_unit = 20.0\n";
+
+    assert!(error_of("[domain]\nwidth = 1.0\n").contains("depth"));
+    assert!(error_of(&format!("{domain}[pml]\nthicknes = 3\n")).contains("thicknes"));
+    assert!(error_of(&format!("{domain}[boundaries]\nx_min = \"sticky\"\n")).contains("sticky"));
+    assert!(error_of(&domain.replace("width = 1.0", "width = -1.0")).contains("domain.width"));
+    assert!(error_of(&format!("{domain}[medium]\nspeed = 0.0\n")).contains("medium.speed"));
+    assert!(
+        error_of(&domain.replace("width = 1.0", "width = 1000.0"))
+            .contains("domain is too large: a grid of 20000x20 cells exceeds the GPU limit")
+    );
+
+    let source = error_of(&format!(
+        "{domain}[[sources]]\nshape = {{ kind = \"point\", position = [0.5, 0.5] }}\n\
+         waveform = \"sinusoid\"\nfrequency = 1.0\namplitude = 0.1\n\n\
+         [[sources]]\nshape = {{ kind = \"point\", position = [3.0, 0.5] }}\n\
+         waveform = \"sinusoid\"\nfrequency = 1.0\namplitude = 0.1\n"
+    ));
+    assert!(source.contains("sources[1]") && source.contains("shape.position"));
+
+    let drive = error_of(&format!(
+        "{domain}[[sources]]\nshape = {{ kind = \"point\", position = [0.5, 0.5] }}\n\
+         waveform = {{ expression = \"1 / 0\" }}\nfrequency = 1.0\namplitude = 0.1\n"
+    ));
+    assert!(drive.contains("sources[0]") && drive.contains("waveform.expression"));
+}
+
+#[test]
+fn loads_the_bundled_scenarios() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+
+    for entry in std::fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+
+        let scenario = Scenario::load(&path).unwrap();
+        scenario.to_snapshot().unwrap();
+    }
+}
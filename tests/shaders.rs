@@ -0,0 +1,193 @@
+//! Checks that the shaders shipped in `assets/` are valid, that the preprocessor expands its
+//! directives and reports mistakes with their line, that broken shaders are reported with the
+//! offending line of the file it came from, and that edited shader files are picked up for
+//! reloading.
+
+use std::time::Duration;
+
+use gpu_template::renderer::shaders::{Preprocessor, ShaderFile, ShaderWatcher, Shaders};
+
+/// Preprocesses `source` with a single include file and a single define injected from Rust.
+fn preprocess(source: &str) -> anyhow::Result<String> {
+    Preprocessor::new()
+        .include("common.wgsl", "const ONE: f32 = 1.0;\n#define FROM_INCLUDE")
+        .define("SIZE", "16u")
+        .process("shader.wgsl", source)
+        .map(|preprocessed| preprocessed.source)
+}
+
+/// Returns the error of preprocessing `source`, with its whole chain of causes.
+fn error_of(source: &str) -> String {
+    format!("{:#}", preprocess(source).unwrap_err())
+}
+
+#[test]
+fn embedded_shaders_are_valid() {
+    for file in ShaderFile::ALL {
+        let preprocessed = Shaders::preprocessor()
+            .process(file.file_name(), file.embedded_source())
+            .unwrap();
+
+        if let Err(error) = preprocessed.validate() {
+            panic!("{error:#}");
+        }
+    }
+}
+
+#[test]
+fn expands_includes_defines_and_conditionals() {
+    let source = "\
+#include \"common.wgsl\"
+#include \"common.wgsl\"
+#define HALF 8u
+@workgroup_size(SIZE, HALF) // SIZE stays in comments
+#ifdef FROM_INCLUDE
+let a = SIZE_2 + 1e5 + 2SIZE;
+#ifndef SIZE
+let b = 0;
+#else
+let c = HALF;
+#endif
+#else
+let d = 0;
+#endif";
+
+    assert_eq!(
+        preprocess(source).unwrap(),
+        "\
+const ONE: f32 = 1.0;
+
+
+
+@workgroup_size(16u, 8u) // SIZE stays in comments
+
+let a = SIZE_2 + 1e5 + 2SIZE;
+
+
+
+let c = 8u;
+
+
+
+
+"
+    );
+}
+
+#[test]
+fn leaves_block_comments_alone() {
+    let source = "\
+/* SIZE /* nested SIZE */ still SIZE
+#define SIZE 4u
+*/ let a = SIZE; /* SIZE */ let b = SIZE;
+#ifdef NOT_DEFINED
+/*
+#endif
+*/
+#endif
+let c = SIZE;";
+
+    assert_eq!(
+        preprocess(source).unwrap(),
+        "\
+/* SIZE /* nested SIZE */ still SIZE
+#define SIZE 4u
+*/ let a = 16u; /* SIZE */ let b = 16u;
+
+
+
+
+
+let c = 16u;
+"
+    );
+}
+
+#[test]
+fn locates_lines_across_includes() {
+    let preprocessed = Preprocessor::new()
+        .include("common.wgsl", "// first\n// second")
+        .process(
+            "shader.wgsl",
+            "// before\n#include \"common.wgsl\"\n// after",
+        )
+        .unwrap();
+
+    assert!(preprocessed.includes("common.wgsl"));
+    assert_eq!(preprocessed.locate(1), Some(("shader.wgsl", 1)));
+    assert_eq!(preprocessed.locate(3), Some(("common.wgsl", 2)));
+    assert_eq!(preprocessed.locate(4), Some(("shader.wgsl", 3)));
+    assert_eq!(preprocessed.locate(5), None);
+}
+
+#[test]
+fn reports_mistakes_with_their_line() {
+    assert!(error_of("\n#include \"missing.wgsl\"").contains("shader.wgsl:2: unknown include"));
+    assert!(error_of("#include common.wgsl").contains("expected a quoted file name"));
+    assert!(error_of("#define 1x").contains("shader.wgsl:1: expected a name"));
+    assert!(error_of("#ifdef SIZE\n\n").contains("shader.wgsl:1: `#ifdef` is never closed"));
+    assert!(error_of("#ifdef SIZE\n#else\n#else").contains("shader.wgsl:3: `#else` after"));
+    assert!(error_of("\n\n#endif").contains("shader.wgsl:3: `#endif` without"));
+    assert!(error_of("#pragma once").contains("unknown directive `#pragma`"));
+}
+
+#[test]
+fn errors_point_at_the_line_of_the_original_file() {
+    // the include at the top of the shader shifts the lines of the expanded source
+    let source = ShaderFile::Statistics
+        .embedded_source()
+        .replacen("fn main(", "fn main((", 1);
+    let line = source
+        .lines()
+        .position(|line| line.contains("fn main(("))
+        .unwrap()
+        + 1;
+
+    let preprocessed = Shaders::preprocessor()
+        .process("statistics.wgsl", &source)
+        .unwrap();
+    let error = format!("{:#}", preprocessed.validate().unwrap_err());
+    assert!(
+        error.contains(&format!("--> statistics.wgsl:{line}:")),
+        "{error}"
+    );
+
+    // type errors are caught by the validator rather than the parser
+    let preprocessed = Preprocessor::new()
+        .process(
+            "broken.wgsl",
+            "\n@compute @workgroup_size(1) fn main() { let x: f32 = 1.0; let y = x + 1u; }",
+        )
+        .unwrap();
+    let error = format!("{:#}", preprocessed.validate().unwrap_err());
+    assert!(error.contains("--> broken.wgsl:2:"), "{error}");
+    assert!(error.contains("Operation Add can't work"), "{error}");
+}
+
+#[test]
+fn watcher_picks_up_edited_files() {
+    let directory = std::env::temp_dir().join(format!("wave-shaders-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let path = directory.join(ShaderFile::Probes.file_name());
+    std::fs::write(&path, ShaderFile::Probes.embedded_source()).unwrap();
+
+    let mut watcher = ShaderWatcher::new(&directory);
+
+    // wait out both the poll interval and the resolution of modification times
+    std::thread::sleep(Duration::from_millis(1100));
+    assert!(watcher.poll().is_empty());
+
+    std::fs::write(&path, "// edited").unwrap();
+    std::thread::sleep(Duration::from_millis(600));
+
+    let changed = watcher.poll();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].0, ShaderFile::Probes.file_name());
+    assert_eq!(changed[0].1.as_ref().unwrap(), "// edited");
+
+    std::thread::sleep(Duration::from_millis(600));
+    assert!(watcher.poll().is_empty());
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,229 @@
+//! Checks that snapshots survive a trip through their binary format unchanged, that malformed
+//! files are rejected, that a restored run continues exactly like the original one, and that
+//! snapshots read back across frames match blocking ones.
+
+use gpu_template::{
+    renderer::{pipelines::Pipelines, shaders::Shaders},
+    simulation::{
+        WaveParameters, WaveSimulation,
+        boundary::{Boundary, BoundaryConditions, PmlSettings},
+        config::SimulationConfig,
+        expression::Expression,
+        initial::InitialCondition,
+        obstacles::{ObstacleMask, ObstaclePreset, Wall},
+        snapshot::{SNAPSHOT_VERSION, Snapshot},
+        sources::{Source, SourceShape, Waveform},
+        speed_map::{SpeedMap, SpeedMapPreset},
+    },
+};
+use wgpu::*;
+
+/// Creates a GPU device able to run the simulation, if the machine has one.
+fn gpu() -> Option<(Device, Queue)> {
+    let instance = Instance::new(&InstanceDescriptor::default());
+    let adapter =
+        pollster::block_on(instance.request_adapter(&RequestAdapterOptions::default())).ok()?;
+
+    let features = adapter.get_texture_format_features(TextureFormat::Rg32Float);
+    let supported = features
+        .allowed_usages
+        .contains(TextureUsages::STORAGE_BINDING)
+        && adapter.features().contains(Features::FLOAT32_FILTERABLE);
+
+    if !supported {
+        return None;
+    }
+
+    pollster::block_on(adapter.request_device(&DeviceDescriptor {
+        required_features: Features::FLOAT32_FILTERABLE,
+        ..Default::default()
+    }))
+    .ok()
+}
+
+/// Builds a snapshot exercising every kind of field, with a distinct value in every cell.
+fn sample_snapshot() -> Snapshot {
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 0.5,
+        cells_per_unit: 20.0,
+    };
+
+    let field = |offset: f32| {
+        (0..config.cell_count())
+            .map(|i| [i as f32 + offset, -(i as f32) * offset])
+            .collect::<Vec<_>>()
+    };
+
+    Snapshot {
+        config,
+        parameters: WaveParameters {
+            dt: 0.004,
+            auto_subdivide: false,
+        },
+        boundaries: BoundaryConditions {
+            x_min: Boundary::Periodic,
+            x_max: Boundary::Periodic,
+            z_min: Boundary::Absorbing,
+            z_max: Boundary::Pml,
+        },
+        pml: PmlSettings {
+            thickness: 7,
+            order: 2.5,
+            reflection: 1e-3,
+        },
+        sources: vec![
+            Source::point([0.2, 0.3], 2.0, 0.05),
+            Source {
+                shape: SourceShape::Line {
+                    start: [0.1, 0.1],
+                    end: [0.1, 0.4],
+                },
+                waveform: Waveform::Ricker,
+                end_time: f32::INFINITY,
+                enabled: false,
+                ..Source::point([0.0; 2], 3.0, 0.1)
+            },
+            Source {
+                shape: SourceShape::Ring {
+                    center: [0.5, 0.25],
+                    radius: 0.1,
+                },
+                waveform: Waveform::GaussianPulse,
+                ..Source::point([0.0; 2], 1.0, 0.2)
+            },
+            Source {
+                waveform: Waveform::Expression(Expression::parse("sin(2 * pi * t) * x").unwrap()),
+                ..Source::point([0.7, 0.2], 1.5, 0.1)
+            },
+        ],
+        initial_condition: InitialCondition::Eigenmode {
+            m: 2,
+            n: 3,
+            amplitude: 0.5,
+        },
+        speed_map: SpeedMap::from_preset(&config, SpeedMapPreset::Lens, 1.0),
+        obstacles: ObstacleMask::from_preset(&config, ObstaclePreset::DoubleSlit, Wall::Reflecting),
+        time: 1.25,
+        active: 1,
+        states: [field(0.5), field(1.5)],
+        auxiliary: [field(2.5), field(3.5)],
+    }
+}
+
+#[test]
+fn round_trips_through_bytes() {
+    let snapshot = sample_snapshot();
+    let decoded = Snapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+
+    assert_eq!(decoded, snapshot);
+}
+
+#[test]
+fn rejects_malformed_files() {
+    let bytes = sample_snapshot().to_bytes();
+
+    assert!(Snapshot::from_bytes(b"not a snapshot").is_err());
+    assert!(Snapshot::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+    let mut extended = bytes.clone();
+    extended.push(0);
+    assert!(Snapshot::from_bytes(&extended).is_err());
+
+    let mut future = bytes.clone();
+    future[8..12].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+    assert!(Snapshot::from_bytes(&future).is_err());
+}
+
+#[test]
+fn restored_run_continues_identically() {
+    let Some((device, queue)) = gpu() else {
+        eprintln!("skipping: no GPU able to run the simulation");
+        return;
+    };
+
+    let shaders = Shaders::new(&device);
+    let pipelines = Pipelines::new(&device, &shaders);
+
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 1.0,
+        cells_per_unit: 40.0,
+    };
+
+    let run = |simulation: &mut WaveSimulation, ticks: u32| {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+        simulation.step(&device, &queue, &mut encoder, &pipelines, ticks);
+        queue.submit([encoder.finish()]);
+    };
+
+    let mut original = WaveSimulation::new(&device, &queue, &pipelines, config);
+    original.boundaries = BoundaryConditions::uniform(Boundary::Pml);
+    original.sources.push(Source::point([0.3, 0.6], 4.0, 0.05));
+
+    run(&mut original, 37);
+
+    let bytes = original.snapshot(&device, &queue).unwrap().to_bytes();
+
+    let mut restored = WaveSimulation::new(
+        &device,
+        &queue,
+        &pipelines,
+        SimulationConfig {
+            cells_per_unit: 10.0,
+            ..config
+        },
+    );
+    restored.restore(
+        &device,
+        &queue,
+        &pipelines,
+        Snapshot::from_bytes(&bytes).unwrap(),
+    );
+
+    run(&mut original, 21);
+    run(&mut restored, 21);
+
+    assert_eq!(restored.time(), original.time());
+    assert_eq!(
+        restored.read_state(&device, &queue).unwrap(),
+        original.read_state(&device, &queue).unwrap()
+    );
+}
+
+#[test]
+fn pending_snapshot_matches_a_blocking_one() {
+    let Some((device, queue)) = gpu() else {
+        eprintln!("skipping: no GPU able to run the simulation");
+        return;
+    };
+
+    let shaders = Shaders::new(&device);
+    let pipelines = Pipelines::new(&device, &shaders);
+
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 0.5,
+        cells_per_unit: 30.0,
+    };
+
+    let mut simulation = WaveSimulation::new(&device, &queue, &pipelines, config);
+    simulation.boundaries = BoundaryConditions::uniform(Boundary::Pml);
+    simulation
+        .sources
+        .push(Source::point([0.5, 0.25], 4.0, 0.05));
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+    simulation.step(&device, &queue, &mut encoder, &pipelines, 19);
+    queue.submit([encoder.finish()]);
+
+    let mut pending = simulation.begin_snapshot(&device, &queue);
+
+    let snapshot = loop {
+        if let Some(snapshot) = pending.poll(&device) {
+            break snapshot.unwrap();
+        }
+    };
+
+    assert_eq!(snapshot, simulation.snapshot(&device, &queue).unwrap());
+}
@@ -0,0 +1,91 @@
+//! Checks that the GPU simulation and the CPU reference solver stay in agreement when run through
+//! the same scenario.
+
+use gpu_template::{
+    renderer::{gpu_context::GpuContext, pipelines::Pipelines, shaders::Shaders},
+    simulation::{
+        Impulse, WaveSimulation,
+        boundary::{Boundary, BoundaryConditions},
+        config::SimulationConfig,
+        cpu::CpuSimulation,
+        initial::InitialCondition,
+        solver::{GpuSolver, WaveSolver, max_difference},
+        sources::Source,
+    },
+};
+
+/// The largest difference of u(t) accepted between the two solvers, which only differ by the
+/// rounding of their floating point operations and the precision of the GPU's `sin`.
+const TOLERANCE: f32 = 1e-3;
+
+/// Sets up a scenario exercising the initial condition, sources, impulses and every kind of
+/// boundary on `solver`.
+fn set_up(solver: &mut dyn WaveSolver) {
+    *solver.boundaries_mut() = BoundaryConditions {
+        x_min: Boundary::Fixed,
+        x_max: Boundary::Free,
+        z_min: Boundary::Absorbing,
+        z_max: Boundary::Pml,
+    };
+    solver
+        .sources_mut()
+        .push(Source::point([0.7, 0.3], 3.0, 0.05));
+    solver.set_initial_condition(InitialCondition::GaussianBump {
+        center: [0.4, 0.5],
+        radius: 0.1,
+        amplitude: 1.0,
+    });
+    solver.poke(Impulse {
+        position: [0.2, 0.8],
+        radius: 0.05,
+        amplitude: 0.5,
+    });
+}
+
+#[test]
+fn gpu_matches_the_cpu_reference() {
+    let Ok((device, queue)) = pollster::block_on(GpuContext::new_headless()) else {
+        eprintln!("skipping: no GPU able to run the simulation");
+        return;
+    };
+
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 1.0,
+        cells_per_unit: 32.0,
+    };
+
+    let shaders = Shaders::new(&device);
+    let pipelines = Pipelines::new(&device, &shaders);
+    let mut simulation = WaveSimulation::new(&device, &queue, &pipelines, config);
+
+    let mut gpu = GpuSolver::new(&mut simulation, &device, &queue, &pipelines);
+    let mut cpu_simulation = CpuSimulation::new(config);
+    let cpu: &mut dyn WaveSolver = &mut cpu_simulation;
+
+    set_up(&mut gpu);
+    set_up(cpu);
+
+    // more ticks than a single step runs at once, with an impulse queued between the steps
+    for _ in 0..3 {
+        gpu.step(100);
+        cpu.step(100);
+
+        let difference = max_difference(&gpu.state().unwrap(), &cpu.state().unwrap());
+        assert!(
+            difference < TOLERANCE,
+            "the solvers differ by {difference} at t = {}",
+            gpu.time()
+        );
+
+        let impulse = Impulse {
+            position: [0.6, 0.6],
+            radius: 0.05,
+            amplitude: 0.2,
+        };
+        gpu.poke(impulse);
+        cpu.poke(impulse);
+    }
+
+    assert_eq!(gpu.time(), cpu.time());
+}
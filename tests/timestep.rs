@@ -0,0 +1,82 @@
+//! Checks that the fixed timestep turns the wall time of frames into whole ticks, carrying the
+//! remainder over, capping and dropping backlogs, running requested steps while paused and
+//! measuring how fast the simulation runs.
+//!
+//! Every duration is a multiple of a power of two, so the arithmetic is exact.
+
+use gpu_template::simulation::timestep::FixedTimestep;
+
+/// The duration of a tick used throughout the tests (in seconds).
+const DT: f32 = 0.25;
+
+#[test]
+fn carries_the_remainder_over_to_the_next_frames() {
+    let mut timestep = FixedTimestep::new(16);
+
+    assert_eq!(timestep.advance(0.375, DT), 1);
+    assert_eq!(timestep.advance(0.375, DT), 2);
+    assert_eq!(timestep.advance(0.125, DT), 0);
+    assert_eq!(timestep.advance(0.125, DT), 1);
+    assert_eq!(timestep.last_substeps(), 1);
+}
+
+#[test]
+fn drops_the_backlog_beyond_max_substeps() {
+    let mut timestep = FixedTimestep::new(4);
+
+    assert_eq!(timestep.advance(2.0, DT), 4);
+    assert_eq!(timestep.advance(0.25, DT), 1);
+}
+
+#[test]
+fn runs_only_the_requested_steps_while_paused() {
+    let mut timestep = FixedTimestep::new(4);
+
+    timestep.step(10);
+    assert!(timestep.paused);
+
+    // the wall time is ignored, and the requested ticks are spread over the frames
+    assert_eq!(timestep.advance(1.0, DT), 4);
+    assert_eq!(timestep.advance(0.0, DT), 4);
+    assert_eq!(timestep.advance(1.0, DT), 2);
+    assert_eq!(timestep.advance(1.0, DT), 0);
+
+    // requests left over when resuming are discarded
+    timestep.step(10);
+    timestep.advance(1.0, DT);
+    timestep.paused = false;
+    assert_eq!(timestep.advance(0.25, DT), 1);
+}
+
+#[test]
+fn scales_the_wall_time_by_the_speed() {
+    let mut timestep = FixedTimestep::new(16);
+
+    timestep.speed = 2.0;
+    assert_eq!(timestep.advance(0.5, DT), 4);
+
+    timestep.speed = 0.5;
+    assert_eq!(timestep.advance(0.5, DT), 1);
+}
+
+#[test]
+fn measures_the_time_ratio_over_each_second() {
+    let mut timestep = FixedTimestep::new(16);
+    timestep.speed = 2.0;
+
+    timestep.advance(0.5, DT);
+    timestep.advance(0.5, DT);
+    assert_eq!(timestep.time_ratio(), 0.0);
+
+    timestep.advance(0.5, DT);
+    assert_eq!(timestep.time_ratio(), 2.0);
+
+    // a capped simulation falls behind the requested speed
+    let mut capped = FixedTimestep::new(1);
+
+    for _ in 0..3 {
+        capped.advance(0.5, DT);
+    }
+
+    assert_eq!(capped.time_ratio(), 0.5);
+}
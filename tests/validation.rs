@@ -0,0 +1,330 @@
+//! Validates the solver against closed-form solutions of the wave equation, checking both the
+//! size of the error and that it shrinks at the expected (second) order as the grid is refined,
+//! and checks that the discrete energy is conserved in closed domains and drained by absorbing
+//! edges.
+//!
+//! The GPU backend is used whenever an adapter supporting the simulation is available, and the
+//! CPU reference solver otherwise.
+
+use std::f32::consts::PI;
+
+use gpu_template::{
+    renderer::{pipelines::Pipelines, shaders::Shaders},
+    simulation::{
+        WaveSimulation,
+        boundary::{Boundary, BoundaryConditions},
+        config::SimulationConfig,
+        cpu::CpuSimulation,
+        initial::InitialCondition,
+        solver::{GpuSolver, WaveSolver},
+    },
+};
+use wgpu::*;
+
+/// The number of cells per world unit of the grids each test is run on, from coarsest to finest.
+const RESOLUTIONS: [f32; 3] = [20.0, 40.0, 80.0];
+
+/// The smallest convergence order accepted between two successive refinements, slightly below
+/// the theoretical order of 2 of the leapfrog scheme.
+const MIN_CONVERGENCE_ORDER: f32 = 1.8;
+
+/// The outcome of running a scenario on a single grid.
+struct Run {
+    /// The grid the scenario was run on.
+    config: SimulationConfig,
+    /// u(t) and u(t - dt) of every cell at the end of the run.
+    state: Vec<[f32; 2]>,
+    /// The simulation time at the end of the run.
+    time: f32,
+}
+
+impl Run {
+    /// Returns the L2 norm of the error against `exact`, relative to the L2 norm of `exact` at
+    /// t = 0, which stays meaningful while a standing wave passes through zero.
+    fn relative_error(&self, exact: impl Fn(f32, f32, f32) -> f32) -> f32 {
+        let (width, depth) = self.config.grid_size();
+
+        let (error, norm) = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .zip(&self.state)
+            .fold((0.0, 0.0), |(error, norm), ((x, z), [u, _])| {
+                let (x, z) = self.config.cell_position(x, z);
+                let expected = exact(x, z, self.time);
+                let initial = exact(x, z, 0.0);
+
+                (
+                    error + (u - expected) * (u - expected),
+                    norm + initial * initial,
+                )
+            });
+
+        (error / norm).sqrt()
+    }
+}
+
+/// Creates a GPU device able to run the simulation, if the machine has one.
+fn gpu() -> Option<(Device, Queue)> {
+    let instance = Instance::new(&InstanceDescriptor::default());
+    let adapter =
+        pollster::block_on(instance.request_adapter(&RequestAdapterOptions::default())).ok()?;
+
+    let features = adapter.get_texture_format_features(TextureFormat::Rg32Float);
+    let supported = features
+        .allowed_usages
+        .contains(TextureUsages::STORAGE_BINDING)
+        && adapter.features().contains(Features::FLOAT32_FILTERABLE);
+
+    if !supported {
+        return None;
+    }
+
+    pollster::block_on(adapter.request_device(&DeviceDescriptor {
+        required_features: Features::FLOAT32_FILTERABLE,
+        ..Default::default()
+    }))
+    .ok()
+}
+
+/// Runs `run` on a fresh solver for the given grid, on the GPU if the machine has one able to
+/// run the simulation and on the CPU otherwise.
+fn with_solver<R>(config: SimulationConfig, run: impl FnOnce(&mut dyn WaveSolver) -> R) -> R {
+    match gpu() {
+        Some((device, queue)) => {
+            let shaders = Shaders::new(&device);
+            let pipelines = Pipelines::new(&device, &shaders);
+
+            let mut simulation = WaveSimulation::new(&device, &queue, &pipelines, config);
+            run(&mut GpuSolver::new(
+                &mut simulation,
+                &device,
+                &queue,
+                &pipelines,
+            ))
+        }
+        None => run(&mut CpuSimulation::new(config)),
+    }
+}
+
+/// Runs `initial_condition` for `duration` seconds on the given grid, with the same boundary
+/// condition on every edge.
+fn simulate(
+    config: SimulationConfig,
+    boundary: Boundary,
+    initial_condition: InitialCondition,
+    duration: f32,
+) -> Run {
+    with_solver(config, |solver| {
+        *solver.boundaries_mut() = BoundaryConditions::uniform(boundary);
+        solver.set_initial_condition(initial_condition);
+
+        let ticks = (duration / solver.tick_dt()).round() as u32;
+        solver.step(ticks);
+
+        Run {
+            config,
+            state: solver.state().unwrap(),
+            time: solver.time(),
+        }
+    })
+}
+
+/// Runs a gaussian bump in the middle of a unit square for `duration` seconds with the same
+/// boundary condition on every edge, returning the total energy measured every few ticks.
+fn energy_history(boundary: Boundary, duration: f32) -> Vec<f32> {
+    let config = SimulationConfig {
+        width: 1.0,
+        depth: 1.0,
+        cells_per_unit: 40.0,
+    };
+
+    with_solver(config, |solver| {
+        *solver.boundaries_mut() = BoundaryConditions::uniform(boundary);
+        solver.set_initial_condition(InitialCondition::GaussianBump {
+            center: [0.5, 0.5],
+            radius: 0.1,
+            amplitude: 1.0,
+        });
+
+        let measure = |solver: &mut dyn WaveSolver| solver.statistics().unwrap().total_energy();
+        let mut energies = vec![measure(solver)];
+
+        while solver.time() < duration {
+            solver.step(10);
+            energies.push(measure(solver));
+        }
+
+        energies
+    })
+}
+
+/// Runs the scenario built by `scenario` on every resolution, asserting that the finest error
+/// lies below `tolerance` and that the error converges at second order.
+fn assert_converges(
+    tolerance: f32,
+    scenario: impl Fn(f32) -> Run,
+    exact: impl Fn(&SimulationConfig, f32, f32, f32) -> f32,
+) {
+    let errors = RESOLUTIONS
+        .map(|cells_per_unit| {
+            let run = scenario(cells_per_unit);
+            run.relative_error(|x, z, t| exact(&run.config, x, z, t))
+        })
+        .to_vec();
+
+    for pair in errors.windows(2) {
+        let order = (pair[0] / pair[1]).log2();
+
+        assert!(
+            order >= MIN_CONVERGENCE_ORDER,
+            "expected second order convergence, observed order {order:.2} (errors {errors:?})"
+        );
+    }
+
+    let finest = *errors.last().unwrap();
+
+    assert!(
+        finest < tolerance,
+        "error {finest:.2e} on the finest grid exceeds {tolerance:.2e} (errors {errors:?})"
+    );
+}
+
+#[test]
+fn rectangular_membrane_standing_mode() {
+    let (m, n, amplitude) = (2, 3, 1.0);
+
+    // fixed edges hold the ghost cells just outside of the grid at zero, so the membrane spans
+    // one cell further than the grid on each side
+    let membrane = |config: &SimulationConfig| {
+        let dx = config.grid_spacing();
+        let (width, depth) = config.grid_size();
+
+        ((width + 1) as f32 * dx, (depth + 1) as f32 * dx, dx)
+    };
+
+    assert_converges(
+        5e-3,
+        |cells_per_unit| {
+            let config = SimulationConfig {
+                width: 1.0,
+                depth: 1.0,
+                cells_per_unit,
+            };
+
+            simulate(
+                config,
+                Boundary::Fixed,
+                InitialCondition::Eigenmode { m, n, amplitude },
+                0.7,
+            )
+        },
+        |config, x, z, t| {
+            let (length_x, length_z, dx) = membrane(config);
+
+            let k_x = m as f32 * PI / length_x;
+            let k_z = n as f32 * PI / length_z;
+
+            amplitude * (k_x * (x + dx)).sin() * (k_z * (z + dx)).sin() * (k_x.hypot(k_z) * t).cos()
+        },
+    );
+}
+
+#[test]
+fn one_dimensional_dalembert_pulse() {
+    let (start, length, amplitude) = (0.5, 0.1, 1.0);
+
+    // the domain is periodic across Z, so nothing varies along it and the wave is effectively 1D
+    assert_converges(
+        1e-2,
+        |cells_per_unit| {
+            let config = SimulationConfig {
+                width: 2.0,
+                depth: 0.1,
+                cells_per_unit,
+            };
+
+            // an infinite wavelength leaves a plain gaussian pulse travelling towards +X
+            simulate(
+                config,
+                Boundary::Periodic,
+                InitialCondition::PlaneWave {
+                    center: [start, 0.0],
+                    direction: 0.0,
+                    wavelength: f32::INFINITY,
+                    length,
+                    amplitude,
+                },
+                0.8,
+            )
+        },
+        |_, x, _, t| {
+            let offset = (x - start - t) / length;
+
+            amplitude * (-0.5 * offset * offset).exp()
+        },
+    );
+}
+
+#[test]
+fn periodic_plane_wave() {
+    let amplitude = 1.0;
+
+    // a single period across X and two across Z, so the wave wraps around seamlessly
+    let (k_x, k_z) = (2.0 * PI, 4.0 * PI);
+
+    assert_converges(
+        1e-2,
+        |cells_per_unit| {
+            let config = SimulationConfig {
+                width: 1.0,
+                depth: 1.0,
+                cells_per_unit,
+            };
+
+            // an infinitely long packet leaves a plain sinusoid
+            simulate(
+                config,
+                Boundary::Periodic,
+                InitialCondition::PlaneWave {
+                    center: [0.0, 0.0],
+                    direction: k_z.atan2(k_x),
+                    wavelength: 2.0 * PI / k_x.hypot(k_z),
+                    length: f32::INFINITY,
+                    amplitude,
+                },
+                0.5,
+            )
+        },
+        |_, x, z, t| amplitude * (k_x * x + k_z * z - k_x.hypot(k_z) * t).cos(),
+    );
+}
+
+#[test]
+fn closed_domain_conserves_energy() {
+    for boundary in [Boundary::Fixed, Boundary::Free, Boundary::Periodic] {
+        let energies = energy_history(boundary, 3.0);
+        let initial = energies[0];
+
+        let drift = energies
+            .iter()
+            .map(|energy| ((energy - initial) / initial).abs())
+            .fold(0.0, f32::max);
+
+        assert!(
+            drift < 1e-4,
+            "energy drifted by {drift:.2e} with {boundary:?} edges"
+        );
+    }
+}
+
+#[test]
+fn absorbing_edges_drain_energy() {
+    for boundary in [Boundary::Absorbing, Boundary::Pml] {
+        let energies = energy_history(boundary, 3.0);
+        let remaining = energies.last().unwrap() / energies[0];
+
+        assert!(
+            remaining < 1e-2,
+            "{remaining:.2e} of the energy remains with {boundary:?} edges"
+        );
+    }
+}